@@ -3,6 +3,7 @@ pub(super) mod actor;
 mod animation;
 mod asset_handles;
 pub(super) mod asset_metadata;
+pub(super) mod chat;
 pub(super) mod city;
 pub(super) mod cli;
 mod collision_groups;
@@ -21,9 +22,11 @@ pub(super) mod lot;
 mod navigation;
 pub(super) mod network;
 pub(super) mod object;
+pub(super) mod player;
 mod player_camera;
 pub(super) mod ready_scene;
 mod reflect_bundle;
+mod rejection;
 pub(super) mod settings;
 pub(super) mod task;
 pub(super) mod wall;
@@ -34,6 +37,7 @@ use action::ActionPlugin;
 use actor::ActorPlugin;
 use animation::AnimationPlugin;
 use asset_metadata::AssetMetadataPlugin;
+use chat::ChatPlugin;
 use city::CityPlugin;
 use cli::CliPlugin;
 use cursor_hover::CursorHoverPlugin;
@@ -48,6 +52,7 @@ use lot::LotPlugin;
 use navigation::NavigationPlugin;
 use network::NetworkPlugin;
 use object::ObjectPlugin;
+use player::PlayerPlugin;
 use player_camera::PlayerCameraPlugin;
 use ready_scene::ReadyScenePlugin;
 use settings::SettingsPlugin;
@@ -63,6 +68,7 @@ impl PluginGroup for CorePlugins {
             .add(TaskPlugin)
             .add(GameStatePlugin)
             .add(GameWorldPlugin)
+            .add(ChatPlugin)
             .add(CityPlugin)
             .add(CliPlugin)
             .add(CursorHoverPlugin)
@@ -80,6 +86,40 @@ impl PluginGroup for CorePlugins {
             .add(ReadyScenePlugin)
             .add(SettingsPlugin)
             .add(ObjectPlugin)
+            .add(PlayerPlugin)
+            .add(WallPlugin)
+            .add(AssetMetadataPlugin) // Should run after registering components.
+    }
+}
+
+/// Simulation part of [`CorePlugins`] for a dedicated server.
+///
+/// Excludes plugins that only serve the local player, such as cameras and cursor hovering.
+pub(super) struct ServerPlugins;
+
+impl PluginGroup for ServerPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(NetworkPlugin)
+            .add(TaskPlugin)
+            .add(GameStatePlugin)
+            .add(GameWorldPlugin)
+            .add(ChatPlugin)
+            .add(CityPlugin)
+            .add(CliPlugin)
+            .add(ActorPlugin)
+            .add(AnimationPlugin)
+            .add(LotPlugin)
+            .add(NavigationPlugin)
+            .add(GroundPlugin)
+            .add(ActionPlugin)
+            .add(ErrorPlugin)
+            .add(FamilyPlugin)
+            .add(GamePathsPlugin)
+            .add(ReadyScenePlugin)
+            .add(SettingsPlugin)
+            .add(ObjectPlugin)
+            .add(PlayerPlugin)
             .add(WallPlugin)
             .add(AssetMetadataPlugin) // Should run after registering components.
     }
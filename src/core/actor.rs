@@ -1,8 +1,12 @@
+pub(crate) mod autonomy;
 mod friendly;
 pub(super) mod movement;
 pub(crate) mod needs;
+pub(super) mod object_interaction;
 pub(crate) mod race;
 
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy_mod_outline::OutlineBundle;
 use bevy_rapier3d::prelude::*;
@@ -18,12 +22,15 @@ use super::{
     family::ActorFamily,
     game_state::GameState,
     game_world::WorldName,
+    network::interpolation::{AppInterpolationExt, Interpolation},
     ready_scene::ReadyScene,
 };
 use crate::core::{collision_groups::LifescapeGroupsExt, cursor_hover::Hoverable};
+use autonomy::AutonomyPlugin;
 use friendly::FriendlyPlugins;
 use movement::MovementPlugin;
 use needs::NeedsPlugin;
+use object_interaction::ObjectInteractionPlugin;
 use race::RacePlugins;
 
 pub(super) struct ActorPlugin;
@@ -31,12 +38,23 @@ pub(super) struct ActorPlugin;
 impl Plugin for ActorPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<AssetHandles<ActorAnimation>>()
-            .add_plugins((RacePlugins, FriendlyPlugins, MovementPlugin, NeedsPlugin))
+            .add_plugins((
+                RacePlugins,
+                AutonomyPlugin,
+                FriendlyPlugins,
+                MovementPlugin,
+                NeedsPlugin,
+                ObjectInteractionPlugin,
+            ))
             .replicate::<Actor>()
             .replicate::<FirstName>()
             .replicate::<Sex>()
             .replicate::<LastName>()
             .not_replicate_if_present::<Name, FirstName>()
+            .interpolate_transform::<Actor>(Interpolation {
+                delay: Duration::from_millis(100),
+                teleport_distance: 2.0,
+            })
             .add_systems(OnExit(GameState::Family), Self::deactivation_system)
             .add_systems(
                 Update,
@@ -0,0 +1,262 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{ecs::entity::EntityMap, prelude::*, time::common_conditions::on_timer};
+use bevy_replicon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    needs::{Need, NeedEffect, NeedKind},
+    Actor,
+};
+use crate::core::{
+    city::City,
+    family::{ActorFamily, Family},
+    game_world::recording::AppRecordingExt,
+    player::Ownership,
+    task::{self, Task, TaskOrder, TaskPriority, TaskState},
+};
+
+/// Queues tasks for idle actors of families with [`FreeWill`].
+///
+/// Tasks are chosen from [`Advertisement`]s by how much they would restore low needs.
+pub(super) struct AutonomyPlugin;
+
+impl Plugin for AutonomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.replicate::<FreeWill>()
+            .add_event::<Advertisement>()
+            .add_mapped_client_event::<FreeWillToggle>(SendPolicy::Unordered)
+            .record_mapped_client_event::<FreeWillToggle>()
+            .configure_set(
+                Update,
+                AdvertisementSet
+                    .run_if(has_authority())
+                    .run_if(on_timer(AUTONOMY_INTERVAL)),
+            )
+            .add_systems(
+                Update,
+                (
+                    Self::toggle_system,
+                    Self::selection_system.after(AdvertisementSet),
+                )
+                    .run_if(has_authority()),
+            );
+    }
+}
+
+impl AutonomyPlugin {
+    fn toggle_system(
+        mut commands: Commands,
+        mut toggle_events: EventReader<FromClient<FreeWillToggle>>,
+        ownership: Ownership,
+        families: Query<(), With<Family>>,
+    ) {
+        for FromClient { client_id, event } in &mut toggle_events {
+            if !ownership.can_control(*client_id, event.family) {
+                error!(
+                    "client {client_id} is not allowed to change free will of {:?}",
+                    event.family
+                );
+            } else if families.get(event.family).is_ok() {
+                commands
+                    .entity(event.family)
+                    .insert(FreeWill(event.enabled));
+            } else {
+                error!("entity {:?} is not a family", event.family);
+            }
+        }
+    }
+
+    fn selection_system(
+        mut commands: Commands,
+        mut advertisements: EventReader<Advertisement>,
+        families: Query<&FreeWill>,
+        actors: Query<(Entity, &ActorFamily, &GlobalTransform, &Children), With<Actor>>,
+        tasks: Query<(), With<TaskState>>,
+        needs: Query<(&NeedKind, &Need)>,
+        sources: Query<&GlobalTransform>,
+        parents: Query<&Parent>,
+        cities: Query<(), With<City>>,
+    ) {
+        let advertisements: Vec<_> = advertisements
+            .iter()
+            .filter_map(|advertisement| {
+                let source_transform = sources.get(advertisement.source).ok()?;
+                let city_entity = find_city(advertisement.source, &parents, &cities)?;
+                Some((advertisement, source_transform.translation(), city_entity))
+            })
+            .collect();
+        if advertisements.is_empty() {
+            return;
+        }
+
+        for (actor_entity, family, transform, children) in &actors {
+            if !families
+                .get(family.0)
+                .map_or(false, |free_will| free_will.0)
+            {
+                continue;
+            }
+            if tasks.iter_many(children).next().is_some() {
+                continue;
+            }
+            let Some(actor_city) = find_city(actor_entity, &parents, &cities) else {
+                continue;
+            };
+
+            let needs: HashMap<_, _> = needs
+                .iter_many(children)
+                .map(|(&kind, need)| (kind, need.0))
+                .collect();
+            let best = advertisements
+                .iter()
+                .filter(|(advertisement, _, city_entity)| {
+                    advertisement.source != actor_entity && *city_entity == actor_city
+                })
+                .filter_map(|&(advertisement, translation, _)| {
+                    let distance = translation.distance(transform.translation());
+                    let score = score(&needs, &advertisement.task.need_effects(), distance);
+                    (score > 0.0).then_some((advertisement, score))
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+            if let Some((advertisement, _)) = best {
+                debug!(
+                    "queueing {:?} for idle actor {actor_entity:?}",
+                    advertisement.task
+                );
+                // Player tasks should be activated first.
+                task::queue_task(
+                    &mut commands,
+                    actor_entity,
+                    &*advertisement.task,
+                    TaskPriority::Low,
+                    TaskOrder::default(),
+                );
+            }
+        }
+    }
+}
+
+const AUTONOMY_INTERVAL: Duration = Duration::from_secs(2);
+/// Needs above this value are not considered during task selection.
+const LOW_NEED: f32 = 70.0;
+/// Score reduction per distance unit to prefer closer tasks.
+const DISTANCE_PENALTY: f32 = 0.1;
+/// Tasks further than this distance are not considered.
+const MAX_DISTANCE: f32 = 50.0;
+
+/// Returns the city to which the entity belongs.
+fn find_city(
+    entity: Entity,
+    parents: &Query<&Parent>,
+    cities: &Query<(), With<City>>,
+) -> Option<Entity> {
+    let mut current_entity = entity;
+    loop {
+        if cities.get(current_entity).is_ok() {
+            return Some(current_entity);
+        }
+        current_entity = **parents.get(current_entity).ok()?;
+    }
+}
+
+/// Returns how much the effects would restore low needs, reduced by the distance to the task.
+///
+/// Tasks beyond [`MAX_DISTANCE`] always score zero.
+fn score(needs: &HashMap<NeedKind, f32>, effects: &[NeedEffect], distance: f32) -> f32 {
+    if distance > MAX_DISTANCE {
+        return 0.0;
+    }
+
+    let restored: f32 = effects
+        .iter()
+        .filter_map(|effect| {
+            let &value = needs.get(&effect.kind)?;
+            if value >= LOW_NEED {
+                return None;
+            }
+
+            // Lower needs are more urgent.
+            let urgency = (LOW_NEED - value) / LOW_NEED;
+            Some(effect.delta.min(100.0 - value) * urgency)
+        })
+        .sum();
+
+    restored / (1.0 + distance * DISTANCE_PENALTY)
+}
+
+/// Systems that send [`Advertisement`] events.
+///
+/// Runs periodically on server.
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct AdvertisementSet;
+
+/// A task that idle actors could perform autonomously.
+#[derive(Event)]
+pub(crate) struct Advertisement {
+    /// Entity that offers the task, used to calculate the distance.
+    ///
+    /// Actors don't consider tasks offered by themselves.
+    pub(crate) source: Entity,
+    pub(crate) task: Box<dyn Task>,
+}
+
+/// Allows actors of the family to choose tasks on their own.
+#[derive(Clone, Component, Copy, Debug, Deserialize, Reflect, Serialize)]
+#[reflect(Component)]
+pub(crate) struct FreeWill(pub(crate) bool);
+
+impl Default for FreeWill {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// An event of changing [`FreeWill`] of a family.
+///
+/// Emitted by players.
+#[derive(Debug, Deserialize, Event, Serialize)]
+pub(crate) struct FreeWillToggle {
+    pub(crate) family: Entity,
+    pub(crate) enabled: bool,
+}
+
+impl MapEventEntities for FreeWillToggle {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapError> {
+        self.family = entity_map.get(self.family).ok_or(MapError(self.family))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoring() {
+        let effects = [NeedEffect {
+            kind: NeedKind::Social,
+            delta: 30.0,
+        }];
+
+        let satisfied = HashMap::from([(NeedKind::Social, 90.0)]);
+        assert_eq!(score(&satisfied, &effects, 0.0), 0.0);
+
+        let low = HashMap::from([(NeedKind::Social, 50.0)]);
+        let critical = HashMap::from([(NeedKind::Social, 10.0)]);
+        assert!(score(&critical, &effects, 0.0) > score(&low, &effects, 0.0));
+        assert!(
+            score(&low, &effects, 0.0) > score(&low, &effects, 10.0),
+            "closer tasks should be preferred"
+        );
+        assert_eq!(
+            score(&critical, &effects, MAX_DISTANCE + 1.0),
+            0.0,
+            "out of range tasks should be rejected"
+        );
+
+        let other_need = HashMap::from([(NeedKind::Hunger, 10.0)]);
+        assert_eq!(score(&other_need, &effects, 0.0), 0.0);
+    }
+}
@@ -1,3 +1,4 @@
+mod interaction;
 mod tell_secret;
 
 use bevy::{app::PluginGroupBuilder, prelude::*};
@@ -0,0 +1,390 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+
+use crate::core::{
+    actor::{
+        movement::{Movement, MovementBundle},
+        ActorAnimation,
+    },
+    animation::AnimationEnded,
+    asset_handles::AssetHandles,
+    game_world::WorldName,
+    navigation::{following::Following, Navigation, NoPath},
+    task::{self, Task, TaskFinished, TaskGroups, TaskOrder, TaskOutcome, TaskState},
+};
+
+/// Performs interactions of type `T` between the initiator and participants.
+///
+/// The initiator walks to the first participant, after which [`Interaction::Participant`] tasks are queued for all participants.
+/// Animations start when everyone joined and the interaction ends with the initiator animation.
+/// Cancellation of any side cancels the whole interaction.
+pub(super) struct InteractionPlugin<T>(PhantomData<T>);
+
+impl<T> Default for InteractionPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Interaction> Plugin for InteractionPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                Self::activation_system,
+                Self::arrival_system,
+                Self::invitation_system.run_if(has_authority()),
+                Self::start_system,
+                Self::finish_system,
+                Self::cancellation_system,
+                Self::leave_system,
+                Self::propagation_system.run_if(has_authority()),
+            )
+                .run_if(resource_exists::<WorldName>()),
+        );
+    }
+}
+
+impl<T: Interaction> InteractionPlugin<T> {
+    fn activation_system(
+        mut commands: Commands,
+        mut finish_events: EventWriter<TaskFinished>,
+        tasks: Query<(Entity, &Parent, &T, &TaskState), Changed<TaskState>>,
+    ) {
+        for (task_entity, parent, interaction, &state) in &tasks {
+            if state != TaskState::Active {
+                continue;
+            }
+
+            if let Some(&target_entity) = interaction.participants().first() {
+                commands.entity(**parent).insert((
+                    MovementBundle::new(Movement::Walk).with_offset(APPROACH_DISTANCE),
+                    Following(target_entity),
+                ));
+            } else {
+                finish_events.send(TaskFinished {
+                    task: task_entity,
+                    actor: **parent,
+                    outcome: TaskOutcome::Failed,
+                });
+            }
+        }
+    }
+
+    fn arrival_system(
+        mut commands: Commands,
+        mut finish_events: EventWriter<TaskFinished>,
+        mut removed_navigations: RemovedComponents<Navigation>,
+        actor_animations: Res<AssetHandles<ActorAnimation>>,
+        mut actors: Query<(&Children, &mut Handle<AnimationClip>, Option<&NoPath>)>,
+        tasks: Query<(Entity, &TaskState), (With<T>, Without<InteractionStage>)>,
+    ) {
+        for actor_entity in &mut removed_navigations {
+            let Ok((children, mut animation_handle, no_path)) = actors.get_mut(actor_entity) else {
+                continue;
+            };
+
+            let Some((task_entity, &state)) = tasks
+                .iter_many(children)
+                .find(|(_, &state)| state != TaskState::Queued)
+            else {
+                continue;
+            };
+
+            *animation_handle = actor_animations.handle(ActorAnimation::Idle);
+            if state == TaskState::Cancelled {
+                finish_events.send(TaskFinished {
+                    task: task_entity,
+                    actor: actor_entity,
+                    outcome: TaskOutcome::Cancelled,
+                });
+            } else if no_path.is_some() {
+                finish_events.send(TaskFinished {
+                    task: task_entity,
+                    actor: actor_entity,
+                    outcome: TaskOutcome::Failed,
+                });
+            } else {
+                commands
+                    .entity(task_entity)
+                    .insert(InteractionStage::Gathering);
+            }
+        }
+    }
+
+    fn invitation_system(
+        mut commands: Commands,
+        tasks: Query<(Entity, &Parent, &T), Added<InteractionStage>>,
+        actors: Query<&Children>,
+        mut participant_tasks: Query<(&TaskGroups, &mut TaskState)>,
+    ) {
+        for (task_entity, parent, interaction) in &tasks {
+            let mut invitations = Vec::new();
+            for participant_entity in interaction.participants() {
+                let participation = T::Participant::new(**parent);
+                if let Ok(children) = actors.get(participant_entity) {
+                    // Participants should react immediately instead of waiting for their active tasks.
+                    let groups = participation.groups();
+                    let mut iter = participant_tasks.iter_many_mut(children);
+                    while let Some((task_groups, mut state)) = iter.fetch_next() {
+                        if *state == TaskState::Active && task_groups.intersects(groups) {
+                            *state = TaskState::Cancelled;
+                        }
+                    }
+                }
+
+                invitations.push(task::queue_task(
+                    &mut commands,
+                    participant_entity,
+                    &participation,
+                    participation.priority(),
+                    TaskOrder::default(),
+                ));
+            }
+            commands
+                .entity(task_entity)
+                .insert(Invitations(invitations));
+        }
+    }
+
+    fn start_system(
+        actor_animations: Res<AssetHandles<ActorAnimation>>,
+        mut tasks: Query<(&Parent, &T, &mut InteractionStage)>,
+        participant_tasks: Query<(&Parent, &T::Participant, &TaskState)>,
+        mut actors: Query<(&mut Transform, &mut Handle<AnimationClip>)>,
+    ) {
+        for (parent, interaction, mut stage) in &mut tasks {
+            if *stage != InteractionStage::Gathering {
+                continue;
+            }
+
+            let participants = interaction.participants();
+            let joined: Vec<_> = participant_tasks
+                .iter()
+                .filter(|(participant_parent, participation, &state)| {
+                    participation.initiator() == **parent
+                        && state == TaskState::Active
+                        && participants.contains(&participant_parent.get())
+                })
+                .map(|(participant_parent, ..)| **participant_parent)
+                .collect();
+            if joined.len() < participants.len() {
+                continue;
+            }
+
+            let Ok((initiator_transform, _)) = actors.get(**parent) else {
+                continue;
+            };
+            let initiator_translation = initiator_transform.translation;
+            let Some(&target_entity) = participants.first() else {
+                continue;
+            };
+            let Ok((target_transform, _)) = actors.get(target_entity) else {
+                continue;
+            };
+            let target_translation = target_transform.translation;
+
+            for &participant_entity in &joined {
+                if let Ok((mut transform, mut animation_handle)) =
+                    actors.get_mut(participant_entity)
+                {
+                    transform.look_at(initiator_translation, Vec3::Y);
+                    *animation_handle = actor_animations.handle(T::PARTICIPANT_ANIMATION);
+                }
+            }
+
+            if let Ok((mut transform, mut animation_handle)) = actors.get_mut(**parent) {
+                transform.look_at(target_translation, Vec3::Y);
+                *animation_handle = actor_animations.handle(T::INITIATOR_ANIMATION);
+            }
+
+            *stage = InteractionStage::Performing;
+        }
+    }
+
+    fn finish_system(
+        mut end_events: EventReader<AnimationEnded>,
+        mut finish_events: EventWriter<TaskFinished>,
+        actor_animations: Res<AssetHandles<ActorAnimation>>,
+        mut animations: Query<&mut Handle<AnimationClip>>,
+        actors: Query<&Children>,
+        tasks: Query<(Entity, &InteractionStage), With<T>>,
+        participant_tasks: Query<(Entity, &Parent, &T::Participant, &TaskState)>,
+    ) {
+        for event in &mut end_events {
+            let Ok(children) = actors.get(event.0) else {
+                continue;
+            };
+
+            if let Some((task_entity, _)) = tasks
+                .iter_many(children)
+                .find(|(_, &stage)| stage == InteractionStage::Performing)
+            {
+                Self::end(
+                    &mut finish_events,
+                    &mut animations,
+                    &actor_animations.handle(ActorAnimation::Idle),
+                    &participant_tasks,
+                    task_entity,
+                    event.0,
+                    TaskOutcome::Success,
+                );
+            }
+        }
+    }
+
+    fn cancellation_system(
+        mut commands: Commands,
+        mut finish_events: EventWriter<TaskFinished>,
+        actor_animations: Res<AssetHandles<ActorAnimation>>,
+        mut animations: Query<&mut Handle<AnimationClip>>,
+        tasks: Query<
+            (Entity, &Parent, &TaskState, Option<&InteractionStage>),
+            (Changed<TaskState>, With<T>),
+        >,
+        participant_tasks: Query<(Entity, &Parent, &T::Participant, &TaskState)>,
+    ) {
+        for (task_entity, parent, &state, stage) in &tasks {
+            if state != TaskState::Cancelled {
+                continue;
+            }
+
+            if stage.is_some() {
+                Self::end(
+                    &mut finish_events,
+                    &mut animations,
+                    &actor_animations.handle(ActorAnimation::Idle),
+                    &participant_tasks,
+                    task_entity,
+                    **parent,
+                    TaskOutcome::Cancelled,
+                );
+            } else {
+                // Will be finished after arrival.
+                commands.entity(**parent).remove::<Navigation>();
+            }
+        }
+    }
+
+    /// Finishes tasks of participants who cancelled their participation.
+    fn leave_system(
+        mut finish_events: EventWriter<TaskFinished>,
+        actor_animations: Res<AssetHandles<ActorAnimation>>,
+        mut animations: Query<&mut Handle<AnimationClip>>,
+        participant_tasks: Query<
+            (Entity, &Parent, &TaskState),
+            (Changed<TaskState>, With<T::Participant>),
+        >,
+    ) {
+        for (task_entity, parent, &state) in &participant_tasks {
+            if state == TaskState::Cancelled {
+                finish_events.send(TaskFinished {
+                    task: task_entity,
+                    actor: **parent,
+                    outcome: TaskOutcome::Cancelled,
+                });
+                if let Ok(mut animation_handle) = animations.get_mut(**parent) {
+                    *animation_handle = actor_animations.handle(ActorAnimation::Idle);
+                }
+            }
+        }
+    }
+
+    /// Cancels interactions that lost a participant.
+    ///
+    /// Reacts on removal to also cover cancellation of queued tasks which are despawned immediately.
+    fn propagation_system(
+        mut removed_participations: RemovedComponents<T::Participant>,
+        mut tasks: Query<(&Invitations, &mut TaskState), With<T>>,
+    ) {
+        for participation_entity in &mut removed_participations {
+            if let Some((_, mut state)) = tasks
+                .iter_mut()
+                .find(|(invitations, _)| invitations.0.contains(&participation_entity))
+            {
+                if *state == TaskState::Active {
+                    *state = TaskState::Cancelled;
+                }
+            }
+        }
+    }
+
+    /// Finishes tasks of the initiator and all participants with the same outcome.
+    fn end(
+        finish_events: &mut EventWriter<TaskFinished>,
+        animations: &mut Query<&mut Handle<AnimationClip>>,
+        idle_handle: &Handle<AnimationClip>,
+        participant_tasks: &Query<(Entity, &Parent, &T::Participant, &TaskState)>,
+        task_entity: Entity,
+        initiator_entity: Entity,
+        outcome: TaskOutcome,
+    ) {
+        for (participation_entity, parent, participation, &state) in participant_tasks {
+            if participation.initiator() != initiator_entity {
+                continue;
+            }
+
+            finish_events.send(TaskFinished {
+                task: participation_entity,
+                actor: **parent,
+                outcome,
+            });
+            if state != TaskState::Queued {
+                if let Ok(mut animation_handle) = animations.get_mut(**parent) {
+                    *animation_handle = idle_handle.clone();
+                }
+            }
+        }
+
+        finish_events.send(TaskFinished {
+            task: task_entity,
+            actor: initiator_entity,
+            outcome,
+        });
+        if let Ok(mut animation_handle) = animations.get_mut(initiator_entity) {
+            *animation_handle = idle_handle.clone();
+        }
+    }
+}
+
+/// Distance to the first participant at which the initiator stops walking.
+const APPROACH_DISTANCE: f32 = 0.5;
+
+/// Task that the initiator performs together with other actors.
+pub(super) trait Interaction: Task + Component {
+    /// Task queued for every participant.
+    type Participant: Participation;
+
+    /// Animation of the initiator, the interaction ends after it.
+    const INITIATOR_ANIMATION: ActorAnimation;
+    /// Animation that participants play while the initiator performs.
+    const PARTICIPANT_ANIMATION: ActorAnimation;
+
+    /// Actors that should join the interaction.
+    ///
+    /// The initiator walks to the first one.
+    fn participants(&self) -> Vec<Entity>;
+}
+
+/// Task of an actor that joined an [`Interaction`].
+pub(super) trait Participation: Task + Component {
+    fn new(initiator: Entity) -> Self;
+    fn initiator(&self) -> Entity;
+}
+
+/// Progress of an interaction, inserted on the initiator task after arrival.
+#[derive(Clone, Component, Copy, PartialEq)]
+enum InteractionStage {
+    /// Waiting for all participants to activate their tasks.
+    Gathering,
+    /// Playing animations.
+    Performing,
+}
+
+/// Participant tasks queued by the initiator.
+///
+/// Available only on server.
+#[derive(Component)]
+struct Invitations(Vec<Entity>);
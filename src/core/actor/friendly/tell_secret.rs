@@ -1,17 +1,16 @@
 use bevy::prelude::*;
 use bevy_replicon::prelude::*;
 
+use super::interaction::{Interaction, InteractionPlugin, Participation};
 use crate::core::{
     actor::{
-        movement::{Movement, MovementBundle},
+        autonomy::{Advertisement, AdvertisementSet},
+        needs::{NeedEffect, NeedKind},
         Actor, ActorAnimation,
     },
-    animation::AnimationEnded,
-    asset_handles::AssetHandles,
     cursor_hover::CursorHover,
     game_world::WorldName,
-    navigation::{following::Following, Navigation},
-    task::{Task, TaskGroups, TaskList, TaskListSet, TaskState},
+    task::{Task, TaskGroups, TaskList, TaskListSet, TaskPriority},
 };
 
 pub(super) struct TellSecretPlugin;
@@ -20,17 +19,12 @@ impl Plugin for TellSecretPlugin {
     fn build(&self, app: &mut App) {
         app.replicate::<TellSecret>()
             .replicate::<ListenSecret>()
+            .add_plugins(InteractionPlugin::<TellSecret>::default())
             .add_systems(
                 Update,
                 (
                     Self::list_system.in_set(TaskListSet),
-                    Self::tell_activation_system,
-                    Self::tell_system,
-                    Self::listen_activation_system,
-                    Self::finish_system,
-                    Self::listen_cancellation_system,
-                    Self::tell_cancellation_system,
-                    Self::cleanup_system,
+                    Self::advertisement_system.in_set(AdvertisementSet),
                 )
                     .run_if(resource_exists::<WorldName>()),
             );
@@ -47,156 +41,19 @@ impl TellSecretPlugin {
         }
     }
 
-    fn tell_activation_system(
-        mut commands: Commands,
-        tasks: Query<(&TellSecret, &Parent, &TaskState), Changed<TaskState>>,
+    fn advertisement_system(
+        mut advertisements: EventWriter<Advertisement>,
+        actors: Query<Entity, With<Actor>>,
     ) {
-        for (tell_secret, parent, &state) in &tasks {
-            if state == TaskState::Active {
-                commands.entity(**parent).insert((
-                    MovementBundle::new(Movement::Walk).with_offset(0.5),
-                    Following(tell_secret.0),
-                ));
-            }
-        }
-    }
-
-    fn tell_system(
-        mut commands: Commands,
-        mut removed_navigations: RemovedComponents<Navigation>,
-        actor_animations: Res<AssetHandles<ActorAnimation>>,
-        mut actors: Query<(&Children, &mut Handle<AnimationClip>)>,
-        tasks: Query<(&TellSecret, &TaskState)>,
-    ) {
-        for entity in &mut removed_navigations {
-            let Ok((children, mut animation_handle)) = actors.get_mut(entity) else {
-                continue;
-            };
-
-            let Some((tell_secret, _)) = tasks.iter_many(children).find(|(_, &state)| state == TaskState::Active) else {
-                continue;
-            };
-
-            commands.entity(entity).insert(TellingSecret);
-            *animation_handle = actor_animations.handle(ActorAnimation::TellSecret);
-
-            // TODO: Handle cancellation of currently active tasks.
-            commands.entity(tell_secret.0).with_children(|parent| {
-                parent.spawn((
-                    Name::new("Listen secret"),
-                    TaskGroups::LEGS,
-                    TaskState::Queued,
-                    ListenSecret(entity),
-                ));
+        for entity in &actors {
+            advertisements.send(Advertisement {
+                source: entity,
+                task: Box::new(TellSecret(entity)),
             });
         }
     }
-
-    fn listen_activation_system(
-        actor_animations: Res<AssetHandles<ActorAnimation>>,
-        tasks: Query<(&ListenSecret, &Parent), Added<ListenSecret>>,
-        mut listen_actors: Query<
-            (&mut Transform, &mut Handle<AnimationClip>),
-            Without<TellingSecret>,
-        >,
-        tell_actors: Query<&Transform, With<TellingSecret>>,
-    ) {
-        for (tell_secret, parent) in &tasks {
-            let (mut listen_transform, mut animation_handle) = listen_actors
-                .get_mut(**parent)
-                .expect("listener should have animation");
-            let tell_transform = tell_actors
-                .get(tell_secret.0)
-                .expect("teller should have transform");
-
-            listen_transform.look_at(tell_transform.translation, Vec3::Y);
-            *animation_handle = actor_animations.handle(ActorAnimation::ThoughtfulNod);
-        }
-    }
-
-    fn finish_system(
-        mut commands: Commands,
-        mut end_events: EventReader<AnimationEnded>,
-        tell_actors: Query<(), With<TellingSecret>>,
-    ) {
-        for event in &mut end_events {
-            if tell_actors.get(event.0).is_ok() {
-                commands.entity(event.0).insert(ToldSecret);
-            }
-        }
-    }
-
-    fn listen_cancellation_system(
-        tasks: Query<(&Parent, &TaskState), (Changed<TaskState>, With<ListenSecret>)>,
-        mut actors: Query<(&mut TaskState, &TellSecret), Without<ListenSecret>>,
-    ) {
-        for (parent, &listen_state) in &tasks {
-            if listen_state == TaskState::Cancelled {
-                if let Some((mut tell_state, _)) = actors
-                    .iter_mut()
-                    .find(|(_, tell_secret)| tell_secret.0 == **parent)
-                {
-                    *tell_state = TaskState::Cancelled;
-                }
-            }
-        }
-    }
-
-    fn tell_cancellation_system(
-        mut commands: Commands,
-        tasks: Query<(&Parent, &TaskState), (Changed<TaskState>, With<TellSecret>)>,
-    ) {
-        for (parent, &state) in &tasks {
-            if state == TaskState::Cancelled {
-                commands.entity(**parent).insert(ToldSecret);
-            }
-        }
-    }
-
-    fn cleanup_system(
-        mut commands: Commands,
-        actor_animations: Res<AssetHandles<ActorAnimation>>,
-        mut tell_actors: Query<(Entity, &Children, &mut Handle<AnimationClip>), Added<ToldSecret>>,
-        mut listen_actors: Query<&mut Handle<AnimationClip>, Without<ToldSecret>>,
-        listen_tasks: Query<(Entity, &Parent, &ListenSecret, &TaskState)>,
-        tell_tasks: Query<(Entity, &TaskState), With<TellSecret>>,
-    ) {
-        for (teller_entity, children, mut animation_handle) in &mut tell_actors {
-            if let Some((listen_entity, parent, ..)) =
-                listen_tasks.iter().find(|(.., listen_secret, &state)| {
-                    listen_secret.0 == teller_entity && state != TaskState::Queued
-                })
-            {
-                commands.entity(listen_entity).despawn();
-                let mut animation_handle = listen_actors
-                    .get_mut(**parent)
-                    .expect("actor should have animation handle");
-                *animation_handle = actor_animations.handle(ActorAnimation::Idle);
-            }
-
-            *animation_handle = actor_animations.handle(ActorAnimation::Idle);
-
-            commands
-                .entity(teller_entity)
-                .remove::<(Navigation, TellingSecret, ToldSecret)>();
-
-            let (tell_entity, _) = tell_tasks
-                .iter_many(children)
-                .find(|(_, &state)| state != TaskState::Queued)
-                .expect("actor should have tell secret task as a child");
-            commands.entity(tell_entity).despawn();
-        }
-    }
 }
 
-/// Indicates that the actor is currently telling a secret.
-#[derive(Component)]
-struct TellingSecret;
-
-/// Indicates that the actor just finished telling a secret.
-#[derive(Component)]
-struct ToldSecret;
-
 #[derive(Debug, Reflect, Component)]
 #[reflect(Component)]
 struct TellSecret(Entity);
@@ -209,6 +66,24 @@ impl Task for TellSecret {
     fn groups(&self) -> TaskGroups {
         TaskGroups::LEGS
     }
+
+    fn need_effects(&self) -> Vec<NeedEffect> {
+        vec![NeedEffect {
+            kind: NeedKind::Social,
+            delta: 30.0,
+        }]
+    }
+}
+
+impl Interaction for TellSecret {
+    type Participant = ListenSecret;
+
+    const INITIATOR_ANIMATION: ActorAnimation = ActorAnimation::TellSecret;
+    const PARTICIPANT_ANIMATION: ActorAnimation = ActorAnimation::ThoughtfulNod;
+
+    fn participants(&self) -> Vec<Entity> {
+        vec![self.0]
+    }
 }
 
 impl FromWorld for TellSecret {
@@ -221,6 +96,31 @@ impl FromWorld for TellSecret {
 #[reflect(Component)]
 struct ListenSecret(Entity);
 
+impl Task for ListenSecret {
+    fn name(&self) -> &str {
+        "Listen secret"
+    }
+
+    fn groups(&self) -> TaskGroups {
+        TaskGroups::LEGS
+    }
+
+    fn priority(&self) -> TaskPriority {
+        // Listener should react before continuing its own tasks.
+        TaskPriority::High
+    }
+}
+
+impl Participation for ListenSecret {
+    fn new(initiator: Entity) -> Self {
+        Self(initiator)
+    }
+
+    fn initiator(&self) -> Entity {
+        self.0
+    }
+}
+
 impl FromWorld for ListenSecret {
     fn from_world(_world: &mut World) -> Self {
         Self(Entity::PLACEHOLDER)
@@ -9,8 +9,10 @@ use crate::core::{
     cursor_hover::CursorHover,
     game_world::WorldName,
     ground::Ground,
-    navigation::{endpoint::Endpoint, Navigation},
-    task::{Task, TaskGroups, TaskList, TaskListSet, TaskState},
+    navigation::{endpoint::Endpoint, Navigation, NoPath},
+    task::{
+        Task, TaskFinished, TaskGroups, TaskList, TaskListSet, TaskOutcome, TaskProgress, TaskState,
+    },
 };
 
 pub(super) struct MoveHerePlugin;
@@ -22,6 +24,7 @@ impl Plugin for MoveHerePlugin {
             (
                 Self::list_system.in_set(TaskListSet),
                 Self::activation_system,
+                Self::progress_system.run_if(has_authority()),
                 Self::cancellation_system,
                 Self::finish_system,
             )
@@ -55,14 +58,38 @@ impl MoveHerePlugin {
 
     fn activation_system(
         mut commands: Commands,
-        tasks: Query<(&Parent, &MoveHere, &TaskState), Changed<TaskState>>,
+        tasks: Query<(Entity, &Parent, &MoveHere, &TaskState), Changed<TaskState>>,
+        actors: Query<&Transform>,
     ) {
-        for (parent, move_here, &state) in &tasks {
+        for (task_entity, parent, move_here, &state) in &tasks {
             if state == TaskState::Active {
                 commands.entity(**parent).insert((
                     MovementBundle::new(move_here.movement),
                     Endpoint::new(move_here.endpoint),
                 ));
+                if let Ok(transform) = actors.get(**parent) {
+                    commands
+                        .entity(task_entity)
+                        .insert(StartPosition(transform.translation));
+                }
+            }
+        }
+    }
+
+    fn progress_system(
+        actors: Query<&Transform>,
+        mut tasks: Query<(&Parent, &MoveHere, &StartPosition, &mut TaskProgress)>,
+    ) {
+        for (parent, move_here, start_position, mut progress) in &mut tasks {
+            let Ok(transform) = actors.get(**parent) else {
+                continue;
+            };
+
+            let total = start_position.0.distance(move_here.endpoint);
+            if total > 0.0 {
+                let remaining = transform.translation.distance(move_here.endpoint);
+                let value = ((1.0 - remaining / total) * 100.0).clamp(0.0, 100.0);
+                progress.set_if_neq(TaskProgress(value));
             }
         }
     }
@@ -79,19 +106,29 @@ impl MoveHerePlugin {
     }
 
     fn finish_system(
-        mut commands: Commands,
+        mut finish_events: EventWriter<TaskFinished>,
         actor_animations: Res<AssetHandles<ActorAnimation>>,
         mut removed_movements: RemovedComponents<Movement>,
-        mut actors: Query<(&Children, &mut Handle<AnimationClip>)>,
+        mut actors: Query<(&Children, &mut Handle<AnimationClip>, Option<&NoPath>)>,
         tasks: Query<(Entity, &TaskState), With<MoveHere>>,
     ) {
         for actor_entity in &mut removed_movements {
-            if let Ok((children, mut animation_handle)) = actors.get_mut(actor_entity) {
-                if let Some((task_entity, _)) = tasks
+            if let Ok((children, mut animation_handle, no_path)) = actors.get_mut(actor_entity) {
+                if let Some((task_entity, &state)) = tasks
                     .iter_many(children)
                     .find(|(_, &state)| state != TaskState::Queued)
                 {
-                    commands.entity(task_entity).despawn();
+                    let outcome = match state {
+                        TaskState::Cancelled => TaskOutcome::Cancelled,
+                        TaskState::Active if no_path.is_some() => TaskOutcome::Failed,
+                        TaskState::Active => TaskOutcome::Success,
+                        TaskState::Queued => continue,
+                    };
+                    finish_events.send(TaskFinished {
+                        task: task_entity,
+                        actor: actor_entity,
+                        outcome,
+                    });
                     *animation_handle = actor_animations.handle(ActorAnimation::Idle);
                 }
             }
@@ -106,6 +143,10 @@ struct MoveHere {
     movement: Movement,
 }
 
+/// Actor position at the moment of task activation, used to calculate progress.
+#[derive(Component)]
+struct StartPosition(Vec3);
+
 impl Task for MoveHere {
     fn name(&self) -> &str {
         match self.movement {
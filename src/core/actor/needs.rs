@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use bevy::{prelude::*, time::common_conditions::on_timer};
 use bevy_replicon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::core::game_world::WorldName;
 
@@ -16,6 +17,8 @@ impl Plugin for NeedsPlugin {
             .replicate::<Energy>()
             .replicate::<Bladder>()
             .replicate::<Need>()
+            .register_type::<NeedKind>()
+            .register_type::<NeedEffect>()
             .not_replicate_if_present::<Name, Need>()
             .add_systems(
                 Update,
@@ -40,49 +43,67 @@ impl Plugin for NeedsPlugin {
 impl NeedsPlugin {
     fn hunger_init_system(mut commands: Commands, needs: Query<Entity, Added<Hunger>>) {
         for entity in &needs {
-            commands
-                .entity(entity)
-                .insert((Name::new("Hunger"), NeedGlyph("🍴"), NeedRate(-0.4)));
+            commands.entity(entity).insert((
+                Name::new("Hunger"),
+                NeedGlyph("🍴"),
+                NeedRate(-0.4),
+                NeedKind::Hunger,
+            ));
         }
     }
 
     fn social_init_system(mut commands: Commands, needs: Query<Entity, Added<Social>>) {
         for entity in &needs {
-            commands
-                .entity(entity)
-                .insert((Name::new("Social"), NeedGlyph("💬"), NeedRate(-0.1)));
+            commands.entity(entity).insert((
+                Name::new("Social"),
+                NeedGlyph("💬"),
+                NeedRate(-0.1),
+                NeedKind::Social,
+            ));
         }
     }
 
     fn hygiene_init_system(mut commands: Commands, needs: Query<Entity, Added<Hygiene>>) {
         for entity in &needs {
-            commands
-                .entity(entity)
-                .insert((Name::new("Hygiene"), NeedGlyph("🚿"), NeedRate(-0.3)));
+            commands.entity(entity).insert((
+                Name::new("Hygiene"),
+                NeedGlyph("🚿"),
+                NeedRate(-0.3),
+                NeedKind::Hygiene,
+            ));
         }
     }
 
     fn fun_init_system(mut commands: Commands, needs: Query<Entity, Added<Fun>>) {
         for entity in &needs {
-            commands
-                .entity(entity)
-                .insert((Name::new("Fun"), NeedGlyph("🎉"), NeedRate(-0.1)));
+            commands.entity(entity).insert((
+                Name::new("Fun"),
+                NeedGlyph("🎉"),
+                NeedRate(-0.1),
+                NeedKind::Fun,
+            ));
         }
     }
 
     fn energy_init_system(mut commands: Commands, needs: Query<Entity, Added<Energy>>) {
         for entity in &needs {
-            commands
-                .entity(entity)
-                .insert((Name::new("Energy"), NeedGlyph("🔋"), NeedRate(-0.2)));
+            commands.entity(entity).insert((
+                Name::new("Energy"),
+                NeedGlyph("🔋"),
+                NeedRate(-0.2),
+                NeedKind::Energy,
+            ));
         }
     }
 
     fn bladder_init_system(mut commands: Commands, needs: Query<Entity, Added<Bladder>>) {
         for entity in &needs {
-            commands
-                .entity(entity)
-                .insert((Name::new("Bladder"), NeedGlyph("🚽"), NeedRate(-0.5)));
+            commands.entity(entity).insert((
+                Name::new("Bladder"),
+                NeedGlyph("🚽"),
+                NeedRate(-0.5),
+                NeedKind::Bladder,
+            ));
         }
     }
 
@@ -150,6 +171,24 @@ impl Default for Need {
     }
 }
 
+/// Identifies the need, inserted automatically based on its marker component.
+#[derive(Clone, Component, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Reflect, Serialize)]
+pub(crate) enum NeedKind {
+    Hunger,
+    Social,
+    Hygiene,
+    Fun,
+    Energy,
+    Bladder,
+}
+
+/// Change of a need after a successfully finished task.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Reflect, Serialize)]
+pub(crate) struct NeedEffect {
+    pub(crate) kind: NeedKind,
+    pub(crate) delta: f32,
+}
+
 #[derive(Component)]
 struct NeedRate(f32);
 
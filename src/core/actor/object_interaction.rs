@@ -0,0 +1,338 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_replicon::prelude::*;
+
+use super::{
+    autonomy::{Advertisement, AdvertisementSet},
+    movement::{Movement, MovementBundle},
+    needs::NeedEffect,
+    ActorAnimation,
+};
+use crate::core::{
+    asset_handles::AssetHandles,
+    asset_metadata::{InteractionMetadata, ObjectMetadata},
+    cursor_hover::CursorHover,
+    game_world::WorldName,
+    navigation::{endpoint::Endpoint, Navigation, NoPath},
+    object::ObjectPath,
+    task::{
+        Task, TaskFinished, TaskGroups, TaskList, TaskListSet, TaskOutcome, TaskProgress, TaskState,
+    },
+};
+
+/// Tasks declared in [`ObjectMetadata::interactions`].
+pub(super) struct ObjectInteractionPlugin;
+
+impl Plugin for ObjectInteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.replicate::<ObjectInteraction>()
+            .register_type::<Vec<NeedEffect>>()
+            .add_systems(
+                Update,
+                (
+                    Self::resolve_system,
+                    Self::list_system.in_set(TaskListSet),
+                    Self::advertisement_system.in_set(AdvertisementSet),
+                    Self::activation_system,
+                    Self::arrival_system,
+                    Self::timer_system,
+                    Self::progress_system.run_if(has_authority()),
+                    Self::cancellation_system,
+                )
+                    .run_if(resource_exists::<WorldName>()),
+            );
+    }
+}
+
+impl ObjectInteractionPlugin {
+    /// Fills interaction details that are not serialized.
+    fn resolve_system(
+        object_interactions: ObjectInteractions,
+        mut tasks: Query<&mut ObjectInteraction, Added<ObjectInteraction>>,
+    ) {
+        for mut interaction in &mut tasks {
+            if let Some(resolved) = object_interactions.get(interaction.object, &interaction.name) {
+                interaction.set_if_neq(resolved);
+            }
+        }
+    }
+
+    fn list_system(
+        mut list_events: EventWriter<TaskList>,
+        asset_server: Res<AssetServer>,
+        object_metadata: Res<Assets<ObjectMetadata>>,
+        objects: Query<(Entity, &ObjectPath), With<CursorHover>>,
+    ) {
+        if let Ok((object_entity, object_path)) = objects.get_single() {
+            let metadata_handle = asset_server.load(&*object_path.0);
+            if let Some(metadata) = object_metadata.get(&metadata_handle) {
+                for interaction in &metadata.interactions {
+                    list_events.send(ObjectInteraction::new(object_entity, interaction).into());
+                }
+            }
+        }
+    }
+
+    fn advertisement_system(
+        mut advertisements: EventWriter<Advertisement>,
+        asset_server: Res<AssetServer>,
+        object_metadata: Res<Assets<ObjectMetadata>>,
+        objects: Query<(Entity, &ObjectPath)>,
+    ) {
+        for (object_entity, object_path) in &objects {
+            let metadata_handle = asset_server.load(&*object_path.0);
+            if let Some(metadata) = object_metadata.get(&metadata_handle) {
+                for interaction in &metadata.interactions {
+                    advertisements.send(Advertisement {
+                        source: object_entity,
+                        task: Box::new(ObjectInteraction::new(object_entity, interaction)),
+                    });
+                }
+            }
+        }
+    }
+
+    fn activation_system(
+        mut commands: Commands,
+        mut finish_events: EventWriter<TaskFinished>,
+        tasks: Query<(Entity, &Parent, &ObjectInteraction, &TaskState), Changed<TaskState>>,
+        objects: Query<&GlobalTransform>,
+    ) {
+        for (task_entity, parent, interaction, &state) in &tasks {
+            if state == TaskState::Active {
+                if let Ok(object_transform) = objects.get(interaction.object) {
+                    commands.entity(**parent).insert((
+                        MovementBundle::new(Movement::Walk).with_offset(INTERACTION_DISTANCE),
+                        Endpoint::new(object_transform.translation()),
+                    ));
+                } else {
+                    finish_events.send(TaskFinished {
+                        task: task_entity,
+                        actor: **parent,
+                        outcome: TaskOutcome::Failed,
+                    });
+                }
+            }
+        }
+    }
+
+    fn arrival_system(
+        mut commands: Commands,
+        mut finish_events: EventWriter<TaskFinished>,
+        mut removed_movements: RemovedComponents<Movement>,
+        asset_server: Res<AssetServer>,
+        actor_animations: Res<AssetHandles<ActorAnimation>>,
+        mut actors: Query<(
+            &Children,
+            &mut Transform,
+            &mut Handle<AnimationClip>,
+            Option<&NoPath>,
+        )>,
+        tasks: Query<(Entity, &ObjectInteraction, &TaskState)>,
+        objects: Query<&GlobalTransform>,
+    ) {
+        for actor_entity in &mut removed_movements {
+            let Ok((children, mut transform, mut animation_handle, no_path)) =
+                actors.get_mut(actor_entity)
+            else {
+                continue;
+            };
+
+            let Some((task_entity, interaction, &state)) = tasks
+                .iter_many(children)
+                .find(|(.., &state)| state != TaskState::Queued)
+            else {
+                continue;
+            };
+
+            if state == TaskState::Cancelled || no_path.is_some() {
+                let outcome = if state == TaskState::Cancelled {
+                    TaskOutcome::Cancelled
+                } else {
+                    TaskOutcome::Failed
+                };
+                finish_events.send(TaskFinished {
+                    task: task_entity,
+                    actor: actor_entity,
+                    outcome,
+                });
+                *animation_handle = actor_animations.handle(ActorAnimation::Idle);
+                continue;
+            }
+
+            if let Ok(object_transform) = objects.get(interaction.object) {
+                let mut target = object_transform.translation();
+                target.y = transform.translation.y;
+                transform.look_at(target, Vec3::Y);
+            }
+            *animation_handle = match &interaction.animation {
+                Some(animation) => asset_server.load(animation.as_str()),
+                None => actor_animations.handle(ActorAnimation::Idle),
+            };
+            commands
+                .entity(task_entity)
+                .insert(InteractionTimer(Timer::from_seconds(
+                    interaction.duration,
+                    TimerMode::Once,
+                )));
+        }
+    }
+
+    fn timer_system(
+        time: Res<Time>,
+        mut finish_events: EventWriter<TaskFinished>,
+        actor_animations: Res<AssetHandles<ActorAnimation>>,
+        mut actors: Query<&mut Handle<AnimationClip>>,
+        mut tasks: Query<(Entity, &Parent, &mut InteractionTimer)>,
+    ) {
+        for (task_entity, parent, mut timer) in &mut tasks {
+            if timer.0.tick(time.delta()).just_finished() {
+                finish_events.send(TaskFinished {
+                    task: task_entity,
+                    actor: **parent,
+                    outcome: TaskOutcome::Success,
+                });
+                if let Ok(mut animation_handle) = actors.get_mut(**parent) {
+                    *animation_handle = actor_animations.handle(ActorAnimation::Idle);
+                }
+            }
+        }
+    }
+
+    fn progress_system(mut tasks: Query<(&InteractionTimer, &mut TaskProgress)>) {
+        for (timer, mut progress) in &mut tasks {
+            progress.set_if_neq(TaskProgress(timer.0.percent() * 100.0));
+        }
+    }
+
+    fn cancellation_system(
+        mut commands: Commands,
+        mut finish_events: EventWriter<TaskFinished>,
+        actor_animations: Res<AssetHandles<ActorAnimation>>,
+        mut actors: Query<&mut Handle<AnimationClip>>,
+        tasks: Query<
+            (Entity, &Parent, &TaskState, Option<&InteractionTimer>),
+            (Changed<TaskState>, With<ObjectInteraction>),
+        >,
+    ) {
+        for (task_entity, parent, &state, timer) in &tasks {
+            if state != TaskState::Cancelled {
+                continue;
+            }
+
+            if timer.is_some() {
+                finish_events.send(TaskFinished {
+                    task: task_entity,
+                    actor: **parent,
+                    outcome: TaskOutcome::Cancelled,
+                });
+                if let Ok(mut animation_handle) = actors.get_mut(**parent) {
+                    *animation_handle = actor_animations.handle(ActorAnimation::Idle);
+                }
+            } else {
+                // Will be finished after the movement stops.
+                commands.entity(**parent).remove::<Navigation>();
+            }
+        }
+    }
+}
+
+/// Distance from the object at which the actor stops walking.
+const INTERACTION_DISTANCE: f32 = 1.0;
+
+/// Counts the interaction duration after the actor reached the object.
+#[derive(Component)]
+struct InteractionTimer(Timer);
+
+/// Looks up interactions in the metadata of objects.
+#[derive(SystemParam)]
+pub(crate) struct ObjectInteractions<'w, 's> {
+    asset_server: Res<'w, AssetServer>,
+    object_metadata: Res<'w, Assets<ObjectMetadata>>,
+    objects: Query<'w, 's, &'static ObjectPath>,
+}
+
+impl ObjectInteractions<'_, '_> {
+    /// Rebuilds [`ObjectInteraction`] requested by a client from the object metadata.
+    ///
+    /// Other tasks are returned as is. Returns [`None`] if the object doesn't offer the interaction.
+    pub(crate) fn rebuild(&self, task: Box<dyn Task>) -> Option<Box<dyn Task>> {
+        let Some(interaction) = task.as_reflect().downcast_ref::<ObjectInteraction>() else {
+            return Some(task);
+        };
+
+        let interaction = self.get(interaction.object, &interaction.name)?;
+        Some(Box::new(interaction))
+    }
+
+    fn get(&self, object_entity: Entity, name: &str) -> Option<ObjectInteraction> {
+        let object_path = self.objects.get(object_entity).ok()?;
+        let metadata_handle = self.asset_server.load(&*object_path.0);
+        let metadata = self.object_metadata.get(&metadata_handle)?;
+        let interaction = metadata
+            .interactions
+            .iter()
+            .find(|interaction| interaction.name == name)?;
+
+        Some(ObjectInteraction::new(object_entity, interaction))
+    }
+}
+
+/// Only the object and the interaction name are serialized.
+///
+/// Other fields are resolved from the object metadata to avoid trusting clients.
+#[derive(Clone, Component, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+struct ObjectInteraction {
+    object: Entity,
+    name: String,
+    /// Bits of [`TaskGroups`] since flags can't be reflected.
+    #[reflect(skip_serializing)]
+    groups: u8,
+    #[reflect(skip_serializing)]
+    duration: f32,
+    #[reflect(skip_serializing)]
+    animation: Option<String>,
+    #[reflect(skip_serializing)]
+    needs: Vec<NeedEffect>,
+}
+
+impl ObjectInteraction {
+    fn new(object: Entity, metadata: &InteractionMetadata) -> Self {
+        Self {
+            object,
+            name: metadata.name.clone(),
+            groups: metadata.groups.bits(),
+            duration: metadata.duration,
+            animation: metadata.animation.clone(),
+            needs: metadata.needs.clone(),
+        }
+    }
+}
+
+impl Task for ObjectInteraction {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn groups(&self) -> TaskGroups {
+        // Legs are always required to walk to the object.
+        TaskGroups::from_bits_truncate(self.groups) | TaskGroups::LEGS
+    }
+
+    fn need_effects(&self) -> Vec<NeedEffect> {
+        self.needs.clone()
+    }
+}
+
+impl FromWorld for ObjectInteraction {
+    fn from_world(_world: &mut World) -> Self {
+        Self {
+            object: Entity::PLACEHOLDER,
+            name: Default::default(),
+            groups: Default::default(),
+            duration: Default::default(),
+            animation: Default::default(),
+            needs: Default::default(),
+        }
+    }
+}
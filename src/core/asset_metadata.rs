@@ -22,6 +22,8 @@ use serde::{
 use strum::{Display, EnumDiscriminants, EnumVariantNames, IntoStaticStr, VariantNames};
 use walkdir::WalkDir;
 
+use super::{actor::needs::NeedEffect, task::TaskGroups};
+
 const METADATA_EXTENSION: &str = "toml";
 
 pub(super) struct AssetMetadataPlugin;
@@ -131,6 +133,7 @@ pub(crate) struct ObjectMetadata {
     pub(crate) general: GeneralMetadata,
     pub(crate) category: ObjectCategory,
     pub(crate) components: Vec<Box<dyn Reflect>>,
+    pub(crate) interactions: Vec<InteractionMetadata>,
 }
 
 /// Fields of [`ObjectMetadata`] for manual deserialization.
@@ -142,6 +145,46 @@ enum ObjectMetadataField {
     PreviewTranslation,
     Category,
     Components,
+    Interactions,
+}
+
+/// Interaction that an object offers to actors as a task.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct InteractionMetadata {
+    pub(crate) name: String,
+    /// Names of [`TaskGroups`] flags required by the interaction.
+    #[serde(default, deserialize_with = "deserialize_groups")]
+    pub(crate) groups: TaskGroups,
+    /// Time in seconds that the actor spends near the object.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub(crate) duration: f32,
+    /// Path to the animation played during the interaction.
+    pub(crate) animation: Option<String>,
+    /// Changes of needs after a successful interaction.
+    #[serde(default)]
+    pub(crate) needs: Vec<NeedEffect>,
+}
+
+fn deserialize_groups<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TaskGroups, D::Error> {
+    let names = Vec::<String>::deserialize(deserializer)?;
+    names.iter().try_fold(TaskGroups::empty(), |groups, name| {
+        let group = TaskGroups::from_name(name)
+            .ok_or_else(|| de::Error::custom(format!("{name} is not a task group")))?;
+        Ok(groups | group)
+    })
+}
+
+/// Rejects durations that can't be converted into [`std::time::Duration`].
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let duration = f32::deserialize(deserializer)?;
+    if !duration.is_finite() || duration < 0.0 {
+        return Err(de::Error::custom(format!(
+            "duration should be a finite non-negative number, but got {duration}"
+        )));
+    }
+
+    Ok(duration)
 }
 
 #[derive(Clone, Component, Copy, Deserialize, Display, PartialEq)]
@@ -243,6 +286,7 @@ impl<'de> Visitor<'de> for ObjectMetadataDeserializer<'_> {
         let mut preview_translation = None;
         let mut category = None;
         let mut components = None;
+        let mut interactions = None;
         while let Some(key) = map.next_key()? {
             match key {
                 ObjectMetadataField::Name => {
@@ -276,6 +320,14 @@ impl<'de> Visitor<'de> for ObjectMetadataDeserializer<'_> {
                     components =
                         Some(map.next_value_seed(ComponentsDeserializer::new(self.registry))?);
                 }
+                ObjectMetadataField::Interactions => {
+                    if interactions.is_some() {
+                        return Err(de::Error::duplicate_field(
+                            ObjectMetadataField::Interactions.into(),
+                        ));
+                    }
+                    interactions = Some(map.next_value()?);
+                }
             }
         }
         let name =
@@ -287,6 +339,7 @@ impl<'de> Visitor<'de> for ObjectMetadataDeserializer<'_> {
             .ok_or_else(|| de::Error::missing_field(ObjectMetadataField::Category.into()))?;
         let components = components
             .ok_or_else(|| de::Error::missing_field(ObjectMetadataField::Components.into()))?;
+        let interactions = interactions.unwrap_or_default();
 
         Ok(ObjectMetadata {
             general: GeneralMetadata {
@@ -295,6 +348,7 @@ impl<'de> Visitor<'de> for ObjectMetadataDeserializer<'_> {
             },
             category,
             components,
+            interactions,
         })
     }
 }
@@ -406,4 +460,18 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn invalid_interaction_duration() {
+        for duration in ["-1.0", "nan", "inf"] {
+            let data = format!("name = \"Rest\"\nduration = {duration}");
+            assert!(
+                toml::from_str::<InteractionMetadata>(&data).is_err(),
+                "duration {duration} should be rejected"
+            );
+        }
+
+        let data = "name = \"Rest\"\nduration = 10.0";
+        assert!(toml::from_str::<InteractionMetadata>(data).is_ok());
+    }
 }
@@ -0,0 +1,275 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use bevy_replicon::{
+    prelude::*,
+    renet::{transport::NetcodeServerTransport, ServerEvent},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    game_world::{recording::AppRecordingExt, WorldName},
+    player::{self, Ownership, Player},
+};
+
+pub(super) struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatHistory>()
+            .init_resource::<RateLimits>()
+            .add_client_event::<ChatSend>(SendPolicy::Ordered)
+            .add_server_event::<ChatMessage>(SendPolicy::Ordered)
+            .record_client_event::<ChatSend>()
+            .add_systems(
+                Update,
+                (
+                    Self::history_system,
+                    Self::cleanup_system.run_if(resource_removed::<WorldName>()),
+                    (
+                        Self::receive_system,
+                        Self::connection_system
+                            .run_if(resource_exists::<NetcodeServerTransport>())
+                            .run_if(resource_exists::<WorldName>()),
+                    )
+                        .run_if(has_authority()),
+                ),
+            );
+    }
+}
+
+impl ChatPlugin {
+    fn receive_system(
+        mut rate_limits: ResMut<RateLimits>,
+        mut send_events: EventReader<FromClient<ChatSend>>,
+        mut message_events: EventWriter<ToClients<ChatMessage>>,
+        time: Res<Time>,
+        ownership: Ownership,
+        players: Query<(&Player, &Name)>,
+    ) {
+        for FromClient { client_id, event } in &mut send_events {
+            let text = event.0.trim();
+            if text.is_empty() {
+                continue;
+            }
+
+            let rate_limit = rate_limits.0.entry(*client_id).or_default();
+            if !rate_limit.try_send(time.elapsed()) {
+                message_events.send(ToClients {
+                    mode: SendMode::Direct(*client_id),
+                    event: ChatMessage::system("you are sending messages too fast"),
+                });
+                continue;
+            }
+
+            let id = ownership.player_id(*client_id);
+            let message = ChatMessage {
+                sender: ChatSender::Player {
+                    id,
+                    name: player_name(&players, id),
+                },
+                text: text.chars().take(MAX_MESSAGE_LEN).collect(),
+            };
+            info!("{message}");
+            message_events.send(ToClients {
+                mode: SendMode::Broadcast,
+                event: message,
+            });
+        }
+    }
+
+    fn connection_system(
+        mut server_events: EventReader<ServerEvent>,
+        mut message_events: EventWriter<ToClients<ChatMessage>>,
+        mut rate_limits: ResMut<RateLimits>,
+        transport: Res<NetcodeServerTransport>,
+        players: Query<(&Player, &Name)>,
+    ) {
+        for event in &mut server_events {
+            let text = match event {
+                ServerEvent::ClientConnected { client_id } => {
+                    format!("{} joined", player::client_name(&transport, *client_id))
+                }
+                ServerEvent::ClientDisconnected { client_id, .. } => {
+                    rate_limits.0.remove(client_id);
+                    format!("{} left", player_name(&players, *client_id))
+                }
+            };
+            announce(&mut message_events, text);
+        }
+    }
+
+    fn history_system(
+        mut message_events: EventReader<ChatMessage>,
+        mut history: ResMut<ChatHistory>,
+    ) {
+        for message in message_events.iter().cloned() {
+            history.push(message);
+        }
+    }
+
+    fn cleanup_system(mut history: ResMut<ChatHistory>, mut rate_limits: ResMut<RateLimits>) {
+        history.0.clear();
+        rate_limits.0.clear();
+    }
+}
+
+/// Maximum number of characters in a single message, the rest is cut off.
+const MAX_MESSAGE_LEN: usize = 256;
+/// Number of messages that can be sent at once before the rate limit kicks in.
+const MESSAGE_BURST: f32 = 5.0;
+/// Time to restore a single message from the burst.
+const MESSAGE_REFILL: Duration = Duration::from_secs(2);
+
+/// Rate limits of connected clients.
+///
+/// Filled only on server.
+#[derive(Default, Resource)]
+struct RateLimits(HashMap<u64, RateLimit>);
+
+/// Token bucket that allows short bursts of messages.
+struct RateLimit {
+    tokens: f32,
+    updated_at: Duration,
+}
+
+impl RateLimit {
+    /// Consumes a token and returns `true` if the message can be sent at the specified time.
+    fn try_send(&mut self, now: Duration) -> bool {
+        let elapsed = now.saturating_sub(self.updated_at);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f32() / MESSAGE_REFILL.as_secs_f32()).min(MESSAGE_BURST);
+        self.updated_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            tokens: MESSAGE_BURST,
+            updated_at: Duration::ZERO,
+        }
+    }
+}
+
+/// Returns name of the registered player or a placeholder based on ID.
+fn player_name(players: &Query<(&Player, &Name)>, id: u64) -> String {
+    players
+        .iter()
+        .find(|(player, _)| player.0 == id)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("Player {id}"))
+}
+
+/// Sends a system message to all players.
+///
+/// Should be called only on server.
+pub(crate) fn announce(
+    message_events: &mut EventWriter<ToClients<ChatMessage>>,
+    text: impl Into<String>,
+) {
+    message_events.send(ToClients {
+        mode: SendMode::Broadcast,
+        event: ChatMessage::system(text),
+    });
+}
+
+/// Recent chat messages received by the local player.
+#[derive(Default, Resource)]
+pub(crate) struct ChatHistory(VecDeque<ChatMessage>);
+
+impl ChatHistory {
+    const MAX_LEN: usize = 100;
+
+    fn push(&mut self, message: ChatMessage) {
+        if self.0.len() == Self::MAX_LEN {
+            self.0.pop_front();
+        }
+        self.0.push_back(message);
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = &ChatMessage> {
+        self.0.iter()
+    }
+}
+
+/// A message that the local player wants to send to the chat.
+#[derive(Debug, Deserialize, Event, Serialize)]
+pub(crate) struct ChatSend(pub(crate) String);
+
+#[derive(Clone, Debug, Deserialize, Event, Serialize)]
+pub(crate) struct ChatMessage {
+    sender: ChatSender,
+    text: String,
+}
+
+impl ChatMessage {
+    fn system(text: impl Into<String>) -> Self {
+        Self {
+            sender: ChatSender::System,
+            text: text.into(),
+        }
+    }
+
+    /// Returns ID of the player who sent the message or [`None`] for system messages.
+    pub(crate) fn sender_id(&self) -> Option<u64> {
+        match self.sender {
+            ChatSender::Player { id, .. } => Some(id),
+            ChatSender::System => None,
+        }
+    }
+}
+
+impl Display for ChatMessage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self.sender {
+            ChatSender::Player { name, .. } => write!(f, "{name}: {}", self.text),
+            ChatSender::System => write!(f, "* {}", self.text),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+enum ChatSender {
+    /// Contains persistent ID and name of the [`Player`].
+    Player { id: u64, name: String },
+    /// Notifications from the server, such as players joining.
+    System,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit() {
+        let mut rate_limit = RateLimit::default();
+        let now = Duration::from_secs(10);
+        for _ in 0..MESSAGE_BURST as usize {
+            assert!(rate_limit.try_send(now));
+        }
+        assert!(!rate_limit.try_send(now));
+        assert!(!rate_limit.try_send(now + MESSAGE_REFILL / 2));
+        assert!(rate_limit.try_send(now + MESSAGE_REFILL));
+    }
+
+    #[test]
+    fn history_limit() {
+        let mut history = ChatHistory::default();
+        for index in 0..=ChatHistory::MAX_LEN {
+            history.push(ChatMessage::system(index.to_string()));
+        }
+        assert_eq!(history.iter().count(), ChatHistory::MAX_LEN);
+        assert_eq!(history.iter().next().unwrap().text, "1");
+    }
+}
@@ -1,6 +1,10 @@
-use std::net::{IpAddr, Ipv4Addr};
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use bevy::prelude::*;
 use bevy_replicon::prelude::*;
 use clap::{Args, Parser, Subcommand};
@@ -10,9 +14,18 @@ use super::{
     city::{ActiveCity, City},
     error::{self, ErrorReport},
     family::ActorFamily,
+    game_paths::GamePaths,
     game_state::GameState,
-    game_world::{GameLoad, WorldName},
-    network::{self, DEFAULT_PORT},
+    game_world::{
+        self,
+        recording::{RecordWorld, ReplayLoad},
+        GameLoad, WorldName,
+    },
+    network::{
+        self, discovery, ClientSettings, ServerSettings, DEFAULT_MAX_CLIENTS, DEFAULT_PORT,
+        DEFAULT_TOKEN_EXPIRATION,
+    },
+    settings::{NetworkConditions, PlayerSettings, Settings},
 };
 
 /// Logic for command line interface.
@@ -38,6 +51,8 @@ impl CliPlugin {
         mut commands: Commands,
         mut load_events: EventWriter<GameLoad>,
         cli: Res<Cli>,
+        game_paths: Res<GamePaths>,
+        settings: Res<Settings>,
         network_channels: Res<NetworkChannels>,
     ) -> Result<()> {
         if let Some(subcommand) = &cli.subcommand {
@@ -45,30 +60,75 @@ impl CliPlugin {
                 GameCommand::Play(world_load) => {
                     load_events.send_default();
                     commands.insert_resource(WorldName(world_load.world_name.clone()));
+                    if world_load.record {
+                        commands.init_resource::<RecordWorld>();
+                    }
                 }
-                GameCommand::Host { world_load, port } => {
+                GameCommand::Host {
+                    world_load,
+                    server_args,
+                } => {
+                    let server_settings = server_args.settings(&game_paths)?;
                     let (server, transport) = network::create_server(
-                        *port,
+                        &server_settings,
                         network_channels.server_channels(),
                         network_channels.client_channels(),
                     )
                     .context("unable to create server")?;
                     commands.insert_resource(server);
                     commands.insert_resource(transport);
+                    discovery::start_responder(&mut commands, &server_settings);
 
                     commands.insert_resource(WorldName(world_load.world_name.clone()));
                     load_events.send_default();
+                    if world_load.record {
+                        commands.init_resource::<RecordWorld>();
+                    }
                 }
-                GameCommand::Join { ip, port } => {
-                    let (client, transport) = network::create_client(
-                        *ip,
-                        *port,
+                GameCommand::Serve {
+                    world_name,
+                    record,
+                    server_args,
+                } => {
+                    let server_settings = server_args.settings(&game_paths)?;
+                    let (server, transport) = network::create_server(
+                        &server_settings,
                         network_channels.server_channels(),
                         network_channels.client_channels(),
                     )
-                    .context("unable to create client")?;
-                    commands.insert_resource(client);
+                    .context("unable to create server")?;
+                    commands.insert_resource(server);
                     commands.insert_resource(transport);
+                    discovery::start_responder(&mut commands, &server_settings);
+
+                    commands.insert_resource(WorldName(world_name.clone()));
+                    load_events.send_default();
+                    if *record {
+                        commands.init_resource::<RecordWorld>();
+                    }
+                    info!("serving world {world_name} on port {}", server_args.port);
+                }
+                GameCommand::Replay { recording } => {
+                    commands.insert_resource(ReplayLoad(recording.clone()));
+                }
+                GameCommand::World(_) | GameCommand::Family(_) | GameCommand::Token { .. } => {
+                    unreachable!("management commands should be handled without the game")
+                }
+                GameCommand::Join {
+                    ip,
+                    port,
+                    token,
+                    player_args,
+                    conditions_args,
+                } => {
+                    let client_settings = ClientSettings {
+                        ip: *ip,
+                        port: *port,
+                        connect_token: token.as_deref().map(network::read_token).transpose()?,
+                        player: player_args.settings(&settings.player),
+                        conditions: conditions_args.conditions(&settings.developer.network),
+                    };
+                    network::connect(&mut commands, &network_channels, client_settings)?;
                 }
             }
         }
@@ -149,6 +209,51 @@ pub(crate) struct Cli {
 }
 
 impl Cli {
+    /// Runs a command that manages game files if it was specified.
+    ///
+    /// Such commands don't need the game, so they should be checked before creating [`App`].
+    /// Returns `None` if the game should be started.
+    pub(crate) fn run_management(&self) -> Option<Result<()>> {
+        let game_paths = GamePaths::default();
+        match &self.subcommand {
+            Some(GameCommand::World(world_command)) => Some(world_command.run(&game_paths)),
+            Some(GameCommand::Family(family_command)) => Some(family_command.run(&game_paths)),
+            Some(GameCommand::Token {
+                public_ip,
+                port,
+                expire,
+                player_args,
+                output,
+            }) => {
+                let player = player_args.settings(&PlayerSettings {
+                    id: fastrand::u64(1..i64::MAX as u64),
+                    ..Default::default()
+                });
+                Some(issue_token(
+                    &game_paths,
+                    SocketAddr::new(*public_ip, *port),
+                    *expire,
+                    &player,
+                    output,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the game should run as a dedicated server without window.
+    pub(crate) fn is_dedicated_server(&self) -> bool {
+        matches!(
+            self.subcommand,
+            Some(GameCommand::Serve { .. } | GameCommand::Replay { .. })
+        )
+    }
+
+    /// Returns `true` if the game should replay recorded events.
+    pub(crate) fn is_replay(&self) -> bool {
+        matches!(self.subcommand, Some(GameCommand::Replay { .. }))
+    }
+
     /// Returns arguments for quick load if was specified from any subcommand.
     fn get_quick_load(&self) -> Option<&QuickLoad> {
         match &self.subcommand {
@@ -172,9 +277,26 @@ enum GameCommand {
         #[command(flatten)]
         world_load: WorldLoad,
 
-        /// Port to use.
-        #[clap(short, long, default_value_t = DEFAULT_PORT)]
-        port: u16,
+        #[command(flatten)]
+        server_args: ServerArgs,
+    },
+    /// Run a dedicated server without window and rendering.
+    Serve {
+        /// World name to load.
+        #[arg(short, long)]
+        world_name: String,
+
+        /// Record received client events for replay.
+        #[arg(long)]
+        record: bool,
+
+        #[command(flatten)]
+        server_args: ServerArgs,
+    },
+    /// Replay recorded events without window and check the result against the final snapshot.
+    Replay {
+        /// Directory with the recording.
+        recording: PathBuf,
     },
     Join {
         /// Server IP address.
@@ -184,7 +306,145 @@ enum GameCommand {
         /// Server port.
         #[clap(short, long, default_value_t = DEFAULT_PORT)]
         port: u16,
+
+        /// Connect token file issued by the server owner.
+        #[clap(short, long)]
+        token: Option<PathBuf>,
+
+        #[command(flatten)]
+        player_args: PlayerArgs,
+
+        #[command(flatten)]
+        conditions_args: ConditionsArgs,
+    },
+    /// Issue a connect token for servers started with `--secure`.
+    Token {
+        /// Address that clients use to connect.
+        #[clap(long, default_value_t = Ipv4Addr::LOCALHOST.into())]
+        public_ip: IpAddr,
+
+        /// Server port.
+        #[clap(short, long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+
+        /// Token lifetime in seconds.
+        #[clap(short, long, default_value_t = DEFAULT_TOKEN_EXPIRATION)]
+        expire: u64,
+
+        /// Identity of the player, ID is generated if not specified.
+        #[command(flatten)]
+        player_args: PlayerArgs,
+
+        /// File to write the token to.
+        output: PathBuf,
     },
+    /// Manage saved worlds without starting the game.
+    #[command(subcommand)]
+    World(WorldCommand),
+    /// Manage the families library without starting the game.
+    #[command(subcommand)]
+    Family(FamilyCommand),
+}
+
+/// Arguments for [`network::create_server`].
+#[derive(Args, Clone)]
+struct ServerArgs {
+    /// Port to use.
+    #[clap(short, long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+
+    /// Local address to listen on, use 0.0.0.0 to accept connections from other machines.
+    #[clap(long, default_value_t = Ipv4Addr::LOCALHOST.into())]
+    bind_ip: IpAddr,
+
+    /// Address that clients use to connect if it differs from the bind address.
+    #[clap(long)]
+    public_ip: Option<IpAddr>,
+
+    /// Maximum number of connected players.
+    #[clap(long, default_value_t = DEFAULT_MAX_CLIENTS)]
+    max_clients: usize,
+
+    /// Accept only clients with tokens from the `token` subcommand.
+    #[clap(long)]
+    secure: bool,
+}
+
+impl ServerArgs {
+    fn settings(&self, game_paths: &GamePaths) -> Result<ServerSettings> {
+        let private_key = if self.secure {
+            Some(network::read_private_key(&game_paths.server_key)?)
+        } else {
+            None
+        };
+
+        Ok(ServerSettings {
+            bind_ip: self.bind_ip,
+            public_ip: self.public_ip,
+            port: self.port,
+            max_clients: self.max_clients,
+            private_key,
+        })
+    }
+}
+
+/// Overrides for [`PlayerSettings`].
+///
+/// Useful to connect from the same machine under a different identity.
+#[derive(Args, Clone)]
+struct PlayerArgs {
+    /// Player name to use instead of the one from settings.
+    #[clap(long)]
+    player_name: Option<String>,
+
+    /// Player ID to use instead of the one from settings.
+    ///
+    /// 0 is reserved for the host.
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    player_id: Option<u64>,
+}
+
+impl PlayerArgs {
+    fn settings(&self, player: &PlayerSettings) -> PlayerSettings {
+        PlayerSettings {
+            name: self
+                .player_name
+                .clone()
+                .unwrap_or_else(|| player.name.clone()),
+            id: self.player_id.unwrap_or(player.id),
+        }
+    }
+}
+
+/// Overrides for [`NetworkConditions`] from developer settings.
+#[derive(Args, Clone)]
+struct ConditionsArgs {
+    /// Simulated delay in milliseconds for packets in each direction.
+    #[clap(long)]
+    latency: Option<u32>,
+
+    /// Maximum random deviation from the simulated latency in milliseconds.
+    #[clap(long)]
+    jitter: Option<u32>,
+
+    /// Probability of dropping a packet from 0 to 1.
+    #[clap(long)]
+    packet_loss: Option<f32>,
+
+    /// Probability of delivering a packet twice from 0 to 1.
+    #[clap(long)]
+    duplication: Option<f32>,
+}
+
+impl ConditionsArgs {
+    fn conditions(&self, conditions: &NetworkConditions) -> NetworkConditions {
+        NetworkConditions {
+            latency: self.latency.unwrap_or(conditions.latency),
+            jitter: self.jitter.unwrap_or(conditions.jitter),
+            packet_loss: self.packet_loss.unwrap_or(conditions.packet_loss),
+            duplication: self.duplication.unwrap_or(conditions.duplication),
+        }
+    }
 }
 
 /// Arguments for quick load.
@@ -194,6 +454,10 @@ struct WorldLoad {
     #[arg(short, long)]
     world_name: String,
 
+    /// Record received client events for replay.
+    #[arg(long)]
+    record: bool,
+
     /// City name to load.
     #[command(subcommand)]
     quick_load: Option<QuickLoad>,
@@ -204,3 +468,191 @@ enum QuickLoad {
     City { name: String },
     Family { name: String },
 }
+
+#[derive(Subcommand, Clone)]
+enum WorldCommand {
+    /// Print names of all saved worlds.
+    List,
+    /// Create an empty world.
+    Create { name: String },
+    /// Delete a world with its backups.
+    Delete { name: String },
+    /// Rename a world with its backups.
+    Rename { name: String, new_name: String },
+    /// Copy a world under a new name.
+    Copy { name: String, new_name: String },
+}
+
+impl WorldCommand {
+    fn run(&self, game_paths: &GamePaths) -> Result<()> {
+        match self {
+            WorldCommand::List => {
+                for (world_name, metadata) in game_world::read_worlds(game_paths)? {
+                    match metadata {
+                        Some(metadata) => println!(
+                            "{world_name}\t{} cities, {} families, {} actors, version {}",
+                            metadata.cities,
+                            metadata.families,
+                            metadata.actors,
+                            metadata.game_version
+                        ),
+                        None => println!("{world_name}"),
+                    }
+                }
+            }
+            WorldCommand::Create { name } => {
+                let settings = Settings::read(&game_paths.settings)?;
+                game_world::create_world(game_paths, name, &settings)?;
+            }
+            WorldCommand::Delete { name } => {
+                let world_path = existing_world_path(game_paths, name)?;
+                fs::remove_file(&world_path)
+                    .with_context(|| format!("unable to remove {world_path:?}"))?;
+                let backups_dir = game_paths.backups.join(name);
+                if backups_dir.exists() {
+                    fs::remove_dir_all(&backups_dir)
+                        .with_context(|| format!("unable to remove {backups_dir:?}"))?;
+                }
+            }
+            WorldCommand::Rename { name, new_name } => {
+                let (world_path, new_path) = new_world_path(game_paths, name, new_name)?;
+                fs::rename(&world_path, &new_path)
+                    .with_context(|| format!("unable to move {world_path:?} to {new_path:?}"))?;
+                let backups_dir = game_paths.backups.join(name);
+                if backups_dir.exists() {
+                    let new_backups_dir = game_paths.backups.join(new_name);
+                    fs::rename(&backups_dir, &new_backups_dir).with_context(|| {
+                        format!("unable to move {backups_dir:?} to {new_backups_dir:?}")
+                    })?;
+                }
+            }
+            WorldCommand::Copy { name, new_name } => {
+                let (world_path, new_path) = new_world_path(game_paths, name, new_name)?;
+                fs::copy(&world_path, &new_path)
+                    .with_context(|| format!("unable to copy {world_path:?} to {new_path:?}"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Subcommand, Clone)]
+enum FamilyCommand {
+    /// Print names of all families in the library.
+    List,
+    /// Delete a family from the library.
+    Delete { name: String },
+}
+
+impl FamilyCommand {
+    fn run(&self, game_paths: &GamePaths) -> Result<()> {
+        match self {
+            FamilyCommand::List => {
+                for family_name in game_paths.get_family_names()? {
+                    println!("{family_name}");
+                }
+            }
+            FamilyCommand::Delete { name } => {
+                let family_path = game_paths.family_path(name);
+                if !family_path.exists() {
+                    bail!("family {name} doesn't exist");
+                }
+                fs::remove_file(&family_path)
+                    .with_context(|| format!("unable to remove {family_path:?}"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a new connect token signed with the server key to the output file.
+fn issue_token(
+    game_paths: &GamePaths,
+    public_addr: SocketAddr,
+    expire: u64,
+    player: &PlayerSettings,
+    output: &Path,
+) -> Result<()> {
+    let private_key = network::read_private_key(&game_paths.server_key)?;
+    let connect_token = network::issue_token(&private_key, public_addr, expire, player)?;
+    network::write_token(&connect_token, output)?;
+    println!(
+        "issued token for player {} with ID {}",
+        player.name, player.id
+    );
+
+    Ok(())
+}
+
+fn existing_world_path(game_paths: &GamePaths, world_name: &str) -> Result<PathBuf> {
+    let world_path = game_paths.world_path(world_name);
+    if !world_path.exists() {
+        bail!("world {world_name} doesn't exist");
+    }
+
+    Ok(world_path)
+}
+
+/// Returns path to the existing world and path for it under the new name in the same format.
+fn new_world_path(
+    game_paths: &GamePaths,
+    world_name: &str,
+    new_name: &str,
+) -> Result<(PathBuf, PathBuf)> {
+    let world_path = existing_world_path(game_paths, world_name)?;
+    if game_paths.world_path(new_name).exists() {
+        bail!("world {new_name} already exists");
+    }
+    let extension = world_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .expect("world saves should have extension");
+    let new_path = game_paths.world_path_with_extension(new_name, extension);
+
+    Ok((world_path, new_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_management() -> Result<()> {
+        const WORLD_NAME: &str = "Test management";
+        const NEW_NAME: &str = "Renamed";
+        const COPY_NAME: &str = "Copied";
+        let game_paths = GamePaths::default();
+
+        WorldCommand::Create {
+            name: WORLD_NAME.to_string(),
+        }
+        .run(&game_paths)?;
+        assert!(WorldCommand::Create {
+            name: WORLD_NAME.to_string(),
+        }
+        .run(&game_paths)
+        .is_err());
+
+        WorldCommand::Rename {
+            name: WORLD_NAME.to_string(),
+            new_name: NEW_NAME.to_string(),
+        }
+        .run(&game_paths)?;
+        WorldCommand::Copy {
+            name: NEW_NAME.to_string(),
+            new_name: COPY_NAME.to_string(),
+        }
+        .run(&game_paths)?;
+        assert_eq!(game_paths.get_world_names()?, &[COPY_NAME, NEW_NAME]);
+
+        WorldCommand::Delete {
+            name: NEW_NAME.to_string(),
+        }
+        .run(&game_paths)?;
+        assert_eq!(game_paths.get_world_names()?, &[COPY_NAME]);
+
+        Ok(())
+    }
+}
@@ -1,13 +1,17 @@
 pub(crate) mod editor;
 pub(crate) mod family_spawn;
 
-use anyhow::Result;
+use std::{any::TypeId, fs};
+
+use anyhow::{Context, Result};
 use bevy::{
     ecs::{
         entity::{EntityMap, EntityMapper, MapEntities},
+        event::ManualEventReader,
         reflect::ReflectMapEntities,
     },
     prelude::*,
+    reflect::TypeRegistryInternal,
     utils::HashMap,
 };
 use bevy_replicon::prelude::*;
@@ -16,13 +20,19 @@ use serde::{Deserialize, Serialize};
 use strum::EnumIter;
 
 use super::{
-    actor::{ActiveActor, ActorBundle},
+    actor::{autonomy::FreeWill, needs::Need, ActiveActor, ActorBundle},
     component_commands::ComponentCommandsExt,
+    error,
+    game_paths::GamePaths,
     game_state::GameState,
-    game_world::WorldName,
+    game_world::{self, recording::AppRecordingExt, WorldName},
+    player::{Owner, Ownership},
 };
 use editor::EditorPlugin;
-use family_spawn::{FamilySpawn, FamilySpawnDeserializer, FamilySpawnSerializer};
+use family_spawn::{
+    FamilyScene, FamilySceneSerializer, FamilySpawn, FamilySpawnDeserializer,
+    FamilySpawnSerializer, SavedActor,
+};
 
 pub(crate) struct FamilyPlugin;
 
@@ -36,7 +46,10 @@ impl Plugin for FamilyPlugin {
             .replicate::<Budget>()
             .add_mapped_client_reflect_event::<FamilySpawn, FamilySpawnSerializer, FamilySpawnDeserializer>(SendPolicy::Unordered)
             .add_mapped_client_event::<FamilyDespawn>(SendPolicy::Unordered)
+            .record_mapped_client_reflect_event::<FamilySpawn, FamilySpawnSerializer, FamilySpawnDeserializer>()
+            .record_mapped_client_event::<FamilyDespawn>()
             .add_mapped_server_event::<SelectedFamilySpawned>(SendPolicy::Unordered)
+            .add_event::<FamilyExport>()
             .add_systems(OnEnter(GameState::Family), (Self::activation_system, Self::reset_mode_system))
             .add_systems(OnExit(GameState::Family), Self::deactivation_system)
             .add_systems(
@@ -44,6 +57,10 @@ impl Plugin for FamilyPlugin {
                 (
                     (Self::spawn_system, Self::despawn_system).run_if(has_authority()),
                     Self::members_update_system.run_if(resource_exists::<WorldName>()),
+                    Self::export_system
+                        .pipe(error::report)
+                        .run_if(on_event::<FamilyExport>())
+                        .run_if(resource_exists::<WorldName>()),
                     Self::cleanup_system.run_if(resource_removed::<WorldName>())
                 )
             );
@@ -94,10 +111,16 @@ impl FamilyPlugin {
         mut commands: Commands,
         mut spawn_select_events: EventWriter<ToClients<SelectedFamilySpawned>>,
         mut spawn_events: ResMut<Events<FromClient<FamilySpawn>>>,
+        ownership: Ownership,
     ) {
         for FromClient { client_id, event } in spawn_events.drain() {
+            let owner = Owner(ownership.player_id(client_id));
             let family_entity = commands
-                .spawn(FamilyBundle::new(event.scene.name, event.scene.budget))
+                .spawn(FamilyBundle::new(
+                    event.scene.name,
+                    event.scene.budget,
+                    owner,
+                ))
                 .id();
             for race_bundle in event.scene.actors {
                 commands.entity(event.city_entity).with_children(|parent| {
@@ -106,6 +129,21 @@ impl FamilyPlugin {
                         .insert_reflect_bundle(race_bundle.into_reflect());
                 });
             }
+            for saved_actor in event.scene.saved_actors {
+                commands.entity(event.city_entity).with_children(|parent| {
+                    parent
+                        .spawn(ActorBundle::new(family_entity))
+                        .insert_reflect(saved_actor.components)
+                        // Spawned needs prevent races from initializing the default ones.
+                        .with_children(|parent| {
+                            for components in saved_actor.needs {
+                                parent
+                                    .spawn((ParentSync::default(), Replication))
+                                    .insert_reflect(components);
+                            }
+                        });
+                });
+            }
             if event.select {
                 spawn_select_events.send(ToClients {
                     mode: SendMode::Direct(client_id),
@@ -118,9 +156,17 @@ impl FamilyPlugin {
     fn despawn_system(
         mut commands: Commands,
         mut despawn_events: EventReader<FromClient<FamilyDespawn>>,
+        ownership: Ownership,
         families: Query<(Entity, &mut FamilyMembers)>,
     ) {
-        for event in despawn_events.iter().map(|event| event.event) {
+        for &FromClient { client_id, event } in &mut despawn_events {
+            if !ownership.can_control(client_id, event.0) {
+                error!(
+                    "client {client_id} is not allowed to despawn family {:?}",
+                    event.0
+                );
+                continue;
+            }
             match families.get(event.0) {
                 Ok((family_entity, members)) => {
                     commands.entity(family_entity).despawn();
@@ -133,6 +179,68 @@ impl FamilyPlugin {
         }
     }
 
+    /// Exports families with the current state of their actors into [`GamePaths::families`].
+    fn export_system(
+        world: &World,
+        mut export_reader: Local<ManualEventReader<FamilyExport>>,
+        registry: Res<AppTypeRegistry>,
+        replication_rules: Res<ReplicationRules>,
+        game_paths: Res<GamePaths>,
+    ) -> Result<()> {
+        let registry = registry.read();
+        let export_events = world.resource::<Events<FamilyExport>>();
+        for event in export_reader.iter(export_events) {
+            let family_entity = world
+                .get_entity(event.0)
+                .with_context(|| format!("unable to get family {:?}", event.0))?;
+            let name = family_entity
+                .get::<Name>()
+                .with_context(|| format!("family {:?} doesn't have a name", event.0))?;
+            let budget = family_entity.get::<Budget>().copied().unwrap_or_default();
+            let members = family_entity
+                .get::<FamilyMembers>()
+                .with_context(|| format!("family {:?} doesn't have members", event.0))?;
+
+            let mut family_scene = FamilyScene::new(name.clone());
+            family_scene.budget = budget;
+            for &actor_entity in members.iter() {
+                let actor_entity = world
+                    .get_entity(actor_entity)
+                    .with_context(|| format!("unable to get actor {actor_entity:?}"))?;
+                let needs = actor_entity
+                    .get::<Children>()
+                    .into_iter()
+                    .flat_map(|children| children.iter())
+                    .filter_map(|&entity| world.get_entity(entity))
+                    .filter(|need_entity| need_entity.contains::<Need>())
+                    .map(|need_entity| {
+                        replicated_components(world, need_entity, &registry, &replication_rules)
+                    })
+                    .collect();
+                family_scene.saved_actors.push(SavedActor {
+                    components: replicated_components(
+                        world,
+                        actor_entity,
+                        &registry,
+                        &replication_rules,
+                    ),
+                    needs,
+                });
+            }
+
+            let serializer = FamilySceneSerializer::new(&family_scene, &registry);
+            let ron = ron::to_string(&serializer).context("unable to serialize family")?;
+
+            fs::create_dir_all(&game_paths.families)
+                .with_context(|| format!("unable to create {:?}", game_paths.families))?;
+            let family_path = game_paths.new_family_path(name);
+            game_world::write_atomically(&family_path, ron.as_bytes())?;
+            info!("exported family {:?} to {family_path:?}", event.0);
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn activation_system(
         mut commands: Commands,
         actors: Query<&ActorFamily, With<ActiveActor>>,
@@ -156,6 +264,49 @@ impl FamilyPlugin {
     }
 }
 
+/// Returns copies of replicated components that can be inserted into an entity in another world.
+///
+/// Components with entities and [`Transform`] are skipped because they make no sense outside
+/// of the current world.
+fn replicated_components(
+    world: &World,
+    entity: EntityRef,
+    registry: &TypeRegistryInternal,
+    replication_rules: &ReplicationRules,
+) -> Vec<Box<dyn Reflect>> {
+    let archetype = entity.archetype();
+    let mut components = Vec::new();
+    for component_id in archetype
+        .components()
+        .filter(|&component_id| replication_rules.is_replicated_component(archetype, component_id))
+    {
+        let Some(type_id) = world
+            .components()
+            .get_info(component_id)
+            .and_then(|component_info| component_info.type_id())
+        else {
+            continue;
+        };
+        if type_id == TypeId::of::<Transform>() {
+            continue;
+        }
+        let Some(registration) = registry.get(type_id) else {
+            continue;
+        };
+        if registration.data::<ReflectMapEntities>().is_some() {
+            continue;
+        }
+        if let Some(component) = registration
+            .data::<ReflectComponent>()
+            .and_then(|reflect_component| reflect_component.reflect(entity))
+        {
+            components.push(component.clone_value());
+        }
+    }
+
+    components
+}
+
 #[derive(
     States, Component, Clone, Copy, Debug, Eq, Hash, PartialEq, Display, EnumIter, Default,
 )]
@@ -197,15 +348,19 @@ struct FamilyBundle {
     name: Name,
     family: Family,
     budget: Budget,
+    owner: Owner,
+    free_will: FreeWill,
     replication: Replication,
 }
 
 impl FamilyBundle {
-    fn new(name: Name, budget: Budget) -> Self {
+    fn new(name: Name, budget: Budget, owner: Owner) -> Self {
         Self {
             name,
             family: Family,
             budget,
+            owner,
+            free_will: Default::default(),
             replication: Replication,
         }
     }
@@ -250,6 +405,10 @@ impl FromWorld for ActorFamily {
     }
 }
 
+/// Exports a family with its actors into [`GamePaths::families`].
+#[derive(Event)]
+pub(crate) struct FamilyExport(pub(crate) Entity);
+
 #[derive(Clone, Copy, Debug, Deserialize, Event, Serialize)]
 pub(crate) struct FamilyDespawn(pub(crate) Entity);
 
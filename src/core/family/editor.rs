@@ -85,7 +85,7 @@ impl EditorPlugin {
             let registry = registry.read();
             let serializer = FamilySceneSerializer::new(family_scene, &registry);
             let ron = ron::to_string(&serializer).expect("unable to serialize family scene");
-            let family_path = game_paths.family_path(&family_scene.name);
+            let family_path = game_paths.new_family_path(&family_scene.name);
             fs::write(&family_path, ron)
                 .with_context(|| format!("unable to save game to {family_path:?}"))?;
         }
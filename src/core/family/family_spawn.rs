@@ -1,8 +1,11 @@
 use std::{
     any,
     fmt::{self, Formatter},
+    fs,
+    path::Path,
 };
 
+use anyhow::{Context, Result};
 use bevy::{
     ecs::entity::EntityMap,
     prelude::*,
@@ -14,9 +17,9 @@ use bevy::{
 use bevy_replicon::prelude::*;
 use derive_more::Constructor;
 use serde::{
-    de::{self, DeserializeSeed, SeqAccess, Visitor},
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
     ser::{SerializeSeq, SerializeStruct},
-    Deserializer, Serialize, Serializer,
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 use strum::{EnumVariantNames, IntoStaticStr, VariantNames};
 
@@ -53,6 +56,8 @@ pub(crate) struct FamilyScene {
     pub(crate) name: Name,
     pub(crate) budget: Budget,
     pub(crate) actors: Vec<Box<dyn RaceBundle>>,
+    /// Actors exported from a world with their current state.
+    pub(crate) saved_actors: Vec<SavedActor>,
 }
 
 impl FamilyScene {
@@ -61,16 +66,46 @@ impl FamilyScene {
             name,
             budget: Default::default(),
             actors: Default::default(),
+            saved_actors: Default::default(),
         }
     }
+
+    /// Reads a family from the families library.
+    pub(crate) fn read(family_path: &Path, registry: &TypeRegistryInternal) -> Result<Self> {
+        let content = fs::read_to_string(family_path)
+            .with_context(|| format!("unable to read {family_path:?}"))?;
+        let mut deserializer = ron::Deserializer::from_str(&content)
+            .with_context(|| format!("unable to parse {family_path:?}"))?;
+        FamilySceneDeserializer::new(registry)
+            .deserialize(&mut deserializer)
+            .with_context(|| format!("unable to deserialize {family_path:?}"))
+    }
 }
 
-#[derive(IntoStaticStr, EnumVariantNames)]
+#[derive(Deserialize, EnumVariantNames, IntoStaticStr)]
 #[strum(serialize_all = "snake_case")]
+#[serde(field_identifier, rename_all = "snake_case")]
 enum FamilySceneField {
     Name,
     Budget,
     Actors,
+    SavedActors,
+}
+
+/// Replicated components of an actor and its needs.
+#[derive(Debug, Default)]
+pub(crate) struct SavedActor {
+    pub(crate) components: Vec<Box<dyn Reflect>>,
+    /// Components of each need entity.
+    pub(crate) needs: Vec<Vec<Box<dyn Reflect>>>,
+}
+
+#[derive(Deserialize, EnumVariantNames, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum SavedActorField {
+    Components,
+    Needs,
 }
 
 #[derive(Constructor)]
@@ -124,6 +159,10 @@ impl Serialize for FamilySceneSerializer<'_> {
             FamilySceneField::Actors.into(),
             &ActorsSerializer::new(&self.family_scene.actors, self.registry),
         )?;
+        state.serialize_field(
+            FamilySceneField::SavedActors.into(),
+            &SavedActorsSerializer::new(&self.family_scene.saved_actors, self.registry),
+        )?;
         state.end()
     }
 }
@@ -147,6 +186,65 @@ impl Serialize for ActorsSerializer<'_> {
     }
 }
 
+#[derive(Constructor)]
+struct SavedActorsSerializer<'a> {
+    saved_actors: &'a [SavedActor],
+    registry: &'a TypeRegistryInternal,
+}
+
+impl Serialize for SavedActorsSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.saved_actors.len()))?;
+        for saved_actor in self.saved_actors {
+            seq.serialize_element(&SavedActorSerializer::new(saved_actor, self.registry))?;
+        }
+        seq.end()
+    }
+}
+
+#[derive(Constructor)]
+struct SavedActorSerializer<'a> {
+    saved_actor: &'a SavedActor,
+    registry: &'a TypeRegistryInternal,
+}
+
+impl Serialize for SavedActorSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct(
+            any::type_name::<SavedActor>(),
+            SavedActorField::VARIANTS.len(),
+        )?;
+        state.serialize_field(
+            SavedActorField::Components.into(),
+            &ComponentsSerializer::new(&self.saved_actor.components, self.registry),
+        )?;
+        let needs: Vec<_> = self
+            .saved_actor
+            .needs
+            .iter()
+            .map(|components| ComponentsSerializer::new(components, self.registry))
+            .collect();
+        state.serialize_field(SavedActorField::Needs.into(), &needs)?;
+        state.end()
+    }
+}
+
+#[derive(Constructor)]
+struct ComponentsSerializer<'a> {
+    components: &'a [Box<dyn Reflect>],
+    registry: &'a TypeRegistryInternal,
+}
+
+impl Serialize for ComponentsSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.components.len()))?;
+        for component in self.components {
+            seq.serialize_element(&ReflectSerializer::new(&**component, self.registry))?;
+        }
+        seq.end()
+    }
+}
+
 pub(super) struct FamilySpawnDeserializer<'a> {
     registry: &'a TypeRegistryInternal,
 }
@@ -231,11 +329,69 @@ impl<'de> Visitor<'de> for FamilySceneDeserializer<'_> {
         let actors = seq
             .next_element_seed(ActorsDeserializer::new(self.registry))?
             .ok_or_else(|| de::Error::invalid_length(FamilySceneField::Actors as usize, &self))?;
+        let saved_actors = seq
+            .next_element_seed(SavedActorsDeserializer::new(self.registry))?
+            .ok_or_else(|| {
+                de::Error::invalid_length(FamilySceneField::SavedActors as usize, &self)
+            })?;
 
         Ok(FamilyScene {
             name,
             budget,
             actors,
+            saved_actors,
+        })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut name = None;
+        let mut budget = None;
+        let mut actors = None;
+        let mut saved_actors = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                FamilySceneField::Name => {
+                    if name.is_some() {
+                        return Err(de::Error::duplicate_field(FamilySceneField::Name.into()));
+                    }
+                    name = Some(map.next_value()?);
+                }
+                FamilySceneField::Budget => {
+                    if budget.is_some() {
+                        return Err(de::Error::duplicate_field(FamilySceneField::Budget.into()));
+                    }
+                    budget = Some(map.next_value()?);
+                }
+                FamilySceneField::Actors => {
+                    if actors.is_some() {
+                        return Err(de::Error::duplicate_field(FamilySceneField::Actors.into()));
+                    }
+                    actors = Some(map.next_value_seed(ActorsDeserializer::new(self.registry))?);
+                }
+                FamilySceneField::SavedActors => {
+                    if saved_actors.is_some() {
+                        return Err(de::Error::duplicate_field(
+                            FamilySceneField::SavedActors.into(),
+                        ));
+                    }
+                    saved_actors =
+                        Some(map.next_value_seed(SavedActorsDeserializer::new(self.registry))?);
+                }
+            }
+        }
+
+        let name = name.ok_or_else(|| de::Error::missing_field(FamilySceneField::Name.into()))?;
+        let budget =
+            budget.ok_or_else(|| de::Error::missing_field(FamilySceneField::Budget.into()))?;
+        let actors =
+            actors.ok_or_else(|| de::Error::missing_field(FamilySceneField::Actors.into()))?;
+
+        Ok(FamilyScene {
+            name,
+            budget,
+            actors,
+            // Families created in the editor have no saved state.
+            saved_actors: saved_actors.unwrap_or_default(),
         })
     }
 }
@@ -284,6 +440,170 @@ impl<'de> Visitor<'de> for ActorsDeserializer<'_> {
     }
 }
 
+#[derive(Constructor)]
+struct SavedActorsDeserializer<'a> {
+    registry: &'a TypeRegistryInternal,
+}
+
+impl<'de> DeserializeSeed<'de> for SavedActorsDeserializer<'_> {
+    type Value = Vec<SavedActor>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SavedActorsDeserializer<'_> {
+    type Value = Vec<SavedActor>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(any::type_name::<Self::Value>())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut saved_actors = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(saved_actor) =
+            seq.next_element_seed(SavedActorDeserializer::new(self.registry))?
+        {
+            saved_actors.push(saved_actor);
+        }
+
+        Ok(saved_actors)
+    }
+}
+
+#[derive(Constructor)]
+struct SavedActorDeserializer<'a> {
+    registry: &'a TypeRegistryInternal,
+}
+
+impl<'de> DeserializeSeed<'de> for SavedActorDeserializer<'_> {
+    type Value = SavedActor;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct(
+            any::type_name::<Self::Value>(),
+            SavedActorField::VARIANTS,
+            self,
+        )
+    }
+}
+
+impl<'de> Visitor<'de> for SavedActorDeserializer<'_> {
+    type Value = SavedActor;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(any::type_name::<Self::Value>())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let components = seq
+            .next_element_seed(ComponentsDeserializer::new(self.registry))?
+            .ok_or_else(|| {
+                de::Error::invalid_length(SavedActorField::Components as usize, &self)
+            })?;
+        let needs = seq
+            .next_element_seed(NeedsDeserializer::new(self.registry))?
+            .ok_or_else(|| de::Error::invalid_length(SavedActorField::Needs as usize, &self))?;
+
+        Ok(SavedActor { components, needs })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut components = None;
+        let mut needs = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                SavedActorField::Components => {
+                    if components.is_some() {
+                        return Err(de::Error::duplicate_field(
+                            SavedActorField::Components.into(),
+                        ));
+                    }
+                    components =
+                        Some(map.next_value_seed(ComponentsDeserializer::new(self.registry))?);
+                }
+                SavedActorField::Needs => {
+                    if needs.is_some() {
+                        return Err(de::Error::duplicate_field(SavedActorField::Needs.into()));
+                    }
+                    needs = Some(map.next_value_seed(NeedsDeserializer::new(self.registry))?);
+                }
+            }
+        }
+
+        let components = components
+            .ok_or_else(|| de::Error::missing_field(SavedActorField::Components.into()))?;
+        let needs = needs.ok_or_else(|| de::Error::missing_field(SavedActorField::Needs.into()))?;
+
+        Ok(SavedActor { components, needs })
+    }
+}
+
+#[derive(Constructor)]
+struct NeedsDeserializer<'a> {
+    registry: &'a TypeRegistryInternal,
+}
+
+impl<'de> DeserializeSeed<'de> for NeedsDeserializer<'_> {
+    type Value = Vec<Vec<Box<dyn Reflect>>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NeedsDeserializer<'_> {
+    type Value = Vec<Vec<Box<dyn Reflect>>>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(any::type_name::<Self::Value>())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut needs = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(components) =
+            seq.next_element_seed(ComponentsDeserializer::new(self.registry))?
+        {
+            needs.push(components);
+        }
+
+        Ok(needs)
+    }
+}
+
+#[derive(Constructor)]
+struct ComponentsDeserializer<'a> {
+    registry: &'a TypeRegistryInternal,
+}
+
+impl<'de> DeserializeSeed<'de> for ComponentsDeserializer<'_> {
+    type Value = Vec<Box<dyn Reflect>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentsDeserializer<'_> {
+    type Value = Vec<Box<dyn Reflect>>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(any::type_name::<Self::Value>())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut components = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(component) =
+            seq.next_element_seed(UntypedReflectDeserializer::new(self.registry))?
+        {
+            components.push(component);
+        }
+
+        Ok(components)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_test::Token;
@@ -301,6 +621,7 @@ mod tests {
                 name: NAME.into(),
                 budget: Budget(100),
                 actors: vec![Box::<DummyRaceBundle>::default()],
+                saved_actors: Vec::new(),
             },
             select: true,
         };
@@ -342,6 +663,9 @@ mod tests {
                 Token::StructEnd,
                 Token::MapEnd,
                 Token::SeqEnd,
+                Token::Str(FamilySceneField::SavedActors.into()),
+                Token::Seq { len: Some(0) },
+                Token::SeqEnd,
                 Token::StructEnd,
                 Token::Str(FamilySpawnField::Select.into()),
                 Token::Bool(family_spawn.select),
@@ -350,6 +674,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn saved_actors_ron() -> Result<()> {
+        let mut registry = TypeRegistryInternal::new();
+        registry.register::<DummyRaceBundle>();
+        registry.register::<DummyComponent>();
+        registry.register::<DummyNeed>();
+        let family_scene = FamilyScene {
+            name: "Dummy family".into(),
+            budget: Budget(100),
+            actors: Vec::new(),
+            saved_actors: vec![SavedActor {
+                components: vec![Box::new(DummyComponent)],
+                needs: vec![vec![Box::new(DummyNeed(42.0))]],
+            }],
+        };
+
+        let ron = ron::to_string(&FamilySceneSerializer::new(&family_scene, &registry))?;
+        let mut deserializer = ron::Deserializer::from_str(&ron)?;
+        let deserialized =
+            FamilySceneDeserializer::new(&registry).deserialize(&mut deserializer)?;
+
+        assert_eq!(deserialized.name, family_scene.name);
+        assert_eq!(deserialized.budget.0, family_scene.budget.0);
+        let [saved_actor] = deserialized.saved_actors.as_slice() else {
+            panic!("family should contain a single saved actor");
+        };
+        assert_eq!(saved_actor.components.len(), 1);
+        let need = DummyNeed::from_reflect(&*saved_actor.needs[0][0])
+            .expect("need should be deserialized");
+        assert_eq!(need.0, 42.0);
+
+        Ok(())
+    }
+
     #[derive(Reflect, Bundle, Default, Debug)]
     struct DummyRaceBundle {
         dummy: DummyComponent,
@@ -363,4 +721,7 @@ mod tests {
 
     #[derive(Component, Reflect, Default, Debug)]
     struct DummyComponent;
+
+    #[derive(Component, Reflect, Default, Debug)]
+    struct DummyNeed(f32);
 }
@@ -1,4 +1,8 @@
-use std::{env, fs::DirEntry, path::PathBuf};
+use std::{
+    env,
+    fs::DirEntry,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
 use bevy::prelude::*;
@@ -14,37 +18,114 @@ impl Plugin for GamePathsPlugin {
     }
 }
 
-const SCENE_EXTENSION: &str = "scn";
+/// Extension for RON scenes.
+pub(crate) const SCENE_EXTENSION: &str = "scn";
+
+/// Extension for binary world saves.
+pub(crate) const BINARY_SCENE_EXTENSION: &str = "bscn";
+
+/// Extension for lot blueprints.
+const BLUEPRINT_EXTENSION: &str = "ron";
+
+/// Extensions of all supported world save formats.
+const WORLD_EXTENSIONS: [&str; 2] = [BINARY_SCENE_EXTENSION, SCENE_EXTENSION];
 
 /// Paths with game files, such as settings and savegames.
-#[derive(Resource)]
+#[derive(Clone, Resource)]
 pub(crate) struct GamePaths {
     pub(crate) settings: PathBuf,
     pub(crate) worlds: PathBuf,
+    pub(crate) backups: PathBuf,
     pub(crate) families: PathBuf,
+    pub(crate) blueprints: PathBuf,
+    /// Recorded client events with world snapshots for replay.
+    pub(crate) recordings: PathBuf,
+    /// Private key for issuing and verifying connect tokens.
+    pub(crate) server_key: PathBuf,
 }
 
 impl GamePaths {
+    /// Returns path to the existing world save in any supported format.
+    ///
+    /// Points to a RON scene if the world doesn't exist.
     pub(crate) fn world_path(&self, world_name: &str) -> PathBuf {
+        WORLD_EXTENSIONS
+            .iter()
+            .map(|extension| self.world_path_with_extension(world_name, extension))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| self.world_path_with_extension(world_name, SCENE_EXTENSION))
+    }
+
+    pub(crate) fn world_path_with_extension(&self, world_name: &str, extension: &str) -> PathBuf {
         let mut path = self.worlds.join(world_name);
-        path.set_extension(SCENE_EXTENSION);
+        path.set_extension(extension);
         path
     }
 
+    /// Returns path to the backup slot of a world.
+    ///
+    /// Slots with lower index contain more recent backups.
+    pub(crate) fn backup_path(&self, world_name: &str, index: usize, extension: &str) -> PathBuf {
+        let mut path = self.backups.join(world_name).join(index.to_string());
+        path.set_extension(extension);
+        path
+    }
+
+    /// Returns paths to all backups of a world from the most recent.
+    pub(crate) fn get_backups(&self, world_name: &str) -> Result<Vec<PathBuf>> {
+        let backups_dir = self.backups.join(world_name);
+        if !backups_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries = backups_dir
+            .read_dir()
+            .with_context(|| format!("unable to read {backups_dir:?}"))?;
+        let mut backups = Vec::new();
+        for entry in entries.filter_map(Result::ok) {
+            // Backups are named by slot indices and use the same extensions as worlds.
+            let index = self::world_name(&entry).and_then(|stem| stem.parse::<usize>().ok());
+            if let Some(index) = index {
+                backups.push((index, entry.path()));
+            }
+        }
+        backups.sort_by_key(|&(index, _)| index);
+
+        Ok(backups.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// Returns path to the existing family.
     pub(crate) fn family_path(&self, family_name: &str) -> PathBuf {
         let mut path = self.families.join(family_name);
         path.set_extension(SCENE_EXTENSION);
+        path
+    }
 
-        let mut file_index = 0;
-        while path.exists() {
-            file_index += 1;
-            path.set_file_name(format!("{family_name} {file_index}"));
-            path.set_extension(SCENE_EXTENSION);
-        }
+    /// Returns path for a new family that doesn't overwrite existing ones.
+    pub(crate) fn new_family_path(&self, family_name: &str) -> PathBuf {
+        unique_path(&self.families, family_name, SCENE_EXTENSION)
+    }
+
+    pub(crate) fn get_family_names(&self) -> Result<Vec<String>> {
+        file_names(&self.families, SCENE_EXTENSION)
+    }
 
+    /// Returns path to the existing blueprint.
+    pub(crate) fn blueprint_path(&self, blueprint_name: &str) -> PathBuf {
+        let mut path = self.blueprints.join(blueprint_name);
+        path.set_extension(BLUEPRINT_EXTENSION);
         path
     }
 
+    /// Returns path for a new blueprint that doesn't overwrite existing ones.
+    pub(crate) fn new_blueprint_path(&self, blueprint_name: &str) -> PathBuf {
+        unique_path(&self.blueprints, blueprint_name, BLUEPRINT_EXTENSION)
+    }
+
+    pub(crate) fn get_blueprint_names(&self) -> Result<Vec<String>> {
+        file_names(&self.blueprints, BLUEPRINT_EXTENSION)
+    }
+
     pub(crate) fn get_world_names(&self) -> Result<Vec<String>> {
         let entries = self
             .worlds
@@ -56,6 +137,9 @@ impl GamePaths {
                 worlds.push(name);
             }
         }
+        // The same world could be saved in several formats.
+        worlds.sort();
+        worlds.dedup();
         Ok(worlds)
     }
 }
@@ -84,13 +168,30 @@ impl Default for GamePaths {
         let mut worlds = config_dir.clone();
         worlds.push("worlds");
 
-        let mut families = config_dir;
+        let mut backups = config_dir.clone();
+        backups.push("backups");
+
+        let mut families = config_dir.clone();
         families.push("families");
 
+        let mut blueprints = config_dir.clone();
+        blueprints.push("blueprints");
+
+        let mut recordings = config_dir.clone();
+        recordings.push("recordings");
+
+        let mut server_key = config_dir;
+        server_key.push("server");
+        server_key.set_extension("key");
+
         Self {
             settings,
             worlds,
+            backups,
             families,
+            blueprints,
+            recordings,
+            server_key,
         }
     }
 }
@@ -107,6 +208,49 @@ impl Drop for GamePaths {
     }
 }
 
+/// Returns path to a file in the directory, adding an index to the name if such file already exists.
+fn unique_path(dir: &Path, name: &str, extension: &str) -> PathBuf {
+    let mut path = dir.join(name);
+    path.set_extension(extension);
+
+    let mut file_index = 0;
+    while path.exists() {
+        file_index += 1;
+        path.set_file_name(format!("{name} {file_index}"));
+        path.set_extension(extension);
+    }
+
+    path
+}
+
+/// Returns sorted names of files with the extension in the directory.
+///
+/// Returns an empty list if the directory doesn't exist.
+fn file_names(dir: &Path, extension: &str) -> Result<Vec<String>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = dir
+        .read_dir()
+        .with_context(|| format!("unable to read {dir:?}"))?;
+    let mut names = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_file()
+            && path
+                .extension()
+                .map_or(false, |file_extension| file_extension == extension)
+        {
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
 fn world_name(entry: &DirEntry) -> Option<String> {
     let file_type = entry.file_type().ok()?;
     if !file_type.is_file() {
@@ -115,7 +259,10 @@ fn world_name(entry: &DirEntry) -> Option<String> {
 
     let path = entry.path();
     let extension = path.extension()?;
-    if extension != SCENE_EXTENSION {
+    if !WORLD_EXTENSIONS
+        .iter()
+        .any(|&world_extension| extension == world_extension)
+    {
         return None;
     }
 
@@ -134,11 +281,13 @@ mod tests {
         const FAMILY_NAME: &str = "Test family";
 
         fs::create_dir_all(&game_paths.families)?;
-        let family_path = game_paths.family_path(FAMILY_NAME);
+        let family_path = game_paths.new_family_path(FAMILY_NAME);
         File::create(&family_path)?;
-        let new_family_path = game_paths.family_path(FAMILY_NAME);
+        let new_family_path = game_paths.new_family_path(FAMILY_NAME);
 
         assert_ne!(family_path, new_family_path);
+        assert_eq!(family_path, game_paths.family_path(FAMILY_NAME));
+        assert_eq!(game_paths.get_family_names()?, &[FAMILY_NAME.to_string()]);
 
         Ok(())
     }
@@ -153,10 +302,52 @@ mod tests {
         File::create(game_paths.worlds.join("Not a world.txt"))?;
         File::create(game_paths.worlds.join(format!(".{SCENE_EXTENSION}")))?;
         File::create(game_paths.world_path(WORLD_NAME))?;
+        File::create(game_paths.world_path_with_extension(WORLD_NAME, BINARY_SCENE_EXTENSION))?;
 
         let world_names = game_paths.get_world_names()?;
         assert_eq!(world_names, &[WORLD_NAME]);
 
         Ok(())
     }
+
+    #[test]
+    fn blueprint_names_reading() -> Result<()> {
+        let game_paths = GamePaths::default();
+        const BLUEPRINT_NAME: &str = "Test blueprint";
+
+        assert!(game_paths.get_blueprint_names()?.is_empty());
+
+        fs::create_dir_all(&game_paths.blueprints)?;
+        File::create(game_paths.blueprints.join("Not a blueprint.txt"))?;
+        File::create(game_paths.new_blueprint_path(BLUEPRINT_NAME))?;
+        File::create(game_paths.new_blueprint_path(BLUEPRINT_NAME))?;
+
+        let blueprint_names = game_paths.get_blueprint_names()?;
+        assert_eq!(
+            blueprint_names,
+            &[BLUEPRINT_NAME.to_string(), format!("{BLUEPRINT_NAME} 1")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn backups_reading() -> Result<()> {
+        let game_paths = GamePaths::default();
+        const WORLD_NAME: &str = "Test backups";
+
+        assert!(game_paths.get_backups(WORLD_NAME)?.is_empty());
+
+        let first_backup = game_paths.backup_path(WORLD_NAME, 0, SCENE_EXTENSION);
+        let second_backup = game_paths.backup_path(WORLD_NAME, 2, BINARY_SCENE_EXTENSION);
+        fs::create_dir_all(game_paths.backups.join(WORLD_NAME))?;
+        File::create(&second_backup)?;
+        File::create(&first_backup)?;
+        File::create(game_paths.backups.join(WORLD_NAME).join("Not a backup.scn"))?;
+
+        let backups = game_paths.get_backups(WORLD_NAME)?;
+        assert_eq!(backups, &[first_backup, second_backup]);
+
+        Ok(())
+    }
 }
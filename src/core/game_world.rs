@@ -1,94 +1,374 @@
-use std::fs;
+pub(crate) mod backup;
+pub(crate) mod migration;
+pub(crate) mod recording;
+pub(crate) mod save_format;
+mod world_save;
+
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    time::Duration,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use bevy::{
+    app::AppExit,
     ecs::{archetype::ArchetypeId, component::ComponentId},
     prelude::*,
     reflect::TypeRegistryArc,
-    scene::{self, serde::SceneDeserializer, DynamicEntity},
+    scene::{self, DynamicEntity},
+    tasks::{IoTaskPool, Task},
+    time::Stopwatch,
 };
 use bevy_replicon::prelude::*;
-use serde::de::DeserializeSeed;
+use futures_lite::future;
 
-use super::{error, game_paths::GamePaths, game_state::GameState};
+use super::{
+    actor::Actor, city::City, error, family::Family, game_paths::GamePaths, game_state::GameState,
+    settings::Settings,
+};
+use migration::SaveMigrations;
+use recording::{RecordingPlugin, Replay};
+use save_format::SaveFormat;
+use world_save::{Leniency, WorldSaveDeserializer, WorldSaveSerializer};
+pub(crate) use world_save::{LoadReport, WorldMetadata};
 
 pub(crate) struct GameWorldPlugin;
 
 impl Plugin for GameWorldPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<IgnoreSaving>()
+        app.add_plugins(RecordingPlugin)
+            .init_resource::<IgnoreSaving>()
+            .init_resource::<SaveMigrations>()
+            .init_resource::<AutosaveStopwatch>()
+            .init_resource::<PlayTime>()
+            .init_resource::<SaveTask>()
             .add_event::<GameSave>()
+            .add_event::<GameSaved>()
+            .add_event::<GameSaveFailed>()
             .add_event::<GameLoad>()
+            .add_event::<GameLoadFailed>()
             .add_systems(
                 Update,
                 (
+                    Self::play_time_system
+                        .run_if(has_authority())
+                        .run_if(resource_exists::<WorldName>()),
+                    (Self::play_time_reset_system, Self::autosave_reset_system)
+                        .run_if(resource_removed::<WorldName>()),
+                    Self::autosave_system
+                        .run_if(has_authority())
+                        .run_if(resource_exists::<WorldName>())
+                        .run_if(not(resource_exists::<Replay>()))
+                        .before(Self::saving_system),
                     Self::loading_system
                         .pipe(error::report)
                         .run_if(on_event::<GameLoad>())
                         .before(scene::scene_spawner_system),
-                    Self::saving_system
+                    // Replays shouldn't overwrite anything next to the recording.
+                    Self::snapshot_system
+                        .pipe(Self::saving_system)
+                        .run_if(on_event::<GameSave>())
+                        .run_if(not(resource_exists::<Replay>())),
+                    Self::save_polling_system
                         .pipe(error::report)
-                        .run_if(on_event::<GameSave>()),
+                        .after(Self::saving_system),
                 ),
             );
     }
 }
 
 impl GameWorldPlugin {
-    /// Saves world to disk with the name from [`GameWorld`] resource.
-    pub(crate) fn saving_system(
+    /// Takes a snapshot of the world for [`Self::saving_system`].
+    fn snapshot_system(
         world: &World,
-        world_name: Res<WorldName>,
-        game_paths: Res<GamePaths>,
         registry: Res<AppTypeRegistry>,
         replication_rules: Res<ReplicationRules>,
         ignore_saving: Res<IgnoreSaving>,
-    ) -> Result<()> {
-        let world_path = game_paths.world_path(&world_name.0);
+    ) -> DynamicScene {
+        save_to_scene(world, &registry, &replication_rules, &ignore_saving)
+    }
+
+    /// Saves world snapshot to disk in background with the name from [`GameWorld`] resource.
+    ///
+    /// See also [`GameSaved`] and [`GameSaveFailed`].
+    pub(crate) fn saving_system(
+        In(scene): In<DynamicScene>,
+        mut save_events: EventReader<GameSave>,
+        mut save_task: ResMut<SaveTask>,
+        world_name: Res<WorldName>,
+        game_paths: Res<GamePaths>,
+        registry: Res<AppTypeRegistry>,
+        settings: Res<Settings>,
+        play_time: Res<PlayTime>,
+        cities: Query<(), With<City>>,
+        families: Query<(), With<Family>>,
+        actors: Query<(), With<Actor>>,
+    ) {
+        let format = SaveFormat::from_settings(&settings.saves);
+        let metadata = WorldMetadata::new(
+            play_time.0,
+            cities.iter().count(),
+            families.iter().count(),
+            actors.iter().count(),
+        );
+        let world_path = game_paths.world_path_with_extension(&world_name.0, format.extension());
+        // Save in a different format should replace the previous one.
+        let previous_path = game_paths.world_path(&world_name.0);
+        let registry = registry.0.clone();
+        let previous_task = save_task.0.take();
+        let backups = save_events
+            .iter()
+            .any(|event| event.backup)
+            .then_some(settings.saves.backups);
+        let game_paths = game_paths.clone();
+        let world_name = world_name.0.clone();
+
+        let task = IoTaskPool::get().spawn(async move {
+            // Wait for the previous save to avoid concurrent writes into the same file.
+            let previous_result = match previous_task {
+                Some(previous_task) => previous_task.await,
+                None => Ok(()),
+            };
+
+            let backup_result = match backups {
+                Some(slots) => backup::rotate(&game_paths, &world_name, slots)
+                    .context("unable to backup world before saving"),
+                None => Ok(()),
+            };
+
+            let result = write_world(
+                format,
+                &metadata,
+                &scene,
+                &registry,
+                &world_path,
+                &previous_path,
+            );
+            result.and(backup_result).and(previous_result)
+        });
+        save_task.0 = Some(task);
+    }
 
-        fs::create_dir_all(&game_paths.worlds)
-            .with_context(|| format!("unable to create {world_path:?}"))?;
+    /// Reports result of the save started by [`Self::saving_system`].
+    ///
+    /// Waits for the save if the app is about to exit.
+    fn save_polling_system(
+        mut saved_events: EventWriter<GameSaved>,
+        mut save_failed_events: EventWriter<GameSaveFailed>,
+        mut exit_events: EventReader<AppExit>,
+        mut save_task: ResMut<SaveTask>,
+    ) -> Result<()> {
+        let Some(task) = save_task.0.as_mut() else {
+            return Ok(());
+        };
 
-        let scene = save_to_scene(world, &registry, &replication_rules, &ignore_saving);
-        let bytes = scene
-            .serialize_ron(&registry)
-            .expect("game world should be serialized");
+        let result = if exit_events.is_empty() {
+            let Some(result) = future::block_on(future::poll_once(task)) else {
+                return Ok(());
+            };
+            result
+        } else {
+            exit_events.clear();
+            future::block_on(task)
+        };
+        save_task.0 = None;
 
-        fs::write(&world_path, bytes)
-            .with_context(|| format!("unable to save game to {world_path:?}"))
+        match result {
+            Ok(()) => {
+                saved_events.send_default();
+                Ok(())
+            }
+            Err(e) => {
+                save_failed_events.send_default();
+                Err(e)
+            }
+        }
     }
 
     /// Loads world from disk with the name from [`GameWorld`] resource.
     pub(crate) fn loading_system(
+        mut commands: Commands,
         mut scene_spawner: ResMut<SceneSpawner>,
         mut scenes: ResMut<Assets<DynamicScene>>,
         mut game_state: ResMut<NextState<GameState>>,
+        mut load_failed_events: EventWriter<GameLoadFailed>,
+        mut play_time: ResMut<PlayTime>,
         world_name: Res<WorldName>,
         game_paths: Res<GamePaths>,
         registry: Res<AppTypeRegistry>,
+        migrations: Res<SaveMigrations>,
+        settings: Res<Settings>,
     ) -> Result<()> {
         let world_path = game_paths.world_path(&world_name.0);
         let bytes =
             fs::read(&world_path).with_context(|| format!("unable to load {world_path:?}"))?;
-        let mut deserializer = ron::Deserializer::from_bytes(&bytes)
-            .with_context(|| format!("unable to parse {world_path:?}"))?;
-        let scene_deserializer = SceneDeserializer {
-            type_registry: &registry.read(),
+        let registry = registry.read();
+        let leniency = Leniency::default();
+        let mut deserializer = WorldSaveDeserializer::new(&registry, &migrations);
+        if settings.saves.lenient_loading {
+            deserializer = deserializer.with_leniency(&leniency);
+        }
+        let result = SaveFormat::detect(&bytes).deserialize(&bytes, deserializer);
+        let mut world_save = match result {
+            Ok(world_save) => world_save,
+            Err(e) => {
+                load_failed_events.send_default();
+                return Err(e.context(format!("unable to deserialize {world_path:?}")));
+            }
         };
-        let mut scene = scene_deserializer
-            .deserialize(&mut deserializer)
-            .with_context(|| format!("unable to deserialize {world_path:?}"))?;
+
+        if let Some(metadata) = world_save.metadata {
+            play_time.0 = metadata.play_time;
+        }
+
+        let report = leniency.into_report();
+        if !report.skipped.is_empty() {
+            warn!("{world_path:?} loaded with {report}");
+            commands.insert_resource(report);
+        }
 
         // All saved entities should have `Replication` component.
-        for entity in &mut scene.entities {
+        for entity in &mut world_save.scene.entities {
             entity.components.push(Replication.clone_value());
         }
 
-        scene_spawner.spawn_dynamic(scenes.add(scene));
+        scene_spawner.spawn_dynamic(scenes.add(world_save.scene));
         game_state.set(GameState::World);
 
         Ok(())
     }
+
+    fn play_time_system(mut play_time: ResMut<PlayTime>, time: Res<Time>) {
+        play_time.0 += time.delta();
+    }
+
+    fn play_time_reset_system(mut play_time: ResMut<PlayTime>) {
+        play_time.0 = Duration::ZERO;
+    }
+
+    fn autosave_reset_system(mut autosave_stopwatch: ResMut<AutosaveStopwatch>) {
+        autosave_stopwatch.reset();
+    }
+
+    /// Saves world periodically, keeping previous saves as backups.
+    fn autosave_system(
+        mut save_events: EventWriter<GameSave>,
+        mut autosave_stopwatch: ResMut<AutosaveStopwatch>,
+        time: Res<Time>,
+        settings: Res<Settings>,
+    ) {
+        if settings.saves.autosave_interval == 0 {
+            return;
+        }
+
+        autosave_stopwatch.tick(time.delta());
+        if autosave_stopwatch.elapsed_secs() < settings.saves.autosave_interval as f32 {
+            return;
+        }
+
+        autosave_stopwatch.reset();
+        save_events.send(GameSave { backup: true });
+    }
+}
+
+/// Serializes the world save and writes it to disk, removing the previous save at a different path.
+fn write_world(
+    format: SaveFormat,
+    metadata: &WorldMetadata,
+    scene: &DynamicScene,
+    registry: &TypeRegistryArc,
+    world_path: &Path,
+    previous_path: &Path,
+) -> Result<()> {
+    let bytes = format
+        .serialize(&WorldSaveSerializer::new(metadata, scene, &registry.read()))
+        .with_context(|| format!("unable to serialize {world_path:?}"))?;
+
+    let worlds_dir = world_path
+        .parent()
+        .expect("world path should have a parent dir");
+    fs::create_dir_all(worlds_dir).with_context(|| format!("unable to create {worlds_dir:?}"))?;
+    write_atomically(world_path, &bytes)
+        .with_context(|| format!("unable to save game to {world_path:?}"))?;
+
+    if previous_path != world_path && previous_path.exists() {
+        fs::remove_file(previous_path)
+            .with_context(|| format!("unable to remove {previous_path:?}"))?;
+    }
+
+    Ok(())
+}
+
+/// Writes a world without entities, returns an error if such world already exists.
+pub(crate) fn create_world(
+    game_paths: &GamePaths,
+    world_name: &str,
+    settings: &Settings,
+) -> Result<()> {
+    let previous_path = game_paths.world_path(world_name);
+    if previous_path.exists() {
+        bail!("world {world_name} already exists");
+    }
+
+    let format = SaveFormat::from_settings(&settings.saves);
+    let metadata = WorldMetadata::new(Duration::ZERO, 0, 0, 0);
+    let world_path = game_paths.world_path_with_extension(world_name, format.extension());
+    write_world(
+        format,
+        &metadata,
+        &DynamicScene::default(),
+        &TypeRegistryArc::default(),
+        &world_path,
+        &previous_path,
+    )
+}
+
+/// Writes data into a temporary file and then renames it to the specified path.
+///
+/// Guarantees that the file at the path is either the previous or the new version, even on crash.
+pub(crate) fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut temp_name = path
+        .file_name()
+        .map(OsString::from)
+        .expect("path should point to a file");
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file =
+        File::create(&temp_path).with_context(|| format!("unable to create {temp_path:?}"))?;
+    file.write_all(bytes)
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("unable to write {temp_path:?}"))?;
+    fs::rename(&temp_path, path)
+        .with_context(|| format!("unable to move {temp_path:?} to {path:?}"))
+}
+
+/// Reads names and metadata of all saved worlds, from the most recently saved.
+///
+/// Worlds without metadata are placed at the end.
+pub(crate) fn read_worlds(game_paths: &GamePaths) -> Result<Vec<(String, Option<WorldMetadata>)>> {
+    let mut worlds: Vec<_> = game_paths
+        .get_world_names()?
+        .into_iter()
+        .map(|world_name| {
+            let world_path = game_paths.world_path(&world_name);
+            let metadata = save_format::read_metadata(&world_path)
+                .map_err(|e| error!("unable to read metadata for {world_path:?}: {e:#}"))
+                .unwrap_or_default();
+            (world_name, metadata)
+        })
+        .collect();
+
+    worlds.sort_by(|(name_a, metadata_a), (name_b, metadata_b)| {
+        let saved_a = metadata_a.as_ref().map(|metadata| metadata.saved_at);
+        let saved_b = metadata_b.as_ref().map(|metadata| metadata.saved_at);
+        saved_b.cmp(&saved_a).then_with(|| name_a.cmp(name_b))
+    });
+
+    Ok(worlds)
 }
 
 /// Iterates over a world and serializes all components that implement [`Reflect`]
@@ -150,7 +430,20 @@ fn save_to_scene(
 
 /// Event that indicates that game is about to be saved to the file name based on [`GameWorld`] resource.
 #[derive(Default, Event)]
-pub(crate) struct GameSave;
+pub(crate) struct GameSave {
+    /// Rotate backups before writing, the previous save will be copied into the first slot.
+    pub(crate) backup: bool,
+}
+
+/// Event that indicates that the save from [`GameSave`] was written to disk.
+#[derive(Default, Event)]
+pub(crate) struct GameSaved;
+
+/// Event that indicates that the save from [`GameSave`] failed.
+///
+/// The error is reported separately.
+#[derive(Default, Event)]
+pub(crate) struct GameSaveFailed;
 
 /// Event that indicates that game is about to be loaded from the file name based on [`GameWorld`] resource.
 ///
@@ -158,10 +451,26 @@ pub(crate) struct GameSave;
 #[derive(Default, Event)]
 pub(crate) struct GameLoad;
 
+/// Event that indicates that the world from [`GameLoad`] failed to deserialize.
+#[derive(Default, Event)]
+pub(crate) struct GameLoadFailed;
+
 /// Contains name of the currently loaded world.
 #[derive(Default, Resource)]
 pub(crate) struct WorldName(pub(crate) String);
 
+/// Accumulated time spent in the current world.
+#[derive(Default, Resource)]
+pub(crate) struct PlayTime(pub(crate) Duration);
+
+/// Save that is currently being written in background.
+#[derive(Default, Deref, Resource)]
+pub(crate) struct SaveTask(Option<Task<Result<()>>>);
+
+/// Measures time since the last autosave.
+#[derive(Default, Deref, DerefMut, Resource)]
+struct AutosaveStopwatch(Stopwatch);
+
 /// Contains component IDs that will be ignored on game world serialization.
 #[derive(Default, Deref, DerefMut, Resource)]
 pub(crate) struct IgnoreSaving(Vec<ComponentId>);
@@ -202,10 +511,12 @@ mod tests {
             .replicate::<City>()
             .not_replicate_if_present::<Transform, City>()
             .init_resource::<GamePaths>()
+            .init_resource::<Settings>()
             .insert_resource(WorldName(WORLD_NAME.to_string()))
             .add_plugins((
                 TaskPoolPlugin::default(),
                 TypeRegistrationPlugin,
+                TimePlugin,
                 AssetPlugin::default(),
                 ScenePlugin,
                 TransformPlugin,
@@ -220,6 +531,14 @@ mod tests {
 
         app.update();
 
+        while app.world.resource::<SaveTask>().is_some() {
+            app.update();
+        }
+        assert!(
+            !app.world.resource::<Events<GameSaved>>().is_empty(),
+            "game should be saved"
+        );
+
         app.world.clear_entities();
 
         app.update();
@@ -0,0 +1,89 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+
+use crate::core::game_paths::GamePaths;
+
+/// Moves existing backups of a world to the next slots and copies the current save into the first one.
+///
+/// Backups that don't fit into the specified number of slots are removed.
+pub(super) fn rotate(game_paths: &GamePaths, world_name: &str, slots: usize) -> Result<()> {
+    let world_path = game_paths.world_path(world_name);
+    if slots == 0 || !world_path.exists() {
+        return Ok(());
+    }
+
+    let backups = game_paths.get_backups(world_name)?;
+    for (index, backup_path) in backups.iter().enumerate().rev() {
+        if index + 1 >= slots {
+            fs::remove_file(backup_path)
+                .with_context(|| format!("unable to remove {backup_path:?}"))?;
+        } else {
+            let new_path = game_paths.backup_path(world_name, index + 1, extension(backup_path));
+            fs::rename(backup_path, &new_path)
+                .with_context(|| format!("unable to move {backup_path:?} to {new_path:?}"))?;
+        }
+    }
+
+    let backup_path = game_paths.backup_path(world_name, 0, extension(&world_path));
+    let backups_dir = backup_path
+        .parent()
+        .expect("backup path should have a parent dir");
+    fs::create_dir_all(backups_dir).with_context(|| format!("unable to create {backups_dir:?}"))?;
+    fs::copy(&world_path, &backup_path)
+        .with_context(|| format!("unable to copy {world_path:?} to {backup_path:?}"))?;
+
+    Ok(())
+}
+
+/// Replaces world save with the specified backup.
+pub(crate) fn restore(game_paths: &GamePaths, world_name: &str, backup_path: &Path) -> Result<()> {
+    let previous_path = game_paths.world_path(world_name);
+    let world_path = game_paths.world_path_with_extension(world_name, extension(backup_path));
+    fs::copy(backup_path, &world_path)
+        .with_context(|| format!("unable to restore {world_path:?} from {backup_path:?}"))?;
+
+    // Backup could be in a different format.
+    if previous_path != world_path && previous_path.exists() {
+        fs::remove_file(&previous_path)
+            .with_context(|| format!("unable to remove {previous_path:?}"))?;
+    }
+
+    Ok(())
+}
+
+fn extension(path: &Path) -> &str {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .expect("world saves should have extension")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation() -> Result<()> {
+        const WORLD_NAME: &str = "Test rotation";
+        const SLOTS: usize = 2;
+        let game_paths = GamePaths::default();
+        fs::create_dir_all(&game_paths.worlds)?;
+
+        let world_path = game_paths.world_path(WORLD_NAME);
+        for content in ["first", "second", "third"] {
+            fs::write(&world_path, content)?;
+            rotate(&game_paths, WORLD_NAME, SLOTS)?;
+        }
+
+        let backups = game_paths.get_backups(WORLD_NAME)?;
+        assert_eq!(backups.len(), SLOTS);
+        assert_eq!(fs::read_to_string(&backups[0])?, "third");
+        assert_eq!(fs::read_to_string(&backups[1])?, "second");
+
+        fs::write(&world_path, "broken")?;
+        restore(&game_paths, WORLD_NAME, &backups[1])?;
+        assert_eq!(fs::read_to_string(&world_path)?, "second");
+
+        Ok(())
+    }
+}
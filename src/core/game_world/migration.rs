@@ -0,0 +1,182 @@
+use std::{any, sync::Arc};
+
+use bevy::{prelude::*, reflect::GetTypeRegistration};
+use serde::de;
+
+use super::world_save::SAVE_VERSION;
+
+/// Registered migration steps for world saves.
+#[derive(Default, Resource, Clone)]
+pub(crate) struct SaveMigrations(Vec<Migration>);
+
+impl SaveMigrations {
+    /// Resolves how the component stored under `type_name` in a save of the specified `version`
+    /// should be read.
+    ///
+    /// Returns [`None`] if the component was removed.
+    pub(super) fn resolve(
+        &self,
+        version: u32,
+        mut type_name: String,
+    ) -> Option<ComponentMigration> {
+        let mut conversion = None;
+        for migration in self
+            .0
+            .iter()
+            .filter(|migration| migration.version > version)
+        {
+            match &migration.kind {
+                MigrationKind::Rename { from, to } => {
+                    if type_name == *from {
+                        type_name = to.to_string();
+                    }
+                }
+                MigrationKind::Remove(removed_name) => {
+                    if type_name == *removed_name {
+                        return None;
+                    }
+                }
+                MigrationKind::Convert {
+                    legacy_name,
+                    type_name: converted_name,
+                    convert,
+                } => {
+                    // Stored data has the layout of the oldest legacy type and
+                    // every conversion produces the current type, so later ones are skipped.
+                    if conversion.is_none() && type_name == *converted_name {
+                        conversion = Some((legacy_name.to_string(), convert.clone()));
+                    }
+                }
+            }
+        }
+
+        let (read_as, convert) = match conversion {
+            Some((legacy_name, convert)) => (legacy_name, Some(convert)),
+            None => (type_name, None),
+        };
+
+        Some(ComponentMigration { read_as, convert })
+    }
+
+    fn push(&mut self, version: u32, kind: MigrationKind) {
+        assert!(
+            version <= SAVE_VERSION,
+            "migration version {version} should not exceed the current save version {SAVE_VERSION}"
+        );
+        self.0.push(Migration { version, kind });
+        // Steps should be applied in the order of versions, keeping the registration order for equal ones.
+        self.0.sort_by_key(|migration| migration.version);
+    }
+}
+
+/// A single migration step.
+#[derive(Clone)]
+struct Migration {
+    /// Save version in which the change was introduced.
+    ///
+    /// Applied only to saves with a lower version.
+    version: u32,
+    kind: MigrationKind,
+}
+
+#[derive(Clone)]
+enum MigrationKind {
+    Rename {
+        from: &'static str,
+        to: &'static str,
+    },
+    Remove(&'static str),
+    Convert {
+        legacy_name: &'static str,
+        type_name: &'static str,
+        convert: ConvertFn,
+    },
+}
+
+type ConvertFn = Arc<dyn Fn(&dyn Reflect) -> Option<Box<dyn Reflect>> + Send + Sync>;
+
+/// Describes how to read a single stored component.
+pub(super) struct ComponentMigration {
+    /// Type name whose registration should be used for deserialization.
+    pub(super) read_as: String,
+    /// Conversion from [`Self::read_as`] into the current type.
+    convert: Option<ConvertFn>,
+}
+
+impl ComponentMigration {
+    /// Converts component deserialized as [`Self::read_as`] into the current version.
+    pub(super) fn apply<E: de::Error>(
+        &self,
+        component: Box<dyn Reflect>,
+    ) -> Result<Box<dyn Reflect>, E> {
+        let Some(convert) = &self.convert else {
+            return Ok(component);
+        };
+
+        convert(&*component).ok_or_else(|| {
+            de::Error::custom(format!("unable to convert {}", component.type_name()))
+        })
+    }
+}
+
+pub(crate) trait AppMigrationExt {
+    /// Renames component type `from` into `to` for saves older than `version`.
+    fn rename_component(&mut self, version: u32, from: &'static str, to: &'static str)
+        -> &mut Self;
+
+    /// Drops component with the specified type name from saves older than `version`.
+    fn remove_component(&mut self, version: u32, type_name: &'static str) -> &mut Self;
+
+    /// Reads component `T` from saves older than `version` as `L` and converts it using `convert`.
+    ///
+    /// `L` is a copy of the component layout before the change and will be registered automatically.
+    /// If the component was converted several times, only the oldest conversion newer than the save is applied,
+    /// so each conversion should produce the current `T`.
+    fn convert_component<L, T>(&mut self, version: u32, convert: fn(L) -> T) -> &mut Self
+    where
+        L: Reflect + FromReflect + GetTypeRegistration,
+        T: Component + Reflect;
+}
+
+impl AppMigrationExt for App {
+    fn rename_component(
+        &mut self,
+        version: u32,
+        from: &'static str,
+        to: &'static str,
+    ) -> &mut Self {
+        self.init_resource::<SaveMigrations>();
+        self.world
+            .resource_mut::<SaveMigrations>()
+            .push(version, MigrationKind::Rename { from, to });
+        self
+    }
+
+    fn remove_component(&mut self, version: u32, type_name: &'static str) -> &mut Self {
+        self.init_resource::<SaveMigrations>();
+        self.world
+            .resource_mut::<SaveMigrations>()
+            .push(version, MigrationKind::Remove(type_name));
+        self
+    }
+
+    fn convert_component<L, T>(&mut self, version: u32, convert: fn(L) -> T) -> &mut Self
+    where
+        L: Reflect + FromReflect + GetTypeRegistration,
+        T: Component + Reflect,
+    {
+        self.register_type::<L>().init_resource::<SaveMigrations>();
+        self.world.resource_mut::<SaveMigrations>().push(
+            version,
+            MigrationKind::Convert {
+                legacy_name: any::type_name::<L>(),
+                type_name: any::type_name::<T>(),
+                convert: Arc::new(move |reflect| {
+                    L::from_reflect(reflect)
+                        .map(|legacy| Box::new(convert(legacy)) as Box<dyn Reflect>)
+                }),
+            },
+        );
+        self
+    }
+}
@@ -0,0 +1,668 @@
+use std::{
+    any,
+    collections::{HashMap, HashSet},
+    fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use bevy::{
+    app::AppExit,
+    ecs::{entity::EntityMap, reflect::ReflectMapEntities},
+    prelude::*,
+    reflect::{serde::ReflectSerializer, TypeRegistryInternal},
+    time::{TimeSystem, TimeUpdateStrategy},
+};
+use bevy_replicon::prelude::*;
+use bincode::{DefaultOptions, Options};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed},
+    Deserialize, Serialize,
+};
+
+use super::{
+    migration::SaveMigrations, save_format::SaveFormat, world_save::WorldSaveDeserializer,
+    GameSave, IgnoreSaving, PlayTime, WorldMetadata, WorldName,
+};
+use crate::core::{
+    actor::Actor,
+    city::City,
+    error,
+    family::Family,
+    game_paths::{GamePaths, BINARY_SCENE_EXTENSION},
+    game_state::GameState,
+    player::Player,
+    settings::Settings,
+};
+
+/// Records client events received by the server and replays them headlessly.
+///
+/// Recording starts with a snapshot of the loaded world.
+/// On each save and on exit the recorded events are written together with the final snapshot.
+/// Replay loads the starting snapshot, sends the events on the same frames with the same frame durations
+/// and compares the result with the final snapshot.
+pub(super) struct RecordingPlugin;
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EventReplayers>()
+            .add_systems(
+                First,
+                (
+                    (
+                        Self::replay_loading_system
+                            .pipe(error::report)
+                            .run_if(resource_exists::<ReplayLoad>()),
+                        Self::replay_frame_system.run_if(resource_exists::<Replay>()),
+                    )
+                        .chain()
+                        .before(TimeSystem),
+                    (
+                        Self::frame_system.run_if(resource_exists::<Recording>()),
+                        Self::start_system
+                            .pipe(error::report)
+                            .run_if(resource_exists::<RecordWorld>())
+                            .run_if(resource_exists::<WorldName>())
+                            .run_if(in_state(GameState::World)),
+                    )
+                        .chain()
+                        .after(TimeSystem),
+                ),
+            )
+            .add_systems(
+                PreUpdate,
+                Self::replay_system
+                    .pipe(error::report)
+                    .after(ServerSet::Receive)
+                    .run_if(resource_exists::<Replay>()),
+            )
+            .add_systems(
+                Last,
+                (
+                    (
+                        Self::spawn_system,
+                        Self::checkpoint_system
+                            .pipe(error::report)
+                            .run_if(on_event::<GameSave>().or_else(on_event::<AppExit>())),
+                    )
+                        .chain()
+                        .run_if(resource_exists::<Recording>()),
+                    Self::stop_system.run_if(resource_removed::<WorldName>()),
+                    (
+                        Self::replay_spawn_system,
+                        Self::replay_finish_system.pipe(error::report),
+                    )
+                        .chain()
+                        .run_if(resource_exists::<Replay>()),
+                ),
+            );
+    }
+}
+
+impl RecordingPlugin {
+    /// Writes the starting snapshot and starts recording.
+    fn start_system(world: &mut World) -> Result<()> {
+        world.remove_resource::<RecordWorld>();
+
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let dir = world
+            .resource::<GamePaths>()
+            .recordings
+            .join(&world.resource::<WorldName>().0)
+            .join(started_at.as_secs().to_string());
+        write_snapshot(world, &snapshot_path(&dir, START_SNAPSHOT))?;
+
+        let initial_entities = world
+            .query_filtered::<Entity, With<Replication>>()
+            .iter(world)
+            .collect();
+        let log = EventLog {
+            host_player_id: world.resource::<Settings>().player.id,
+            frames: vec![world.resource::<Time>().delta()],
+            ..Default::default()
+        };
+
+        info!("recording client events to {dir:?}");
+        world.insert_resource(Recording {
+            dir,
+            initial_entities,
+            elapsed: Duration::ZERO,
+            log,
+        });
+
+        Ok(())
+    }
+
+    fn frame_system(mut recording: ResMut<Recording>, time: Res<Time>) {
+        recording.elapsed += time.delta();
+        recording.log.frames.push(time.delta());
+    }
+
+    /// Remembers the order of spawned replicated entities to map them on replay.
+    ///
+    /// Players are ignored because connections aren't replayed.
+    fn spawn_system(
+        mut recording: ResMut<Recording>,
+        entities: Query<Entity, (Added<Replication>, Without<Player>)>,
+    ) {
+        for entity in &entities {
+            if !recording.initial_entities.contains(&entity) {
+                recording.log.spawned.push(entity);
+            }
+        }
+    }
+
+    /// Writes recorded events with the current snapshot as the final one.
+    fn checkpoint_system(world: &World, recording: Res<Recording>) -> Result<()> {
+        write_snapshot(world, &snapshot_path(&recording.dir, FINAL_SNAPSHOT))?;
+
+        let events_path = recording.dir.join(EVENTS_FILE);
+        let bytes = DefaultOptions::new()
+            .serialize(&recording.log)
+            .with_context(|| format!("unable to serialize {events_path:?}"))?;
+        super::write_atomically(&events_path, &bytes)
+            .with_context(|| format!("unable to write {events_path:?}"))?;
+
+        debug!(
+            "recorded {} events over {} frames",
+            recording.log.events.len(),
+            recording.log.frames.len()
+        );
+
+        Ok(())
+    }
+
+    fn stop_system(mut commands: Commands) {
+        commands.remove_resource::<Recording>();
+    }
+
+    /// Spawns the starting snapshot of the recording.
+    ///
+    /// Saving is disabled while [`Replay`] exists.
+    fn replay_loading_system(world: &mut World) -> Result<()> {
+        let replay_load = world
+            .remove_resource::<ReplayLoad>()
+            .expect("replay should be requested");
+        let dir = replay_load.0;
+
+        let events_path = dir.join(EVENTS_FILE);
+        let bytes =
+            fs::read(&events_path).with_context(|| format!("unable to load {events_path:?}"))?;
+        let log: EventLog = DefaultOptions::new()
+            .deserialize(&bytes)
+            .with_context(|| format!("unable to deserialize {events_path:?}"))?;
+
+        let start_path = snapshot_path(&dir, START_SNAPSHOT);
+        let mut start_scene = read_snapshot(world, &start_path)?;
+        let final_scene = read_snapshot(world, &snapshot_path(&dir, FINAL_SNAPSHOT))?;
+
+        // All saved entities should have `Replication` component.
+        for entity in &mut start_scene.entities {
+            entity.components.push(Replication.clone_value());
+        }
+
+        let mut entity_map = EntityMap::default();
+        start_scene
+            .write_to_world(world, &mut entity_map)
+            .with_context(|| format!("unable to spawn {start_path:?}"))?;
+
+        info!(
+            "replaying {} events over {} frames from {dir:?}",
+            log.events.len(),
+            log.frames.len()
+        );
+
+        // Events from the host are owned by the player who recorded them.
+        world.resource_mut::<Settings>().player.id = log.host_player_id;
+        world.insert_resource(WorldName(dir.to_string_lossy().into_owned()));
+        world.insert_resource(Replay {
+            initial_entities: entity_map.values().collect(),
+            entity_map,
+            final_scene,
+            log,
+            frame: 0,
+            next_event: 0,
+            next_spawn: 0,
+        });
+
+        Ok(())
+    }
+
+    fn replay_frame_system(replay: Res<Replay>, mut update_strategy: ResMut<TimeUpdateStrategy>) {
+        if let Some(&delta) = replay.log.frames.get(replay.frame) {
+            *update_strategy = TimeUpdateStrategy::ManualDuration(delta);
+        }
+    }
+
+    /// Sends events that were received on the current frame.
+    fn replay_system(world: &mut World) -> Result<()> {
+        world.resource_scope(|world, mut replay: Mut<Replay>| {
+            world.resource_scope(|world, replayers: Mut<EventReplayers>| {
+                let replay = &mut *replay;
+                while let Some(event) = replay.log.events.get(replay.next_event) {
+                    if event.frame > replay.frame {
+                        break;
+                    }
+
+                    let replay_event = replayers
+                        .get(event.type_name.as_str())
+                        .with_context(|| format!("{} is not recorded", event.type_name))?;
+                    replay_event(world, event, &replay.entity_map)?;
+                    replay.next_event += 1;
+                }
+
+                Ok(())
+            })
+        })
+    }
+
+    fn replay_spawn_system(
+        mut replay: ResMut<Replay>,
+        entities: Query<Entity, (Added<Replication>, Without<Player>)>,
+    ) {
+        for entity in &entities {
+            if replay.initial_entities.contains(&entity) {
+                continue;
+            }
+
+            let Some(&recorded_entity) = replay.log.spawned.get(replay.next_spawn) else {
+                warn!("{entity:?} wasn't spawned during recording");
+                continue;
+            };
+            replay.entity_map.insert(recorded_entity, entity);
+            replay.next_spawn += 1;
+        }
+    }
+
+    /// Compares the world with the final snapshot after the last recorded frame and exits.
+    fn replay_finish_system(world: &mut World) -> Result<()> {
+        let mut replay = world.resource_mut::<Replay>();
+        if replay.frame + 1 < replay.log.frames.len() {
+            replay.frame += 1;
+            return Ok(());
+        }
+
+        let replay = world
+            .remove_resource::<Replay>()
+            .expect("replay should be running");
+        world.send_event(AppExit);
+
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let scene = super::save_to_scene(
+            world,
+            &registry,
+            world.resource::<ReplicationRules>(),
+            world.resource::<IgnoreSaving>(),
+        );
+        let registry = registry.read();
+        let expected = entity_digests(&replay.final_scene, &registry)?;
+        let actual = entity_digests(&scene, &registry)?;
+
+        let missing = difference(&expected, &actual);
+        let unexpected = difference(&actual, &expected);
+        if missing.is_empty() && unexpected.is_empty() {
+            info!(
+                "replay matches the final snapshot with {} entities",
+                expected.len()
+            );
+            return Ok(());
+        }
+
+        for digest in &missing {
+            debug!("missing entity: {digest}");
+        }
+        for digest in &unexpected {
+            debug!("unexpected entity: {digest}");
+        }
+        bail!(
+            "replay doesn't match the final snapshot: {} entities are missing and {} are unexpected",
+            missing.len(),
+            unexpected.len(),
+        )
+    }
+}
+
+const START_SNAPSHOT: &str = "start";
+const FINAL_SNAPSHOT: &str = "final";
+const EVENTS_FILE: &str = "events.bin";
+
+fn snapshot_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(name);
+    path.set_extension(BINARY_SCENE_EXTENSION);
+    path
+}
+
+/// Writes replicated entities of the world as a compressed save.
+fn write_snapshot(world: &World, path: &Path) -> Result<()> {
+    let registry = world.resource::<AppTypeRegistry>();
+    let scene = super::save_to_scene(
+        world,
+        registry,
+        world.resource::<ReplicationRules>(),
+        world.resource::<IgnoreSaving>(),
+    );
+    let count = |has_component: fn(&EntityRef) -> bool| {
+        world
+            .iter_entities()
+            .filter(|entity| has_component(entity))
+            .count()
+    };
+    let metadata = WorldMetadata::new(
+        world.resource::<PlayTime>().0,
+        count(|entity| entity.contains::<City>()),
+        count(|entity| entity.contains::<Family>()),
+        count(|entity| entity.contains::<Actor>()),
+    );
+
+    super::write_world(
+        SaveFormat::Binary { compressed: true },
+        &metadata,
+        &scene,
+        registry,
+        path,
+        path,
+    )
+}
+
+fn read_snapshot(world: &World, path: &Path) -> Result<DynamicScene> {
+    let bytes = fs::read(path).with_context(|| format!("unable to load {path:?}"))?;
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let deserializer = WorldSaveDeserializer::new(&registry, world.resource::<SaveMigrations>());
+    let world_save = SaveFormat::detect(&bytes)
+        .deserialize(&bytes, deserializer)
+        .with_context(|| format!("unable to deserialize {path:?}"))?;
+
+    Ok(world_save.scene)
+}
+
+/// Returns sorted descriptions of entities to compare worlds regardless of entity IDs.
+///
+/// Components that contain entities are skipped since IDs differ between worlds.
+/// Players are skipped because connections aren't replayed.
+fn entity_digests(scene: &DynamicScene, registry: &TypeRegistryInternal) -> Result<Vec<String>> {
+    let mut digests = Vec::new();
+    for entity in &scene.entities {
+        if entity
+            .components
+            .iter()
+            .any(|component| component.type_name() == any::type_name::<Player>())
+        {
+            continue;
+        }
+
+        let mut components = Vec::new();
+        for component in &entity.components {
+            let has_entities = registry
+                .get_with_name(component.type_name())
+                .map_or(false, |registration| {
+                    registration.data::<ReflectMapEntities>().is_some()
+                });
+            if !has_entities {
+                let serializer = ReflectSerializer::new(component.as_reflect(), registry);
+                let ron = ron::to_string(&serializer)
+                    .with_context(|| format!("unable to serialize {}", component.type_name()))?;
+                components.push(ron);
+            }
+        }
+        components.sort();
+        digests.push(components.join(", "));
+    }
+    digests.sort();
+
+    Ok(digests)
+}
+
+/// Returns elements of the sorted slice `a` that are missing in the sorted slice `b`.
+///
+/// Duplicates are counted.
+fn difference<'a>(a: &'a [String], b: &[String]) -> Vec<&'a String> {
+    let mut result = Vec::new();
+    let mut b_iter = b.iter().peekable();
+    for value in a {
+        while b_iter.next_if(|other| *other < value).is_some() {}
+        if b_iter.next_if(|other| *other == value).is_none() {
+            result.push(value);
+        }
+    }
+    result
+}
+
+/// Requests recording after the world is loaded.
+#[derive(Default, Resource)]
+pub(crate) struct RecordWorld;
+
+/// Requests replay of the recording from the specified directory.
+#[derive(Resource)]
+pub(crate) struct ReplayLoad(pub(crate) PathBuf);
+
+#[derive(Resource)]
+struct Recording {
+    dir: PathBuf,
+    /// Replicated entities from the starting snapshot.
+    initial_entities: HashSet<Entity>,
+    /// Time since the recording start.
+    elapsed: Duration,
+    log: EventLog,
+}
+
+#[derive(Resource)]
+pub(crate) struct Replay {
+    log: EventLog,
+    /// Maps recorded entities to the replayed ones.
+    entity_map: EntityMap,
+    /// Replicated entities from the starting snapshot.
+    initial_entities: HashSet<Entity>,
+    final_scene: DynamicScene,
+    /// Index of the current frame in [`EventLog::frames`].
+    frame: usize,
+    next_event: usize,
+    next_spawn: usize,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct EventLog {
+    /// Player ID that was used for events from the host.
+    host_player_id: u64,
+    /// Duration of each recorded frame.
+    frames: Vec<Duration>,
+    events: Vec<RecordedEvent>,
+    /// Replicated entities in spawn order.
+    spawned: Vec<Entity>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RecordedEvent {
+    /// Index of the frame on which the event was received.
+    frame: usize,
+    /// Time since the recording start.
+    time: Duration,
+    client_id: u64,
+    /// Type name to find the corresponding function in [`EventReplayers`].
+    type_name: String,
+    bytes: Vec<u8>,
+}
+
+/// Sends recorded event to the server systems.
+type ReplayFn = fn(&mut World, &RecordedEvent, &EntityMap) -> Result<()>;
+
+/// Replay functions for recorded events by their type names.
+#[derive(Default, Deref, DerefMut, Resource)]
+struct EventReplayers(HashMap<&'static str, ReplayFn>);
+
+pub(crate) trait AppRecordingExt {
+    /// Records a client event without entities for replay.
+    fn record_client_event<T>(&mut self) -> &mut Self
+    where
+        T: Event + Serialize + DeserializeOwned;
+
+    /// Same as [`Self::record_client_event`], but maps entities on replay.
+    fn record_mapped_client_event<T>(&mut self) -> &mut Self
+    where
+        T: Event + Serialize + DeserializeOwned + MapEventEntities;
+
+    /// Same as [`Self::record_mapped_client_event`], but for events with reflected data.
+    ///
+    /// Uses the same serializers as the event registration.
+    fn record_mapped_client_reflect_event<T, S, D>(&mut self) -> &mut Self
+    where
+        T: Event + MapEventEntities,
+        S: BuildEventSerializer<T> + 'static,
+        D: BuildEventDeserializer + 'static,
+        for<'a> S::EventSerializer<'a>: Serialize,
+        for<'a, 'de> D::EventDeserializer<'a>: DeserializeSeed<'de, Value = T>;
+}
+
+impl AppRecordingExt for App {
+    fn record_client_event<T>(&mut self) -> &mut Self
+    where
+        T: Event + Serialize + DeserializeOwned,
+    {
+        record::<T, SerdeCodec, Unmapped>(self)
+    }
+
+    fn record_mapped_client_event<T>(&mut self) -> &mut Self
+    where
+        T: Event + Serialize + DeserializeOwned + MapEventEntities,
+    {
+        record::<T, SerdeCodec, Mapped>(self)
+    }
+
+    fn record_mapped_client_reflect_event<T, S, D>(&mut self) -> &mut Self
+    where
+        T: Event + MapEventEntities,
+        S: BuildEventSerializer<T> + 'static,
+        D: BuildEventDeserializer + 'static,
+        for<'a> S::EventSerializer<'a>: Serialize,
+        for<'a, 'de> D::EventDeserializer<'a>: DeserializeSeed<'de, Value = T>,
+    {
+        record::<T, ReflectCodec<S, D>, Mapped>(self)
+    }
+}
+
+fn record<T: Event, C: EventCodec<T>, M: EventMapping<T>>(app: &mut App) -> &mut App {
+    app.world
+        .get_resource_or_insert_with(EventReplayers::default)
+        .insert(any::type_name::<T>(), replay_event::<T, C, M>);
+    app.add_systems(
+        PreUpdate,
+        record_system::<T, C>
+            .pipe(error::report)
+            .after(ServerSet::Receive)
+            .run_if(resource_exists::<Recording>()),
+    )
+}
+
+fn record_system<T: Event, C: EventCodec<T>>(
+    mut recording: ResMut<Recording>,
+    mut client_events: EventReader<FromClient<T>>,
+    registry: Res<AppTypeRegistry>,
+) -> Result<()> {
+    let registry = registry.read();
+    for FromClient { client_id, event } in &mut client_events {
+        let bytes = C::encode(event, &registry)
+            .with_context(|| format!("unable to record {}", any::type_name::<T>()))?;
+        let event = RecordedEvent {
+            frame: recording.log.frames.len() - 1,
+            time: recording.elapsed,
+            client_id: *client_id,
+            type_name: any::type_name::<T>().to_string(),
+            bytes,
+        };
+        recording.log.events.push(event);
+    }
+
+    Ok(())
+}
+
+fn replay_event<T: Event, C: EventCodec<T>, M: EventMapping<T>>(
+    world: &mut World,
+    recorded_event: &RecordedEvent,
+    entity_map: &EntityMap,
+) -> Result<()> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let mut event = C::decode(&recorded_event.bytes, &registry.read())
+        .with_context(|| format!("unable to deserialize {}", recorded_event.type_name))?;
+    M::map(&mut event, entity_map)
+        .map_err(|e| anyhow!("unable to map {:?} from {}", e.0, recorded_event.type_name))?;
+    world.send_event(FromClient {
+        client_id: recorded_event.client_id,
+        event,
+    });
+
+    Ok(())
+}
+
+/// Converts events into bytes and back.
+trait EventCodec<T>: 'static {
+    fn encode(event: &T, registry: &TypeRegistryInternal) -> bincode::Result<Vec<u8>>;
+    fn decode(bytes: &[u8], registry: &TypeRegistryInternal) -> bincode::Result<T>;
+}
+
+struct SerdeCodec;
+
+impl<T: Serialize + DeserializeOwned> EventCodec<T> for SerdeCodec {
+    fn encode(event: &T, _registry: &TypeRegistryInternal) -> bincode::Result<Vec<u8>> {
+        DefaultOptions::new().serialize(event)
+    }
+
+    fn decode(bytes: &[u8], _registry: &TypeRegistryInternal) -> bincode::Result<T> {
+        DefaultOptions::new().deserialize(bytes)
+    }
+}
+
+struct ReflectCodec<S, D>(PhantomData<(S, D)>);
+
+impl<T, S, D> EventCodec<T> for ReflectCodec<S, D>
+where
+    S: BuildEventSerializer<T> + 'static,
+    D: BuildEventDeserializer + 'static,
+    for<'a> S::EventSerializer<'a>: Serialize,
+    for<'a, 'de> D::EventDeserializer<'a>: DeserializeSeed<'de, Value = T>,
+{
+    fn encode(event: &T, registry: &TypeRegistryInternal) -> bincode::Result<Vec<u8>> {
+        DefaultOptions::new().serialize(&S::new(event, registry))
+    }
+
+    fn decode(bytes: &[u8], registry: &TypeRegistryInternal) -> bincode::Result<T> {
+        DefaultOptions::new().deserialize_seed(D::new(registry), bytes)
+    }
+}
+
+/// Maps entities of recorded events to the replayed ones.
+trait EventMapping<T>: 'static {
+    fn map(event: &mut T, entity_map: &EntityMap) -> Result<(), MapError>;
+}
+
+struct Unmapped;
+
+impl<T> EventMapping<T> for Unmapped {
+    fn map(_event: &mut T, _entity_map: &EntityMap) -> Result<(), MapError> {
+        Ok(())
+    }
+}
+
+struct Mapped;
+
+impl<T: MapEventEntities> EventMapping<T> for Mapped {
+    fn map(event: &mut T, entity_map: &EntityMap) -> Result<(), MapError> {
+        event.map_entities(entity_map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_difference() {
+        let a = ["a", "b", "b", "d"].map(String::from);
+        let b = ["b", "c", "d"].map(String::from);
+        assert_eq!(difference(&a, &b), ["a", "b"]);
+        assert_eq!(difference(&b, &a), ["c"]);
+        assert!(difference(&a, &a).is_empty());
+    }
+}
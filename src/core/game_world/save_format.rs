@@ -0,0 +1,241 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use bincode::{DefaultOptions, Options};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use ron::ser::PrettyConfig;
+use serde::de::DeserializeSeed;
+
+use super::world_save::{
+    WorldMetadata, WorldMetadataReader, WorldSave, WorldSaveDeserializer, WorldSaveSerializer,
+};
+use crate::core::{
+    game_paths::{BINARY_SCENE_EXTENSION, SCENE_EXTENSION},
+    settings::SaveSettings,
+};
+
+/// Bytes at the beginning of binary world saves.
+const BINARY_MAGIC: &[u8] = b"LSWB";
+
+/// Header flag that indicates that the data after header is compressed.
+const COMPRESSED_FLAG: u8 = 1;
+
+/// Size of the binary save header: magic bytes and flags.
+const BINARY_HEADER_LEN: usize = BINARY_MAGIC.len() + 1;
+
+/// Number of bytes to read from RON saves at once while looking for metadata.
+const RON_METADATA_CHUNK_LEN: u64 = 4096;
+
+/// Supported formats for world saves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SaveFormat {
+    /// Human-readable format, useful for debugging and diffing.
+    Ron,
+    /// Compact format with optional compression.
+    Binary { compressed: bool },
+}
+
+impl SaveFormat {
+    /// Returns format for new saves from settings.
+    pub(crate) fn from_settings(settings: &SaveSettings) -> Self {
+        if settings.binary {
+            Self::Binary {
+                compressed: settings.compress,
+            }
+        } else {
+            Self::Ron
+        }
+    }
+
+    /// Detects format by magic bytes at the beginning of a save.
+    ///
+    /// Saves without them are considered RON.
+    pub(super) fn detect(bytes: &[u8]) -> Self {
+        match bytes
+            .strip_prefix(BINARY_MAGIC)
+            .and_then(|header| header.first())
+        {
+            Some(flags) => Self::Binary {
+                compressed: flags & COMPRESSED_FLAG != 0,
+            },
+            None => Self::Ron,
+        }
+    }
+
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Ron => SCENE_EXTENSION,
+            SaveFormat::Binary { .. } => BINARY_SCENE_EXTENSION,
+        }
+    }
+
+    pub(super) fn serialize(self, save: &WorldSaveSerializer) -> Result<Vec<u8>> {
+        match self {
+            SaveFormat::Ron => {
+                let content = ron::ser::to_string_pretty(
+                    save,
+                    PrettyConfig::default().indentor("  ".to_string()),
+                )
+                .context("unable to serialize world to RON")?;
+                Ok(content.into_bytes())
+            }
+            SaveFormat::Binary { compressed } => {
+                let mut bytes = BINARY_MAGIC.to_vec();
+                if compressed {
+                    bytes.push(COMPRESSED_FLAG);
+                    let mut encoder = ZlibEncoder::new(bytes, Compression::default());
+                    bincode_options()
+                        .serialize_into(&mut encoder, save)
+                        .context("unable to serialize world to binary")?;
+                    encoder.finish().context("unable to compress world")
+                } else {
+                    bytes.push(0);
+                    bincode_options()
+                        .serialize_into(&mut bytes, save)
+                        .context("unable to serialize world to binary")?;
+                    Ok(bytes)
+                }
+            }
+        }
+    }
+
+    pub(super) fn deserialize(
+        self,
+        bytes: &[u8],
+        deserializer: WorldSaveDeserializer,
+    ) -> Result<WorldSave> {
+        match self {
+            SaveFormat::Ron => loop {
+                let mut ron_deserializer = ron::Deserializer::from_bytes(bytes)?;
+                match deserializer.deserialize(&mut ron_deserializer) {
+                    Ok(world_save) => return Ok(world_save),
+                    // RON can't continue after a broken value, so parse again skipping it.
+                    Err(_) if deserializer.skip_failed() => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            },
+            SaveFormat::Binary { compressed } => {
+                let data = &bytes[BINARY_HEADER_LEN..];
+                let world_save = if compressed {
+                    bincode_options().deserialize_from_seed(deserializer, ZlibDecoder::new(data))?
+                } else {
+                    bincode_options().deserialize_seed(deserializer, data)?
+                };
+                Ok(world_save)
+            }
+        }
+    }
+}
+
+/// Reads only [`WorldMetadata`] from a world save.
+///
+/// Reads only the beginning of the file since metadata is written before the scene.
+pub(crate) fn read_metadata(world_path: &Path) -> Result<Option<WorldMetadata>> {
+    let mut file =
+        File::open(world_path).with_context(|| format!("unable to open {world_path:?}"))?;
+    let mut header = [0; BINARY_HEADER_LEN];
+    let format = match file.read_exact(&mut header) {
+        Ok(()) => SaveFormat::detect(&header),
+        // Too short for binary header.
+        Err(_) => SaveFormat::Ron,
+    };
+
+    let reader = WorldMetadataReader::default();
+    match format {
+        SaveFormat::Ron => {
+            file.seek(SeekFrom::Start(0))
+                .with_context(|| format!("unable to read {world_path:?}"))?;
+            // Grow the read prefix until it contains the metadata.
+            let mut bytes = Vec::new();
+            loop {
+                let len = file
+                    .by_ref()
+                    .take(RON_METADATA_CHUNK_LEN)
+                    .read_to_end(&mut bytes)
+                    .with_context(|| format!("unable to read {world_path:?}"))?;
+                let mut deserializer = ron::Deserializer::from_bytes(&bytes)?;
+                // Reports an unfinished struct after the metadata, which is expected.
+                let result = (&reader).deserialize(&mut deserializer);
+                if reader.is_finished() {
+                    break;
+                }
+                if len == 0 {
+                    result?;
+                    break;
+                }
+            }
+        }
+        SaveFormat::Binary { compressed } => {
+            let options = bincode_options().allow_trailing_bytes();
+            let file = BufReader::new(file);
+            if compressed {
+                options.deserialize_from_seed(&reader, ZlibDecoder::new(file))?
+            } else {
+                options.deserialize_from_seed(&reader, file)?
+            }
+        }
+    }
+
+    reader
+        .into_metadata()
+        .with_context(|| format!("{world_path:?} ended before the metadata"))
+}
+
+/// Options for binary serialization.
+///
+/// Should be the same for serialization and deserialization.
+pub(super) fn bincode_options() -> impl Options {
+    DefaultOptions::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use super::*;
+    use crate::core::game_paths::GamePaths;
+
+    #[test]
+    fn format_detection() -> Result<()> {
+        let game_paths = GamePaths::default();
+        fs::create_dir_all(&game_paths.worlds)?;
+
+        let metadata = WorldMetadata {
+            saved_at: Duration::from_secs(1),
+            play_time: Duration::from_secs(2),
+            cities: 3,
+            families: 4,
+            actors: 5,
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        for format in [
+            SaveFormat::Ron,
+            SaveFormat::Binary { compressed: false },
+            SaveFormat::Binary { compressed: true },
+        ] {
+            let scene = DynamicScene::default();
+            let registry = AppTypeRegistry::default();
+            let bytes = format.serialize(&WorldSaveSerializer::new(
+                &metadata,
+                &scene,
+                &registry.read(),
+            ))?;
+            assert_eq!(SaveFormat::detect(&bytes), format);
+
+            let world_path = game_paths.world_path_with_extension("Test", format.extension());
+            fs::write(&world_path, &bytes)?;
+            assert_eq!(read_metadata(&world_path)?.as_ref(), Some(&metadata));
+
+            // Only the beginning of the save should be parsed.
+            fs::write(&world_path, &bytes[..bytes.len() - 1])?;
+            assert_eq!(read_metadata(&world_path)?.as_ref(), Some(&metadata));
+        }
+
+        Ok(())
+    }
+}
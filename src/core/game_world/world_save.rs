@@ -0,0 +1,1027 @@
+use std::{
+    any,
+    cell::{Cell, RefCell},
+    fmt::{self, Display, Formatter},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    reflect::{
+        serde::{TypedReflectDeserializer, TypedReflectSerializer},
+        TypeRegistryInternal,
+    },
+    scene::DynamicEntity,
+    utils::{HashMap, HashSet},
+};
+use bincode::Options;
+use derive_more::Constructor;
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use strum::{EnumVariantNames, IntoStaticStr, VariantNames};
+
+use super::{migration::SaveMigrations, save_format};
+
+/// Current version of the world save format.
+///
+/// Should be incremented on every incompatible change to saved components
+/// together with registering a migration using [`super::migration::AppMigrationExt`].
+pub(crate) const SAVE_VERSION: u32 = 2;
+
+/// Version in which [`WorldMetadata`] was added to saves.
+const METADATA_VERSION: u32 = 2;
+
+const WORLD_SAVE_STRUCT: &str = "WorldSave";
+
+/// Fields of a saved world.
+///
+/// Saves without a version are bare scenes, so their fields are also accepted.
+#[derive(Deserialize, EnumVariantNames, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum WorldSaveField {
+    Version,
+    Metadata,
+    Scene,
+    Resources,
+    Entities,
+}
+
+/// Summary of a saved world that is stored before the scene.
+///
+/// Can be read without deserializing the whole world.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct WorldMetadata {
+    /// Time since Unix epoch when the world was saved.
+    pub(crate) saved_at: Duration,
+    /// Accumulated time spent in the world.
+    pub(crate) play_time: Duration,
+    pub(crate) cities: usize,
+    pub(crate) families: usize,
+    pub(crate) actors: usize,
+    /// Version of the game that saved the world.
+    pub(crate) game_version: String,
+}
+
+impl WorldMetadata {
+    /// Creates metadata for a save made now by the current game version.
+    pub(crate) fn new(play_time: Duration, cities: usize, families: usize, actors: usize) -> Self {
+        Self {
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+            play_time,
+            cities,
+            families,
+            actors,
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// Deserialized world save.
+pub(super) struct WorldSave {
+    /// Metadata of the save, missing for saves before [`METADATA_VERSION`].
+    pub(super) metadata: Option<WorldMetadata>,
+    pub(super) scene: DynamicScene,
+}
+
+/// Serializes a scene with the header that contains the current [`SAVE_VERSION`] and [`WorldMetadata`].
+#[derive(Constructor)]
+pub(super) struct WorldSaveSerializer<'a> {
+    metadata: &'a WorldMetadata,
+    scene: &'a DynamicScene,
+    registry: &'a TypeRegistryInternal,
+}
+
+impl Serialize for WorldSaveSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct(WORLD_SAVE_STRUCT, 3)?;
+        state.serialize_field(WorldSaveField::Version.into(), &SAVE_VERSION)?;
+        state.serialize_field(WorldSaveField::Metadata.into(), self.metadata)?;
+        state.serialize_field(
+            WorldSaveField::Scene.into(),
+            &SceneSerializer {
+                scene: self.scene,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+fn check_version<E: de::Error>(version: u32) -> Result<u32, E> {
+    if version > SAVE_VERSION {
+        return Err(de::Error::custom(format!(
+            "save version {version} is newer than supported {SAVE_VERSION}"
+        )));
+    }
+
+    Ok(version)
+}
+
+/// Deserializes a world save of any version into a [`WorldSave`] of the current version.
+#[derive(Clone, Copy)]
+pub(super) struct WorldSaveDeserializer<'a> {
+    registry: &'a TypeRegistryInternal,
+    migrations: &'a SaveMigrations,
+    leniency: Option<&'a Leniency>,
+}
+
+impl<'a> WorldSaveDeserializer<'a> {
+    pub(super) fn new(registry: &'a TypeRegistryInternal, migrations: &'a SaveMigrations) -> Self {
+        Self {
+            registry,
+            migrations,
+            leniency: None,
+        }
+    }
+
+    /// Skips broken components instead of failing and collects them into [`Leniency`].
+    pub(super) fn with_leniency(mut self, leniency: &'a Leniency) -> Self {
+        self.leniency = Some(leniency);
+        self
+    }
+
+    /// Marks the last component that failed to deserialize as broken to skip it on the next attempt.
+    ///
+    /// Needed for formats that can't skip values after an error.
+    /// Returns `false` if not in lenient mode or there is no such component.
+    pub(super) fn skip_failed(&self) -> bool {
+        let Some(leniency) = self.leniency else {
+            return false;
+        };
+        let Some(component) = leniency.failed.take() else {
+            return false;
+        };
+
+        leniency
+            .broken
+            .borrow_mut()
+            .insert((component.entity, component.type_name), component.reason);
+        leniency.report.borrow_mut().skipped.clear();
+
+        true
+    }
+
+    fn context(&self, version: u32) -> SceneContext<'a> {
+        SceneContext {
+            version,
+            registry: self.registry,
+            migrations: self.migrations,
+            leniency: self.leniency,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for WorldSaveDeserializer<'_> {
+    type Value = WorldSave;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct(WORLD_SAVE_STRUCT, WorldSaveField::VARIANTS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for WorldSaveDeserializer<'_> {
+    type Value = WorldSave;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(WORLD_SAVE_STRUCT)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut version = None;
+        let mut metadata = None;
+        let mut scene = None;
+        let mut resources = None;
+        let mut entities = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                WorldSaveField::Version => {
+                    if version.is_some() {
+                        return Err(de::Error::duplicate_field(WorldSaveField::Version.into()));
+                    }
+                    version = Some(check_version(map.next_value()?)?);
+                }
+                WorldSaveField::Metadata => {
+                    if metadata.is_some() {
+                        return Err(de::Error::duplicate_field(WorldSaveField::Metadata.into()));
+                    }
+                    metadata = Some(map.next_value()?);
+                }
+                WorldSaveField::Scene => {
+                    if scene.is_some() {
+                        return Err(de::Error::duplicate_field(WorldSaveField::Scene.into()));
+                    }
+                    let version = version.ok_or_else(|| {
+                        de::Error::custom("save version should be specified before the scene")
+                    })?;
+                    scene = Some(map.next_value_seed(SceneDeserializer {
+                        context: self.context(version),
+                    })?);
+                }
+                // Fields of a bare scene from saves before versioning.
+                WorldSaveField::Resources => {
+                    if resources.is_some() {
+                        return Err(de::Error::duplicate_field(WorldSaveField::Resources.into()));
+                    }
+                    resources = Some(map.next_value_seed(ComponentsDeserializer {
+                        context: self.context(0),
+                        entity: None,
+                    })?);
+                }
+                WorldSaveField::Entities => {
+                    if entities.is_some() {
+                        return Err(de::Error::duplicate_field(WorldSaveField::Entities.into()));
+                    }
+                    entities = Some(map.next_value_seed(EntitiesDeserializer {
+                        context: self.context(0),
+                    })?);
+                }
+            }
+        }
+
+        if let Some(scene) = scene {
+            return Ok(WorldSave { metadata, scene });
+        }
+
+        let resources =
+            resources.ok_or_else(|| de::Error::missing_field(WorldSaveField::Scene.into()))?;
+        let entities =
+            entities.ok_or_else(|| de::Error::missing_field(WorldSaveField::Entities.into()))?;
+
+        Ok(WorldSave {
+            metadata: None,
+            scene: DynamicScene {
+                resources,
+                entities,
+            },
+        })
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let version = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(WorldSaveField::Version as usize, &self))?;
+        let version = check_version(version)?;
+        let metadata = if version >= METADATA_VERSION {
+            let metadata = seq.next_element()?.ok_or_else(|| {
+                de::Error::invalid_length(WorldSaveField::Metadata as usize, &self)
+            })?;
+            Some(metadata)
+        } else {
+            None
+        };
+        let scene = seq
+            .next_element_seed(SceneDeserializer {
+                context: self.context(version),
+            })?
+            .ok_or_else(|| de::Error::invalid_length(WorldSaveField::Scene as usize, &self))?;
+
+        Ok(WorldSave { metadata, scene })
+    }
+}
+
+/// Deserializes only [`WorldMetadata`] from the beginning of a world save.
+///
+/// Stops right after the metadata, so the scene doesn't need to be read.
+/// Self-describing formats report an unfinished struct after it,
+/// so the result should be taken from [`Self::into_metadata`] even on error.
+#[derive(Default)]
+pub(super) struct WorldMetadataReader(RefCell<Option<Option<WorldMetadata>>>);
+
+impl WorldMetadataReader {
+    /// Returns `true` if the metadata was read or the save turned out to have none.
+    pub(super) fn is_finished(&self) -> bool {
+        self.0.borrow().is_some()
+    }
+
+    /// Returns the read metadata or [`None`] if the reading wasn't finished.
+    ///
+    /// The inner value is [`None`] for saves before [`METADATA_VERSION`].
+    pub(super) fn into_metadata(self) -> Option<Option<WorldMetadata>> {
+        self.0.into_inner()
+    }
+
+    fn finish(&self, metadata: Option<WorldMetadata>) {
+        *self.0.borrow_mut() = Some(metadata);
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &WorldMetadataReader {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct(WORLD_SAVE_STRUCT, WorldSaveField::VARIANTS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for &WorldMetadataReader {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(WORLD_SAVE_STRUCT)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        while let Some(key) = map.next_key()? {
+            match key {
+                WorldSaveField::Version => {
+                    if check_version(map.next_value()?)? < METADATA_VERSION {
+                        self.finish(None);
+                        return Ok(());
+                    }
+                }
+                WorldSaveField::Metadata => {
+                    self.finish(Some(map.next_value()?));
+                    return Ok(());
+                }
+                // Metadata is written before the scene.
+                WorldSaveField::Scene | WorldSaveField::Resources | WorldSaveField::Entities => {
+                    break;
+                }
+            }
+        }
+
+        self.finish(None);
+        Ok(())
+    }
+
+    /// Reads only the beginning of the sequence since binary formats can't skip values.
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let version = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(WorldSaveField::Version as usize, &self))?;
+        if check_version(version)? < METADATA_VERSION {
+            self.finish(None);
+            return Ok(());
+        }
+
+        let metadata = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(WorldSaveField::Metadata as usize, &self))?;
+        self.finish(Some(metadata));
+
+        Ok(())
+    }
+}
+
+/// State for lenient loading.
+#[derive(Default)]
+pub(super) struct Leniency {
+    report: RefCell<LoadReport>,
+    /// Component that failed to deserialize in a format that can't skip it.
+    failed: Cell<Option<SkippedComponent>>,
+    /// Components from previous attempts that should be skipped with their failure reasons.
+    broken: RefCell<HashMap<(Option<Entity>, String), String>>,
+}
+
+impl Leniency {
+    pub(super) fn into_report(self) -> LoadReport {
+        self.report.into_inner()
+    }
+}
+
+/// Components that were skipped during lenient loading.
+#[derive(Default, Resource)]
+pub(crate) struct LoadReport {
+    pub(crate) skipped: Vec<SkippedComponent>,
+}
+
+impl Display for LoadReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "skipped {} component(s)", self.skipped.len())?;
+        for component in &self.skipped {
+            write!(f, "\n{component}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SkippedComponent {
+    /// Entity with the component, [`None`] for resources.
+    pub(crate) entity: Option<Entity>,
+    pub(crate) type_name: String,
+    pub(crate) reason: String,
+}
+
+impl Display for SkippedComponent {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.entity {
+            Some(entity) => write!(f, "{} on entity {entity:?}", self.type_name)?,
+            None => write!(f, "resource {}", self.type_name)?,
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// Parameters shared between nested scene deserializers.
+#[derive(Clone, Copy)]
+struct SceneContext<'a> {
+    version: u32,
+    registry: &'a TypeRegistryInternal,
+    migrations: &'a SaveMigrations,
+    leniency: Option<&'a Leniency>,
+}
+
+/// Fields of [`DynamicScene`] in the same format as in [`SceneSerializer`].
+#[derive(Deserialize, EnumVariantNames, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum SceneField {
+    Resources,
+    Entities,
+}
+
+/// Like [`bevy::scene::serde::SceneSerializer`], but stores components as blobs in binary formats.
+struct SceneSerializer<'a> {
+    scene: &'a DynamicScene,
+    registry: &'a TypeRegistryInternal,
+}
+
+impl Serialize for SceneSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer
+            .serialize_struct(any::type_name::<DynamicScene>(), SceneField::VARIANTS.len())?;
+        state.serialize_field(
+            SceneField::Resources.into(),
+            &ComponentsSerializer {
+                components: &self.scene.resources,
+                registry: self.registry,
+            },
+        )?;
+        state.serialize_field(
+            SceneField::Entities.into(),
+            &EntitiesSerializer {
+                entities: &self.scene.entities,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+struct EntitiesSerializer<'a> {
+    entities: &'a [DynamicEntity],
+    registry: &'a TypeRegistryInternal,
+}
+
+impl Serialize for EntitiesSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_map(Some(self.entities.len()))?;
+        for entity in self.entities {
+            state.serialize_entry(
+                &entity.entity,
+                &EntitySerializer {
+                    entity,
+                    registry: self.registry,
+                },
+            )?;
+        }
+        state.end()
+    }
+}
+
+struct EntitySerializer<'a> {
+    entity: &'a DynamicEntity,
+    registry: &'a TypeRegistryInternal,
+}
+
+impl Serialize for EntitySerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct(
+            any::type_name::<DynamicEntity>(),
+            EntityField::VARIANTS.len(),
+        )?;
+        state.serialize_field(
+            EntityField::Components.into(),
+            &ComponentsSerializer {
+                components: &self.entity.components,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+/// Serializes a map of reflected components by their type names.
+///
+/// Binary formats can't skip values of unknown types, so for them each component is serialized as a separate blob.
+struct ComponentsSerializer<'a> {
+    components: &'a [Box<dyn Reflect>],
+    registry: &'a TypeRegistryInternal,
+}
+
+impl Serialize for ComponentsSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let human_readable = serializer.is_human_readable();
+        let mut state = serializer.serialize_map(Some(self.components.len()))?;
+        for component in self.components {
+            let component_serializer = TypedReflectSerializer::new(&**component, self.registry);
+            if human_readable {
+                state.serialize_entry(component.type_name(), &component_serializer)?;
+            } else {
+                let bytes = save_format::bincode_options()
+                    .serialize(&component_serializer)
+                    .map_err(ser::Error::custom)?;
+                state.serialize_entry(component.type_name(), &bytes)?;
+            }
+        }
+        state.end()
+    }
+}
+
+/// Like [`bevy::scene::serde::SceneDeserializer`], but applies [`SaveMigrations`] to each component.
+struct SceneDeserializer<'a> {
+    context: SceneContext<'a>,
+}
+
+impl<'de> DeserializeSeed<'de> for SceneDeserializer<'_> {
+    type Value = DynamicScene;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct(any::type_name::<Self::Value>(), SceneField::VARIANTS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for SceneDeserializer<'_> {
+    type Value = DynamicScene;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(any::type_name::<Self::Value>())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut resources = None;
+        let mut entities = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                SceneField::Resources => {
+                    if resources.is_some() {
+                        return Err(de::Error::duplicate_field(SceneField::Resources.into()));
+                    }
+                    resources = Some(map.next_value_seed(ComponentsDeserializer {
+                        context: self.context,
+                        entity: None,
+                    })?);
+                }
+                SceneField::Entities => {
+                    if entities.is_some() {
+                        return Err(de::Error::duplicate_field(SceneField::Entities.into()));
+                    }
+                    entities = Some(map.next_value_seed(EntitiesDeserializer {
+                        context: self.context,
+                    })?);
+                }
+            }
+        }
+
+        let resources =
+            resources.ok_or_else(|| de::Error::missing_field(SceneField::Resources.into()))?;
+        let entities =
+            entities.ok_or_else(|| de::Error::missing_field(SceneField::Entities.into()))?;
+
+        Ok(DynamicScene {
+            resources,
+            entities,
+        })
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let resources = seq
+            .next_element_seed(ComponentsDeserializer {
+                context: self.context,
+                entity: None,
+            })?
+            .ok_or_else(|| de::Error::invalid_length(SceneField::Resources as usize, &self))?;
+        let entities = seq
+            .next_element_seed(EntitiesDeserializer {
+                context: self.context,
+            })?
+            .ok_or_else(|| de::Error::invalid_length(SceneField::Entities as usize, &self))?;
+
+        Ok(DynamicScene {
+            resources,
+            entities,
+        })
+    }
+}
+
+struct EntitiesDeserializer<'a> {
+    context: SceneContext<'a>,
+}
+
+impl<'de> DeserializeSeed<'de> for EntitiesDeserializer<'_> {
+    type Value = Vec<DynamicEntity>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntitiesDeserializer<'_> {
+    type Value = Vec<DynamicEntity>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(any::type_name::<Self::Value>())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entities = Vec::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some(entity) = map.next_key()? {
+            let components = map.next_value_seed(EntityDeserializer {
+                context: self.context,
+                entity,
+            })?;
+            entities.push(DynamicEntity { entity, components });
+        }
+
+        Ok(entities)
+    }
+}
+
+/// Fields of [`DynamicEntity`] in the same format as in [`SceneSerializer`].
+#[derive(Deserialize, EnumVariantNames, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum EntityField {
+    Components,
+}
+
+struct EntityDeserializer<'a> {
+    context: SceneContext<'a>,
+    entity: Entity,
+}
+
+impl<'de> DeserializeSeed<'de> for EntityDeserializer<'_> {
+    type Value = Vec<Box<dyn Reflect>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct(
+            any::type_name::<DynamicEntity>(),
+            EntityField::VARIANTS,
+            self,
+        )
+    }
+}
+
+impl<'de> Visitor<'de> for EntityDeserializer<'_> {
+    type Value = Vec<Box<dyn Reflect>>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(any::type_name::<DynamicEntity>())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut components = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                EntityField::Components => {
+                    if components.is_some() {
+                        return Err(de::Error::duplicate_field(EntityField::Components.into()));
+                    }
+                    components = Some(map.next_value_seed(ComponentsDeserializer {
+                        context: self.context,
+                        entity: Some(self.entity),
+                    })?);
+                }
+            }
+        }
+
+        components.ok_or_else(|| de::Error::missing_field(EntityField::Components.into()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        seq.next_element_seed(ComponentsDeserializer {
+            context: self.context,
+            entity: Some(self.entity),
+        })?
+        .ok_or_else(|| de::Error::invalid_length(EntityField::Components as usize, &self))
+    }
+}
+
+/// Deserializes a map of reflected components and applies migrations to them by type names.
+struct ComponentsDeserializer<'a> {
+    context: SceneContext<'a>,
+    /// Entity to which components belong, [`None`] for resources.
+    entity: Option<Entity>,
+}
+
+impl<'de> DeserializeSeed<'de> for ComponentsDeserializer<'_> {
+    type Value = Vec<Box<dyn Reflect>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let human_readable = deserializer.is_human_readable();
+        deserializer.deserialize_map(ComponentsVisitor {
+            human_readable,
+            deserializer: self,
+        })
+    }
+}
+
+struct ComponentsVisitor<'a> {
+    human_readable: bool,
+    deserializer: ComponentsDeserializer<'a>,
+}
+
+impl ComponentsVisitor<'_> {
+    fn skip_value<'de, A: MapAccess<'de>>(&self, map: &mut A) -> Result<(), A::Error> {
+        if self.human_readable {
+            map.next_value::<IgnoredAny>()?;
+        } else {
+            map.next_value::<Vec<u8>>()?;
+        }
+
+        Ok(())
+    }
+
+    fn deserialize_value<'de, A: MapAccess<'de>>(
+        &self,
+        map: &mut A,
+        type_name: &str,
+    ) -> Result<Result<Box<dyn Reflect>, String>, A::Error> {
+        let context = self.deserializer.context;
+        let Some(registration) = context.registry.get_with_name(type_name) else {
+            self.skip_value(map)?;
+            return Ok(Err(format!("{type_name} is not registered")));
+        };
+
+        let component_deserializer = TypedReflectDeserializer::new(registration, context.registry);
+        if self.human_readable {
+            match map.next_value_seed(component_deserializer) {
+                Ok(component) => Ok(Ok(component)),
+                Err(e) => {
+                    if let Some(leniency) = context.leniency {
+                        // Value can't be skipped after an error, the whole save should be reparsed.
+                        leniency.failed.set(Some(SkippedComponent {
+                            entity: self.deserializer.entity,
+                            type_name: type_name.to_string(),
+                            reason: e.to_string(),
+                        }));
+                    }
+                    Err(e)
+                }
+            }
+        } else {
+            let bytes = map.next_value::<Vec<u8>>()?;
+            Ok(save_format::bincode_options()
+                .deserialize_seed(component_deserializer, &bytes)
+                .map_err(|e| e.to_string()))
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentsVisitor<'_> {
+    type Value = Vec<Box<dyn Reflect>>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str(any::type_name::<Self::Value>())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let ComponentsDeserializer { context, entity } = self.deserializer;
+
+        let mut added = HashSet::new();
+        let mut components = Vec::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some(type_name) = map.next_key::<String>()? {
+            if let Some(leniency) = context.leniency {
+                let broken = leniency.broken.borrow();
+                if let Some(reason) = broken.get(&(entity, type_name.clone())) {
+                    self.skip_value(&mut map)?;
+                    leniency.report.borrow_mut().skipped.push(SkippedComponent {
+                        entity,
+                        type_name,
+                        reason: reason.clone(),
+                    });
+                    continue;
+                }
+            }
+
+            let Some(migration) = context
+                .migrations
+                .resolve(context.version, type_name.clone())
+            else {
+                self.skip_value(&mut map)?;
+                continue;
+            };
+
+            let result = self
+                .deserialize_value(&mut map, &migration.read_as)?
+                .and_then(|component| {
+                    migration
+                        .apply::<de::value::Error>(component)
+                        .map_err(|e| e.to_string())
+                })
+                .and_then(|component| {
+                    if added.insert(component.type_name().to_string()) {
+                        Ok(component)
+                    } else {
+                        Err(format!("duplicate component {}", component.type_name()))
+                    }
+                });
+
+            match result {
+                Ok(component) => components.push(component),
+                Err(reason) => {
+                    let skipped = SkippedComponent {
+                        entity,
+                        type_name,
+                        reason,
+                    };
+                    let Some(leniency) = context.leniency else {
+                        return Err(de::Error::custom(skipped));
+                    };
+                    leniency.report.borrow_mut().skipped.push(skipped);
+                }
+            }
+        }
+
+        Ok(components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::game_world::{migration::AppMigrationExt, save_format::SaveFormat};
+
+    #[test]
+    fn legacy_migration() {
+        let mut app = App::new();
+        app.register_type::<DummyComponent>()
+            .rename_component(
+                1,
+                "lifescape::core::game_world::world_save::tests::OldComponent",
+                any::type_name::<DummyComponent>(),
+            )
+            .convert_component(1, |legacy: LegacyDummyComponent| DummyComponent {
+                value: legacy.value.into(),
+            })
+            .remove_component(
+                1,
+                "lifescape::core::game_world::world_save::tests::RemovedComponent",
+            );
+
+        // Bare scene from before versioning.
+        const LEGACY_SAVE: &str = r#"(
+            resources: {},
+            entities: {
+                0: (
+                    components: {
+                        "lifescape::core::game_world::world_save::tests::OldComponent": (value: 4),
+                        "lifescape::core::game_world::world_save::tests::RemovedComponent": (),
+                    },
+                ),
+            },
+        )"#;
+
+        let registry = app.world.resource::<AppTypeRegistry>().read();
+        let migrations = app.world.resource::<SaveMigrations>();
+        let mut deserializer = ron::Deserializer::from_str(LEGACY_SAVE).unwrap();
+        let world_save = WorldSaveDeserializer::new(&registry, migrations)
+            .deserialize(&mut deserializer)
+            .unwrap();
+        assert!(world_save.metadata.is_none());
+
+        let entity = world_save
+            .scene
+            .entities
+            .first()
+            .expect("scene should contain an entity");
+        assert_eq!(
+            entity.components.len(),
+            1,
+            "removed component should be dropped"
+        );
+        let component = entity.components[0]
+            .downcast_ref::<DummyComponent>()
+            .expect("component should be converted into the new type");
+        assert_eq!(component.value, 4);
+    }
+
+    #[test]
+    fn chained_conversions() {
+        let mut app = App::new();
+        app.register_type::<DummyComponent>()
+            .convert_component(1, |legacy: LegacyDummyComponent| DummyComponent {
+                value: legacy.value.into(),
+            })
+            .convert_component(2, |legacy: IntermediateDummyComponent| DummyComponent {
+                value: legacy.amount.into(),
+            });
+
+        const OLDEST_SAVE: &str = r#"(
+            version: 0,
+            scene: (
+                resources: {},
+                entities: {
+                    0: (
+                        components: {
+                            "lifescape::core::game_world::world_save::tests::DummyComponent": (value: 4),
+                        },
+                    ),
+                },
+            ),
+        )"#;
+        const INTERMEDIATE_SAVE: &str = r#"(
+            version: 1,
+            scene: (
+                resources: {},
+                entities: {
+                    0: (
+                        components: {
+                            "lifescape::core::game_world::world_save::tests::DummyComponent": (amount: 5),
+                        },
+                    ),
+                },
+            ),
+        )"#;
+
+        let registry = app.world.resource::<AppTypeRegistry>().read();
+        let migrations = app.world.resource::<SaveMigrations>();
+        for (save, expected) in [(OLDEST_SAVE, 4), (INTERMEDIATE_SAVE, 5)] {
+            let deserializer = WorldSaveDeserializer::new(&registry, migrations);
+            let world_save = SaveFormat::Ron
+                .deserialize(save.as_bytes(), deserializer)
+                .unwrap();
+            let component = world_save.scene.entities[0].components[0]
+                .downcast_ref::<DummyComponent>()
+                .expect("component should be converted into the new type");
+            assert_eq!(component.value, expected);
+        }
+    }
+
+    #[test]
+    fn lenient_loading() {
+        let mut app = App::new();
+        app.register_type::<DummyComponent>()
+            .init_resource::<SaveMigrations>();
+
+        const BROKEN_SAVE: &str = r#"(
+            version: 2,
+            scene: (
+                resources: {},
+                entities: {
+                    0: (
+                        components: {
+                            "lifescape::core::game_world::world_save::tests::DummyComponent": (value: "broken"),
+                            "lifescape::core::game_world::world_save::tests::UnknownComponent": (),
+                        },
+                    ),
+                    1: (
+                        components: {
+                            "lifescape::core::game_world::world_save::tests::DummyComponent": (value: 1),
+                        },
+                    ),
+                },
+            ),
+        )"#;
+
+        let registry = app.world.resource::<AppTypeRegistry>().read();
+        let migrations = app.world.resource::<SaveMigrations>();
+        let deserializer = WorldSaveDeserializer::new(&registry, migrations);
+        assert!(
+            SaveFormat::Ron
+                .deserialize(BROKEN_SAVE.as_bytes(), deserializer)
+                .is_err(),
+            "broken save should fail without leniency"
+        );
+
+        let leniency = Leniency::default();
+        let world_save = SaveFormat::Ron
+            .deserialize(
+                BROKEN_SAVE.as_bytes(),
+                deserializer.with_leniency(&leniency),
+            )
+            .unwrap();
+
+        let entities = &world_save.scene.entities;
+        assert_eq!(entities.len(), 2);
+        assert!(entities[0].components.is_empty());
+        assert_eq!(entities[1].components.len(), 1);
+
+        let report = leniency.into_report();
+        assert_eq!(report.skipped.len(), 2);
+    }
+
+    #[derive(Component, Default, Reflect)]
+    #[reflect(Component)]
+    struct DummyComponent {
+        value: u64,
+    }
+
+    #[derive(Default, Reflect)]
+    struct LegacyDummyComponent {
+        value: u32,
+    }
+
+    #[derive(Default, Reflect)]
+    struct IntermediateDummyComponent {
+        amount: u32,
+    }
+}
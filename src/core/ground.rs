@@ -3,27 +3,43 @@ use bevy_rapier3d::prelude::*;
 use oxidized_navigation::NavMeshAffector;
 
 use super::{
-    city::{ActiveCity, CityPlugin},
+    city::{ActiveCity, City, CityPlugin},
     collision_groups::LifescapeGroupsExt,
     cursor_hover::Hoverable,
     game_state::GameState,
+    game_world::WorldName,
 };
 
+/// Spawns ground collider for each city and its visual part for the active city.
+///
+/// Collider is independent of the game state to let dedicated servers build the navigation mesh.
 pub(super) struct GroundPlugin;
 
 impl Plugin for GroundPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::City), Self::spawn_system)
-            .add_systems(OnExit(GameState::City), Self::despawn_system)
-            .add_systems(
-                OnEnter(GameState::Family),
-                Self::spawn_system.after(CityPlugin::activation_system),
-            )
-            .add_systems(OnExit(GameState::Family), Self::despawn_system);
+        app.add_systems(
+            Update,
+            Self::init_system.run_if(resource_exists::<WorldName>()),
+        )
+        .add_systems(OnEnter(GameState::City), Self::spawn_system)
+        .add_systems(OnExit(GameState::City), Self::despawn_system)
+        .add_systems(
+            OnEnter(GameState::Family),
+            Self::spawn_system.after(CityPlugin::activation_system),
+        )
+        .add_systems(OnExit(GameState::Family), Self::despawn_system);
     }
 }
 
 impl GroundPlugin {
+    fn init_system(mut commands: Commands, added_cities: Query<Entity, Added<City>>) {
+        for entity in &added_cities {
+            commands.entity(entity).with_children(|parent| {
+                parent.spawn(GroundBundle::default());
+            });
+        }
+    }
+
     fn spawn_system(
         activated_cities: Query<Entity, Added<ActiveCity>>,
         mut commands: Commands,
@@ -33,14 +49,14 @@ impl GroundPlugin {
         commands
             .entity(activated_cities.single())
             .with_children(|parent| {
-                parent.spawn(GroundBundle {
-                    pbr_bundle: PbrBundle {
+                parent.spawn((
+                    GroundMesh,
+                    PbrBundle {
                         mesh: meshes.add(Mesh::from(Plane::from_size(GroundBundle::SIZE))),
                         material: materials.add(Color::rgb_u8(69, 108, 69).into()),
                         ..Default::default()
                     },
-                    ..Default::default()
-                });
+                ));
                 parent.spawn(DirectionalLightBundle {
                     directional_light: DirectionalLight {
                         illuminance: 6000.0,
@@ -57,7 +73,7 @@ impl GroundPlugin {
         mut commands: Commands,
         active_cities: Query<&Children, With<ActiveCity>>,
         direction_lights: Query<Entity, With<DirectionalLight>>,
-        grounds: Query<Entity, With<Ground>>,
+        ground_meshes: Query<Entity, With<GroundMesh>>,
     ) {
         let children = active_cities.single();
         let light_entity = *children
@@ -66,11 +82,11 @@ impl GroundPlugin {
             .expect("deactivated city should have a children light");
         commands.entity(light_entity).despawn();
 
-        let ground_entity = *children
+        let mesh_entity = *children
             .iter()
-            .find(|&&entity| grounds.get(entity).is_ok())
-            .expect("deactivated city should have a children ground");
-        commands.entity(ground_entity).despawn();
+            .find(|&&entity| ground_meshes.get(entity).is_ok())
+            .expect("deactivated city should have a children ground mesh");
+        commands.entity(mesh_entity).despawn();
     }
 }
 
@@ -82,7 +98,7 @@ struct GroundBundle {
     ground: Ground,
     hoverable: Hoverable,
     nav_mesh_affector: NavMeshAffector,
-    pbr_bundle: PbrBundle,
+    transform_bundle: TransformBundle,
 }
 
 impl GroundBundle {
@@ -98,10 +114,14 @@ impl Default for GroundBundle {
             ground: Ground,
             hoverable: Hoverable,
             nav_mesh_affector: NavMeshAffector,
-            pbr_bundle: Default::default(),
+            transform_bundle: Default::default(),
         }
     }
 }
 
 #[derive(Component)]
 pub(super) struct Ground;
+
+/// Visual part of the ground, spawned only for the active city.
+#[derive(Component)]
+struct GroundMesh;
@@ -1,3 +1,4 @@
+pub(crate) mod blueprint;
 mod buy_lot;
 pub(crate) mod creating_lot;
 pub(crate) mod moving_lot;
@@ -10,7 +11,12 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use strum::EnumIter;
 
-use super::game_world::WorldName;
+use super::{
+    game_world::{recording::AppRecordingExt, WorldName},
+    player::{Owner, Ownership},
+    rejection::{self, RejectReason},
+};
+use blueprint::BlueprintPlugin;
 use buy_lot::BuyLotPlugin;
 use creating_lot::CreatingLotPlugin;
 use moving_lot::MovingLotPlugin;
@@ -20,19 +26,29 @@ pub(super) struct LotPlugin;
 impl Plugin for LotPlugin {
     fn build(&self, app: &mut App) {
         app.add_state::<LotTool>()
-            .add_plugins((CreatingLotPlugin, MovingLotPlugin, BuyLotPlugin))
+            .add_plugins((
+                CreatingLotPlugin,
+                MovingLotPlugin,
+                BuyLotPlugin,
+                BlueprintPlugin,
+            ))
             .register_type::<Vec<Vec2>>()
             .replicate::<LotVertices>()
             .not_replicate_if_present::<Transform, LotVertices>()
             .add_mapped_client_event::<LotSpawn>(SendPolicy::Unordered)
             .add_mapped_client_event::<LotMove>(SendPolicy::Ordered)
             .add_mapped_client_event::<LotDespawn>(SendPolicy::Unordered)
+            .record_mapped_client_event::<LotSpawn>()
+            .record_mapped_client_event::<LotMove>()
+            .record_mapped_client_event::<LotDespawn>()
             .add_server_event::<LotEventConfirmed>(SendPolicy::Unordered)
+            .add_server_event::<LotEventRejected>(SendPolicy::Unordered)
             .add_systems(
                 Update,
                 (
                     (Self::vertices_update_system, Self::init_system)
                         .run_if(resource_exists::<WorldName>()),
+                    rejection::report_system::<LotEventRejected>,
                     (
                         Self::spawn_system,
                         Self::movement_system,
@@ -80,10 +96,12 @@ impl LotPlugin {
         mut commands: Commands,
         mut spawn_events: EventReader<FromClient<LotSpawn>>,
         mut confirm_events: EventWriter<ToClients<LotEventConfirmed>>,
+        ownership: Ownership,
     ) {
         for FromClient { client_id, event } in spawn_events.iter().cloned() {
+            let owner = Owner(ownership.player_id(client_id));
             commands.entity(event.city_entity).with_children(|parent| {
-                parent.spawn(LotBundle::new(event.vertices));
+                parent.spawn(LotBundle::new(event.vertices, owner));
             });
             confirm_events.send(ToClients {
                 mode: SendMode::Direct(client_id),
@@ -95,9 +113,22 @@ impl LotPlugin {
     fn movement_system(
         mut move_events: EventReader<FromClient<LotMove>>,
         mut confirm_events: EventWriter<ToClients<LotEventConfirmed>>,
+        mut reject_events: EventWriter<ToClients<LotEventRejected>>,
+        ownership: Ownership,
         mut lots: Query<&mut LotVertices>,
     ) {
         for FromClient { client_id, event } in move_events.iter().copied() {
+            if !ownership.can_control(client_id, event.entity) {
+                error!(
+                    "client {client_id} is not allowed to move lot {:?}",
+                    event.entity
+                );
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: LotEventRejected(RejectReason::NotOwner),
+                });
+                continue;
+            }
             match lots.get_mut(event.entity) {
                 Ok(mut vertices) => {
                     for vertex in &mut vertices.0 {
@@ -108,7 +139,13 @@ impl LotPlugin {
                         event: LotEventConfirmed,
                     });
                 }
-                Err(e) => error!("unable to apply lot movement: {e}"),
+                Err(e) => {
+                    error!("unable to apply lot movement: {e}");
+                    reject_events.send(ToClients {
+                        mode: SendMode::Direct(client_id),
+                        event: LotEventRejected(RejectReason::InvalidLot),
+                    });
+                }
             }
         }
     }
@@ -117,8 +154,21 @@ impl LotPlugin {
         mut commands: Commands,
         mut despawn_events: EventReader<FromClient<LotDespawn>>,
         mut confirm_events: EventWriter<ToClients<LotEventConfirmed>>,
+        mut reject_events: EventWriter<ToClients<LotEventRejected>>,
+        ownership: Ownership,
     ) {
         for FromClient { client_id, event } in despawn_events.iter().copied() {
+            if !ownership.can_control(client_id, event.0) {
+                error!(
+                    "client {client_id} is not allowed to despawn lot {:?}",
+                    event.0
+                );
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: LotEventRejected(RejectReason::NotOwner),
+                });
+                continue;
+            }
             commands.entity(event.0).despawn();
             confirm_events.send(ToClients {
                 mode: SendMode::Direct(client_id),
@@ -135,6 +185,8 @@ pub(crate) enum LotTool {
     #[default]
     Create,
     Move,
+    Blueprint,
+    Export,
 }
 
 impl LotTool {
@@ -142,6 +194,8 @@ impl LotTool {
         match self {
             Self::Create => "✏",
             Self::Move => "↔",
+            Self::Blueprint => "📋",
+            Self::Export => "📤",
         }
     }
 }
@@ -149,14 +203,16 @@ impl LotTool {
 #[derive(Bundle)]
 struct LotBundle {
     vertices: LotVertices,
+    owner: Owner,
     parent_sync: ParentSync,
     replication: Replication,
 }
 
 impl LotBundle {
-    fn new(vertices: Vec<Vec2>) -> Self {
+    fn new(vertices: Vec<Vec2>, owner: Owner) -> Self {
         Self {
             vertices: LotVertices(vertices),
+            owner,
             parent_sync: Default::default(),
             replication: Replication,
         }
@@ -254,6 +310,10 @@ impl MapEventEntities for LotDespawn {
 #[derive(Debug, Deserialize, Event, Serialize)]
 struct LotEventConfirmed;
 
+/// An event from server which indicates that the action was not applied.
+#[derive(Clone, Copy, Debug, Deref, Deserialize, Event, Serialize)]
+struct LotEventRejected(RejectReason);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,408 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use bevy::{ecs::entity::EntityMap, math::Vec3Swizzles, prelude::*};
+use bevy_replicon::prelude::*;
+use leafwing_input_manager::common_conditions::action_just_pressed;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use super::{LotEventConfirmed, LotEventRejected, LotTool, LotVertices};
+use crate::core::{
+    action::Action,
+    city::CityMode,
+    cursor_hover::CursorHover,
+    error,
+    game_paths::GamePaths,
+    game_state::GameState,
+    game_world::{self, recording::AppRecordingExt, WorldName},
+    ground::Ground,
+    object::{ObjectBundle, ObjectPath},
+    player::Ownership,
+    rejection::RejectReason,
+    wall::{WallBundle, WallEdges},
+};
+
+pub(super) struct BlueprintPlugin;
+
+impl Plugin for BlueprintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BlueprintExportRequest>()
+            .add_event::<BlueprintExport>()
+            .add_event::<BlueprintExported>()
+            .add_mapped_client_event::<BlueprintPlace>(SendPolicy::Unordered)
+            .record_mapped_client_event::<BlueprintPlace>()
+            .add_systems(
+                Update,
+                (
+                    Self::tool_system
+                        .pipe(error::report)
+                        .run_if(action_just_pressed(Action::Confirm))
+                        .run_if(in_state(GameState::City))
+                        .run_if(in_state(CityMode::Lots))
+                        .run_if(in_state(LotTool::Blueprint)),
+                    Self::export_tool_system
+                        .run_if(action_just_pressed(Action::Confirm))
+                        .run_if(in_state(GameState::City))
+                        .run_if(in_state(CityMode::Lots))
+                        .run_if(in_state(LotTool::Export)),
+                    Self::export_system
+                        .pipe(error::report)
+                        .run_if(on_event::<BlueprintExport>())
+                        .run_if(resource_exists::<WorldName>()),
+                    Self::placing_system.run_if(has_authority()),
+                ),
+            );
+    }
+}
+
+impl BlueprintPlugin {
+    /// Places the active blueprint on a hovered empty lot.
+    fn tool_system(
+        mut place_events: EventWriter<BlueprintPlace>,
+        game_paths: Res<GamePaths>,
+        active_blueprint: Option<Res<ActiveBlueprint>>,
+        grounds: Query<&CursorHover, With<Ground>>,
+        lots: Query<(Entity, &LotVertices, Option<&Children>)>,
+        buildings: Query<(), Or<(With<WallEdges>, With<ObjectPath>)>>,
+    ) -> Result<()> {
+        let Ok(hover) = grounds.get_single() else {
+            return Ok(());
+        };
+        let position = hover.xz();
+        let Some((lot_entity, _, children)) = lots
+            .iter()
+            .find(|(_, vertices, _)| vertices.contains_point(position))
+        else {
+            return Ok(());
+        };
+
+        let empty = children.map_or(true, |children| {
+            buildings.iter_many(children.iter()).next().is_none()
+        });
+        if !empty {
+            return Ok(());
+        }
+
+        if let Some(active_blueprint) = active_blueprint {
+            let blueprint_path = game_paths.blueprint_path(&active_blueprint.0);
+            let content = fs::read_to_string(&blueprint_path)
+                .with_context(|| format!("unable to read {blueprint_path:?}"))?;
+            let blueprint = ron::from_str(&content)
+                .with_context(|| format!("unable to deserialize {blueprint_path:?}"))?;
+            place_events.send(BlueprintPlace {
+                lot_entity,
+                blueprint,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Requests a name for exporting a hovered lot with buildings.
+    fn export_tool_system(
+        mut request_events: EventWriter<BlueprintExportRequest>,
+        grounds: Query<&CursorHover, With<Ground>>,
+        lots: Query<(Entity, &LotVertices, &Children)>,
+        buildings: Query<(), Or<(With<WallEdges>, With<ObjectPath>)>>,
+    ) {
+        let Ok(hover) = grounds.get_single() else {
+            return;
+        };
+        let position = hover.xz();
+        if let Some((lot_entity, ..)) = lots.iter().find(|(_, vertices, children)| {
+            vertices.contains_point(position)
+                && buildings.iter_many(children.iter()).next().is_some()
+        }) {
+            request_events.send(BlueprintExportRequest(lot_entity));
+        }
+    }
+
+    fn export_system(
+        mut export_events: EventReader<BlueprintExport>,
+        mut exported_events: EventWriter<BlueprintExported>,
+        game_paths: Res<GamePaths>,
+        lots: Query<(&LotVertices, Option<&Children>)>,
+        walls: Query<&WallEdges>,
+        objects: Query<(&ObjectPath, &Transform)>,
+    ) -> Result<()> {
+        for event in &mut export_events {
+            let (vertices, children) = lots
+                .get(event.lot_entity)
+                .with_context(|| format!("unable to get lot {:?}", event.lot_entity))?;
+            let children = children.map(|children| &**children).unwrap_or_default();
+            let blueprint = Blueprint::new(
+                vertices,
+                walls.iter_many(children).flat_map(|edges| edges.iter()),
+                objects.iter_many(children),
+            );
+
+            let content = ron::ser::to_string_pretty(
+                &blueprint,
+                PrettyConfig::default().indentor("  ".to_string()),
+            )
+            .context("unable to serialize blueprint")?;
+
+            fs::create_dir_all(&game_paths.blueprints)
+                .with_context(|| format!("unable to create {:?}", game_paths.blueprints))?;
+            let blueprint_path = game_paths.new_blueprint_path(&event.name);
+            game_world::write_atomically(&blueprint_path, content.as_bytes())?;
+            info!("exported lot {:?} to {blueprint_path:?}", event.lot_entity);
+            exported_events.send_default();
+        }
+
+        Ok(())
+    }
+
+    fn placing_system(
+        mut commands: Commands,
+        mut place_events: EventReader<FromClient<BlueprintPlace>>,
+        mut confirm_events: EventWriter<ToClients<LotEventConfirmed>>,
+        mut reject_events: EventWriter<ToClients<LotEventRejected>>,
+        ownership: Ownership,
+        lots: Query<(&LotVertices, Option<&Children>)>,
+        buildings: Query<(), Or<(With<WallEdges>, With<ObjectPath>)>>,
+    ) {
+        for FromClient { client_id, event } in place_events.iter().cloned() {
+            if !ownership.can_control(client_id, event.lot_entity) {
+                error!(
+                    "client {client_id} is not allowed to place blueprints on {:?}",
+                    event.lot_entity
+                );
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: LotEventRejected(RejectReason::NotOwner),
+                });
+                continue;
+            }
+            let Ok((vertices, children)) = lots.get(event.lot_entity) else {
+                error!("unable to place blueprint on {:?}: not a lot", event.lot_entity);
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: LotEventRejected(RejectReason::InvalidLot),
+                });
+                continue;
+            };
+            if let Some(children) = children {
+                if buildings.iter_many(children.iter()).next().is_some() {
+                    error!(
+                        "unable to place blueprint on not empty lot {:?}",
+                        event.lot_entity
+                    );
+                    reject_events.send(ToClients {
+                        mode: SendMode::Direct(client_id),
+                        event: LotEventRejected(RejectReason::LotNotEmpty),
+                    });
+                    continue;
+                }
+            }
+            if !event.blueprint.is_compatible(vertices) {
+                error!(
+                    "unable to place blueprint on lot {:?} with different shape",
+                    event.lot_entity
+                );
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: LotEventRejected(RejectReason::LotShapeMismatch),
+                });
+                continue;
+            }
+            if !event.blueprint.is_inside(vertices) {
+                error!(
+                    "unable to place blueprint with buildings outside of lot {:?}",
+                    event.lot_entity
+                );
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: LotEventRejected(RejectReason::InvalidLot),
+                });
+                continue;
+            }
+
+            let origin = vertices[0];
+            commands.entity(event.lot_entity).with_children(|parent| {
+                if !event.blueprint.walls.is_empty() {
+                    let edges = event
+                        .blueprint
+                        .walls
+                        .iter()
+                        .map(|&(a, b)| (a + origin, b + origin))
+                        .collect();
+                    parent.spawn(WallBundle::new(edges));
+                }
+                for object in event.blueprint.objects {
+                    parent.spawn(ObjectBundle::new(
+                        object.metadata_path,
+                        object.translation + Vec3::new(origin.x, 0.0, origin.y),
+                        object.rotation,
+                    ));
+                }
+            });
+            confirm_events.send(ToClients {
+                mode: SendMode::Direct(client_id),
+                event: LotEventConfirmed,
+            });
+        }
+    }
+}
+
+/// Lot with its walls and objects that can be placed on another lot.
+///
+/// All positions are relative to the first lot vertex.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Blueprint {
+    vertices: Vec<Vec2>,
+    walls: Vec<(Vec2, Vec2)>,
+    objects: Vec<BlueprintObject>,
+}
+
+impl Blueprint {
+    fn new<'a>(
+        vertices: &LotVertices,
+        walls: impl Iterator<Item = &'a (Vec2, Vec2)>,
+        objects: impl Iterator<Item = (&'a ObjectPath, &'a Transform)>,
+    ) -> Self {
+        let origin = vertices.first().copied().unwrap_or_default();
+        Self {
+            vertices: vertices.iter().map(|&vertex| vertex - origin).collect(),
+            walls: walls.map(|&(a, b)| (a - origin, b - origin)).collect(),
+            objects: objects
+                .map(|(object_path, transform)| BlueprintObject {
+                    metadata_path: object_path.0.clone(),
+                    translation: transform.translation - Vec3::new(origin.x, 0.0, origin.y),
+                    rotation: transform.rotation,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns `true` if the lot has the same shape as the blueprint.
+    fn is_compatible(&self, vertices: &LotVertices) -> bool {
+        const EPSILON: f32 = 0.01;
+        let Some(&origin) = vertices.first() else {
+            return false;
+        };
+
+        self.vertices.len() == vertices.len()
+            && self
+                .vertices
+                .iter()
+                .zip(vertices.iter())
+                .all(|(&a, &b)| a.abs_diff_eq(b - origin, EPSILON))
+    }
+
+    /// Returns `true` if all walls and objects are within the lot.
+    fn is_inside(&self, vertices: &LotVertices) -> bool {
+        let Some(&origin) = vertices.first() else {
+            return false;
+        };
+
+        self.walls
+            .iter()
+            .flat_map(|&(a, b)| [a, b])
+            .chain(self.objects.iter().map(|object| object.translation.xz()))
+            .all(|point| vertices.contains_point(point + origin))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct BlueprintObject {
+    metadata_path: PathBuf,
+    translation: Vec3,
+    rotation: Quat,
+}
+
+/// Name of the blueprint from [`GamePaths::blueprints`] that will be placed with [`LotTool::Blueprint`].
+#[derive(Resource)]
+pub(crate) struct ActiveBlueprint(pub(crate) String);
+
+/// A lot with buildings was selected with [`LotTool::Export`].
+///
+/// UI should ask for a name and send [`BlueprintExport`].
+#[derive(Event)]
+pub(crate) struct BlueprintExportRequest(pub(crate) Entity);
+
+/// Exports a lot with its walls and objects into [`GamePaths::blueprints`].
+#[derive(Event)]
+pub(crate) struct BlueprintExport {
+    pub(crate) lot_entity: Entity,
+    pub(crate) name: String,
+}
+
+/// A blueprint was successfully written to [`GamePaths::blueprints`].
+#[derive(Default, Event)]
+pub(crate) struct BlueprintExported;
+
+/// Client event to place a blueprint on an empty lot.
+#[derive(Clone, Debug, Deserialize, Event, Serialize)]
+struct BlueprintPlace {
+    lot_entity: Entity,
+    blueprint: Blueprint,
+}
+
+impl MapEventEntities for BlueprintPlace {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapError> {
+        self.lot_entity = entity_map
+            .get(self.lot_entity)
+            .ok_or(MapError(self.lot_entity))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_positions() {
+        const ORIGIN: Vec2 = Vec2::new(5.0, 5.0);
+        let vertices = LotVertices(vec![
+            ORIGIN,
+            ORIGIN + Vec2::X,
+            ORIGIN + Vec2::ONE,
+            ORIGIN + Vec2::Y,
+        ]);
+        let object_path = ObjectPath(PathBuf::from("object.toml"));
+        let transform = Transform::from_xyz(ORIGIN.x, 0.0, ORIGIN.y);
+        let blueprint = Blueprint::new(
+            &vertices,
+            [(ORIGIN, ORIGIN + Vec2::X)].iter(),
+            [(&object_path, &transform)].into_iter(),
+        );
+
+        assert_eq!(blueprint.vertices[0], Vec2::ZERO);
+        assert_eq!(blueprint.walls, &[(Vec2::ZERO, Vec2::X)]);
+        assert_eq!(blueprint.objects[0].translation, Vec3::ZERO);
+
+        let moved_vertices =
+            LotVertices(vertices.iter().map(|&vertex| vertex + Vec2::ONE).collect());
+        assert!(blueprint.is_compatible(&moved_vertices));
+
+        let other_vertices = LotVertices(vec![ORIGIN, ORIGIN + Vec2::X, ORIGIN + Vec2::Y]);
+        assert!(!blueprint.is_compatible(&other_vertices));
+    }
+
+    #[test]
+    fn buildings_inside() {
+        let vertices = LotVertices(vec![
+            Vec2::ZERO,
+            Vec2::new(0.0, 2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(2.0, 0.0),
+        ]);
+        let mut blueprint = Blueprint {
+            vertices: vertices.0.clone(),
+            walls: vec![(Vec2::new(0.5, 0.5), Vec2::new(1.5, 0.5))],
+            objects: vec![BlueprintObject {
+                metadata_path: PathBuf::from("object.toml"),
+                translation: Vec3::new(1.0, 0.0, 1.0),
+                rotation: Quat::IDENTITY,
+            }],
+        };
+        assert!(blueprint.is_inside(&vertices));
+
+        blueprint.objects[0].translation = Vec3::new(3.0, 0.0, 1.0);
+        assert!(!blueprint.is_inside(&vertices));
+    }
+}
@@ -7,10 +7,12 @@ use bevy_replicon::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::core::{
+    chat::{self, ChatMessage},
     cursor_hover::CursorHover,
     family::ActorFamily,
     ground::Ground,
-    task::{Task, TaskList, TaskListSet, TaskState},
+    player::Owner,
+    task::{Task, TaskFinished, TaskList, TaskListSet, TaskOutcome, TaskState},
 };
 
 use super::{LotFamily, LotVertices};
@@ -48,8 +50,12 @@ impl BuyLotPlugin {
 
     fn buying_system(
         mut commands: Commands,
+        mut message_events: EventWriter<ToClients<ChatMessage>>,
+        mut finish_events: EventWriter<TaskFinished>,
         lots: Query<(), Without<LotFamily>>,
         actors: Query<&ActorFamily>,
+        owners: Query<&Owner>,
+        names: Query<&Name>,
         tasks: Query<(Entity, &Parent, &BuyLot, &TaskState), Changed<TaskState>>,
     ) {
         for (entity, parent, buy, &state) in &tasks {
@@ -57,12 +63,27 @@ impl BuyLotPlugin {
                 let family = actors
                     .get(**parent)
                     .expect("actors should have assigned family");
-                if lots.get(buy.0).is_ok() {
-                    commands.entity(buy.0).insert(LotFamily(family.0));
+                let outcome = if lots.get(buy.0).is_ok() {
+                    let mut lot_entity = commands.entity(buy.0);
+                    lot_entity.insert(LotFamily(family.0));
+                    // Lot now belongs to the player who controls the family.
+                    match owners.get(family.0) {
+                        Ok(&owner) => lot_entity.insert(owner),
+                        Err(_) => lot_entity.remove::<Owner>(),
+                    };
+                    if let Ok(name) = names.get(family.0) {
+                        chat::announce(&mut message_events, format!("Family {name} moved in"));
+                    }
+                    TaskOutcome::Success
                 } else {
                     error!("{buy:?} from actor {entity:?} points to not a lot");
-                }
-                commands.entity(entity).despawn();
+                    TaskOutcome::Failed
+                };
+                finish_events.send(TaskFinished {
+                    task: entity,
+                    actor: **parent,
+                    outcome,
+                });
             }
         }
     }
@@ -8,7 +8,7 @@ use crate::core::{
     game_state::GameState,
 };
 
-use super::{LotEventConfirmed, LotSpawn, LotTool, LotVertices};
+use super::{LotEventConfirmed, LotEventRejected, LotSpawn, LotTool, LotVertices};
 
 pub(super) struct CreatingLotPlugin;
 
@@ -23,7 +23,9 @@ impl Plugin for CreatingLotPlugin {
                 Self::movement_system,
                 Self::vertex_placement_system.run_if(action_just_pressed(Action::Confirm)),
                 Self::despawn_system.run_if(
-                    action_just_pressed(Action::Cancel).or_else(on_event::<LotEventConfirmed>()),
+                    action_just_pressed(Action::Cancel)
+                        .or_else(on_event::<LotEventConfirmed>())
+                        .or_else(on_event::<LotEventRejected>()),
                 ),
             )
                 .run_if(in_state(GameState::City))
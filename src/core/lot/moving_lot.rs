@@ -1,7 +1,7 @@
 use bevy::{math::Vec3Swizzles, prelude::*};
 use leafwing_input_manager::common_conditions::action_just_pressed;
 
-use super::{LotDespawn, LotEventConfirmed, LotMove, LotTool, LotVertices};
+use super::{LotDespawn, LotEventConfirmed, LotEventRejected, LotMove, LotTool, LotVertices};
 use crate::core::{
     action::Action,
     city::{ActiveCity, CityMode},
@@ -23,7 +23,9 @@ impl Plugin for MovingLotPlugin {
                 Self::confirmation_system.run_if(action_just_pressed(Action::Confirm)),
                 Self::despawn_system.run_if(action_just_pressed(Action::Delete)),
                 Self::cleanup_system.after(Self::movement_system).run_if(
-                    action_just_pressed(Action::Cancel).or_else(on_event::<LotEventConfirmed>()),
+                    action_just_pressed(Action::Cancel)
+                        .or_else(on_event::<LotEventConfirmed>())
+                        .or_else(on_event::<LotEventRejected>()),
                 ),
             )
                 .run_if(in_state(GameState::City))
@@ -22,6 +22,7 @@ impl Plugin for NavigationPlugin {
             .add_systems(
                 Update,
                 (
+                    Self::init_system,
                     Self::navigation_system,
                     Self::poll_system,
                     Self::cleanup_system,
@@ -32,15 +33,31 @@ impl Plugin for NavigationPlugin {
 }
 
 impl NavigationPlugin {
+    fn init_system(mut commands: Commands, actors: Query<Entity, Added<Navigation>>) {
+        for entity in &actors {
+            commands.entity(entity).remove::<NoPath>();
+        }
+    }
+
     fn poll_system(mut commands: Commands, mut actors: Query<(Entity, &mut ComputePath)>) {
         for (entity, mut compute_path) in &mut actors {
-            if let Some(mut path) = future::block_on(future::poll_once(&mut compute_path.0)) {
-                path.reverse();
-                path.pop(); // Drop current position.
-                commands
-                    .entity(entity)
-                    .insert(NavPath(path))
-                    .remove::<ComputePath>();
+            match future::block_on(future::poll_once(&mut compute_path.0)) {
+                Some(Some(mut path)) => {
+                    path.reverse();
+                    path.pop(); // Drop current position.
+                    commands
+                        .entity(entity)
+                        .insert(NavPath(path))
+                        .remove::<ComputePath>();
+                }
+                Some(None) => {
+                    warn!("unable to find path for {entity:?}");
+                    commands
+                        .entity(entity)
+                        .insert(NoPath)
+                        .remove::<(ComputePath, Navigation)>();
+                }
+                None => (),
             }
         }
     }
@@ -108,8 +125,14 @@ impl Navigation {
     }
 }
 
+/// Indicates that the last navigation was stopped because the destination is unreachable.
+///
+/// Removed when a new navigation starts.
+#[derive(Component)]
+pub(crate) struct NoPath;
+
 #[derive(Component)]
-struct ComputePath(Task<Vec<Vec3>>);
+struct ComputePath(Task<Option<Vec<Vec3>>>);
 
 impl ComputePath {
     fn new(
@@ -121,8 +144,7 @@ impl ComputePath {
         let thread_pool = AsyncComputeTaskPool::get();
         let task = thread_pool.spawn(async move {
             let tiles = tiles.read().expect("tiles shouldn't be poisoned");
-            query::find_path(&tiles, &settings, start, end, None, None)
-                .expect("navigation should happen only inside the city")
+            query::find_path(&tiles, &settings, start, end, None, None).ok()
         });
 
         Self(task)
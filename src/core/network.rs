@@ -1,34 +1,60 @@
+pub(crate) mod conditioner;
+pub(crate) mod discovery;
+pub(crate) mod interpolation;
+pub(crate) mod reconnect;
+
 use std::{
-    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    fs,
+    io::{self, ErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    path::Path,
     time::SystemTime,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use bevy::prelude::*;
 use bevy_replicon::{
     prelude::*,
     renet::{
         transport::{
-            ClientAuthentication, NetcodeClientTransport, NetcodeServerTransport,
-            ServerAuthentication, ServerConfig,
+            self, ClientAuthentication, ConnectToken, NetcodeClientTransport,
+            NetcodeServerTransport, ServerAuthentication, ServerConfig, NETCODE_KEY_BYTES,
         },
-        ChannelConfig, ConnectionConfig, RenetClient, RenetServer,
+        ChannelConfig, ConnectionConfig, RenetClient, RenetServer, ServerEvent,
     },
     transport::client_just_connected,
 };
 
-use super::{game_state::GameState, game_world::WorldName};
+use super::{
+    game_state::GameState,
+    game_world::WorldName,
+    player,
+    settings::{NetworkConditions, PlayerSettings},
+};
+use conditioner::{ConditionerPlugin, LinkConditioner};
+use discovery::DiscoveryPlugin;
+use interpolation::InterpolationPlugin;
+use reconnect::ReconnectPlugin;
 
 pub(super) struct NetworkPlugin;
 
 impl Plugin for NetworkPlugin {
     fn build(&self, app: &mut App) {
-        app.replicate::<Transform>()
-            .replicate::<Name>()
-            .add_systems(
-                Update,
+        app.add_plugins((
+            ConditionerPlugin,
+            DiscoveryPlugin,
+            InterpolationPlugin,
+            ReconnectPlugin,
+        ))
+        .replicate::<Transform>()
+        .replicate::<Name>()
+        .add_systems(
+            Update,
+            (
                 Self::client_connection_system.run_if(client_just_connected()),
-            );
+                Self::server_event_system.run_if(resource_exists::<RenetServer>()),
+            ),
+        );
     }
 }
 
@@ -40,13 +66,77 @@ impl NetworkPlugin {
         commands.insert_resource(WorldName::default());
         game_state.set(GameState::World);
     }
+
+    fn server_event_system(mut server_events: EventReader<ServerEvent>) {
+        for event in &mut server_events {
+            match event {
+                ServerEvent::ClientConnected { client_id } => {
+                    info!("client {client_id} connected");
+                }
+                ServerEvent::ClientDisconnected { client_id, reason } => {
+                    info!("client {client_id} disconnected: {reason}");
+                }
+            }
+        }
+    }
 }
 
 pub(crate) const DEFAULT_PORT: u16 = 4761;
+pub(crate) const DEFAULT_MAX_CLIENTS: usize = 4;
+/// Default lifetime of issued connect tokens in seconds.
+pub(crate) const DEFAULT_TOKEN_EXPIRATION: u64 = 24 * 60 * 60;
 const PROTOCOL_ID: u64 = 7;
+/// Seconds without packets after which the connection is considered lost.
+const TOKEN_TIMEOUT: i32 = 15;
+
+/// Parameters for [`create_server`].
+#[derive(Clone, Debug)]
+pub(crate) struct ServerSettings {
+    /// Local address to listen on.
+    ///
+    /// Unspecified address accepts connections on all interfaces.
+    pub(crate) bind_ip: IpAddr,
+    /// Address that clients use to connect.
+    ///
+    /// Should be specified if it differs from [`Self::bind_ip`].
+    pub(crate) public_ip: Option<IpAddr>,
+    pub(crate) port: u16,
+    /// Maximum number of simultaneously connected clients.
+    pub(crate) max_clients: usize,
+    /// Key for verifying connect tokens.
+    ///
+    /// If set, only clients with tokens from [`issue_token`] can connect.
+    pub(crate) private_key: Option<[u8; NETCODE_KEY_BYTES]>,
+}
+
+impl ServerSettings {
+    fn public_addr(&self) -> Result<SocketAddr> {
+        let public_ip = match self.public_ip {
+            Some(public_ip) => public_ip,
+            None if self.bind_ip.is_unspecified() => {
+                bail!("public address should be specified when listening on all interfaces")
+            }
+            None => self.bind_ip,
+        };
+
+        Ok(SocketAddr::new(public_ip, self.port))
+    }
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind_ip: Ipv4Addr::LOCALHOST.into(),
+            public_ip: None,
+            port: DEFAULT_PORT,
+            max_clients: DEFAULT_MAX_CLIENTS,
+            private_key: None,
+        }
+    }
+}
 
 pub(crate) fn create_server(
-    port: u16,
+    settings: &ServerSettings,
     server_channels_config: Vec<ChannelConfig>,
     client_channels_config: Vec<ChannelConfig>,
 ) -> Result<(RenetServer, NetcodeServerTransport)> {
@@ -57,22 +147,77 @@ pub(crate) fn create_server(
     });
 
     let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
-    let public_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port);
-    let socket = UdpSocket::bind(public_addr)?;
+    let public_addr = settings.public_addr()?;
+    let bind_addr = SocketAddr::new(settings.bind_ip, settings.port);
+    let socket =
+        UdpSocket::bind(bind_addr).with_context(|| format!("unable to bind to {bind_addr}"))?;
     let server_config = ServerConfig {
-        max_clients: 1,
+        max_clients: settings.max_clients,
         protocol_id: PROTOCOL_ID,
         public_addr,
-        authentication: ServerAuthentication::Unsecure,
+        authentication: match settings.private_key {
+            Some(private_key) => ServerAuthentication::Secure { private_key },
+            None => ServerAuthentication::Unsecure,
+        },
     };
     let transport = NetcodeServerTransport::new(current_time, server_config, socket)?;
 
     Ok((server, transport))
 }
 
-pub(crate) fn create_client(
-    ip: IpAddr,
-    port: u16,
+/// Parameters for [`create_client`].
+///
+/// Kept as a resource while connected to reconnect with the same parameters.
+#[derive(Clone, Resource)]
+pub(crate) struct ClientSettings {
+    pub(crate) ip: IpAddr,
+    pub(crate) port: u16,
+    /// Token required for servers with [`ServerSettings::private_key`].
+    ///
+    /// If not set, the client connects with the identity from [`Self::player`].
+    pub(crate) connect_token: Option<ConnectToken>,
+    pub(crate) player: PlayerSettings,
+    /// Simulated conditions, applied only to connections without [`Self::connect_token`].
+    pub(crate) conditions: NetworkConditions,
+}
+
+/// Creates a client connection and inserts it as resources together with its settings.
+pub(crate) fn connect(
+    commands: &mut Commands,
+    network_channels: &NetworkChannels,
+    settings: ClientSettings,
+) -> Result<()> {
+    let mut server_addr = SocketAddr::new(settings.ip, settings.port);
+    if settings.conditions.is_ideal() {
+        commands.remove_resource::<LinkConditioner>();
+    } else if settings.connect_token.is_some() {
+        // Token contains server addresses, so the client can't be redirected.
+        warn!("network conditions can't be simulated for connections with tokens");
+    } else {
+        let conditioner = LinkConditioner::new(settings.conditions.clone(), server_addr)
+            .context("unable to create link conditioner")?;
+        info!("simulating {:?}", settings.conditions);
+        server_addr = conditioner.local_addr()?;
+        commands.insert_resource(conditioner);
+    }
+
+    let (client, transport) = create_client(
+        &settings,
+        server_addr,
+        network_channels.server_channels(),
+        network_channels.client_channels(),
+    )
+    .context("unable to create connection")?;
+    commands.insert_resource(client);
+    commands.insert_resource(transport);
+    commands.insert_resource(settings);
+
+    Ok(())
+}
+
+fn create_client(
+    settings: &ClientSettings,
+    server_addr: SocketAddr,
     server_channels_config: Vec<ChannelConfig>,
     client_channels_config: Vec<ChannelConfig>,
 ) -> Result<(RenetClient, NetcodeClientTransport)> {
@@ -83,16 +228,259 @@ pub(crate) fn create_client(
     });
 
     let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
-    let client_id = current_time.as_millis() as u64;
-    let server_addr = SocketAddr::new(ip, port);
-    let socket = UdpSocket::bind((ip, 0))?;
-    let authentication = ClientAuthentication::Unsecure {
-        client_id,
-        protocol_id: PROTOCOL_ID,
-        server_addr,
-        user_data: None,
+    // Bind to all interfaces because the server could be on another machine.
+    let bind_ip: IpAddr = match server_addr.ip() {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = UdpSocket::bind((bind_ip, 0))?;
+    let authentication = match &settings.connect_token {
+        Some(connect_token) => ClientAuthentication::Secure {
+            connect_token: connect_token.clone(),
+        },
+        None => ClientAuthentication::Unsecure {
+            client_id: settings.player.id,
+            protocol_id: PROTOCOL_ID,
+            server_addr,
+            user_data: Some(player::name_to_user_data(&settings.player.name)),
+        },
     };
     let transport = NetcodeClientTransport::new(current_time, authentication, socket)?;
 
     Ok((client, transport))
 }
+
+/// Reads server private key for [`ServerSettings::private_key`].
+///
+/// Generates a new key if the file doesn't exist.
+pub(crate) fn read_private_key(key_path: &Path) -> Result<[u8; NETCODE_KEY_BYTES]> {
+    if !key_path.exists() {
+        let key_dir = key_path
+            .parent()
+            .expect("key path should have a parent dir");
+        fs::create_dir_all(key_dir).with_context(|| format!("unable to create {key_dir:?}"))?;
+        let private_key = transport::generate_random_bytes();
+        fs::write(key_path, private_key)
+            .with_context(|| format!("unable to write {key_path:?}"))?;
+        info!("generated new server key at {key_path:?}");
+        return Ok(private_key);
+    }
+
+    let bytes = fs::read(key_path).with_context(|| format!("unable to read {key_path:?}"))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("{key_path:?} should contain {NETCODE_KEY_BYTES} bytes"))
+}
+
+/// Generates a token that allows a single player to connect to the server with the private key.
+///
+/// The token contains player identity, so the client settings are ignored.
+pub(crate) fn issue_token(
+    private_key: &[u8; NETCODE_KEY_BYTES],
+    public_addr: SocketAddr,
+    expire_seconds: u64,
+    player: &PlayerSettings,
+) -> Result<ConnectToken> {
+    if player.id == SERVER_ID {
+        bail!("player ID {SERVER_ID} is reserved for the host");
+    }
+
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+    let connect_token = ConnectToken::generate(
+        current_time,
+        PROTOCOL_ID,
+        expire_seconds,
+        player.id,
+        TOKEN_TIMEOUT,
+        vec![public_addr],
+        Some(&player::name_to_user_data(&player.name)),
+        private_key,
+    )
+    .context("unable to generate connect token")?;
+
+    Ok(connect_token)
+}
+
+pub(crate) fn write_token(connect_token: &ConnectToken, token_path: &Path) -> Result<()> {
+    let mut bytes = Vec::new();
+    connect_token
+        .write(&mut bytes)
+        .context("unable to serialize connect token")?;
+    fs::write(token_path, bytes).with_context(|| format!("unable to write {token_path:?}"))
+}
+
+pub(crate) fn read_token(token_path: &Path) -> Result<ConnectToken> {
+    let bytes = fs::read(token_path).with_context(|| format!("unable to read {token_path:?}"))?;
+    ConnectToken::read(&mut bytes.as_slice())
+        .with_context(|| format!("unable to deserialize token from {token_path:?}"))
+}
+
+/// Reads a single packet from the non-blocking socket.
+///
+/// Returns [`None`] if there are no more packets.
+fn receive(socket: &UdpSocket, buffer: &mut [u8]) -> io::Result<Option<(usize, SocketAddr)>> {
+    match socket.recv_from(buffer) {
+        Ok(packet) => Ok(Some(packet)),
+        Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+        // Windows reports ICMP port unreachable from previous sends as a receive error,
+        // Linux does the same for connected sockets.
+        Err(e)
+            if e.kind() == ErrorKind::ConnectionReset
+                || e.kind() == ErrorKind::ConnectionRefused =>
+        {
+            debug!("ignoring unreachable peer: {e}");
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+    use crate::core::{city::City, game_paths::GamePaths};
+
+    #[test]
+    fn token_roundtrip() -> Result<()> {
+        let game_paths = GamePaths::default();
+        let private_key = read_private_key(&game_paths.server_key)?;
+        assert_eq!(read_private_key(&game_paths.server_key)?, private_key);
+
+        let public_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), DEFAULT_PORT);
+        let connect_token = issue_token(
+            &private_key,
+            public_addr,
+            DEFAULT_TOKEN_EXPIRATION,
+            &PlayerSettings {
+                id: 1,
+                ..Default::default()
+            },
+        )?;
+        let token_path = game_paths.server_key.with_extension("token");
+        write_token(&connect_token, &token_path)?;
+        let mut bytes = Vec::new();
+        read_token(&token_path)?.write(&mut bytes)?;
+        assert_eq!(bytes, fs::read(&token_path)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn simultaneous_and_late_joins() -> Result<()> {
+        // Reserve an ephemeral port to avoid conflicts with running servers.
+        let port = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?
+            .local_addr()?
+            .port();
+        let server_settings = ServerSettings {
+            port,
+            ..Default::default()
+        };
+
+        let mut server_app = replication_app();
+        let network_channels = server_app.world.resource::<NetworkChannels>();
+        let (server, transport) = create_server(
+            &server_settings,
+            network_channels.server_channels(),
+            network_channels.client_channels(),
+        )?;
+        server_app
+            .insert_resource(server)
+            .insert_resource(transport);
+        server_app.world.spawn((City, Replication));
+
+        let mut first_client = client_app(1, port)?;
+        let mut second_client = client_app(2, port)?;
+        update_until(
+            &mut [&mut server_app, &mut first_client, &mut second_client],
+            |apps| apps[1..].iter().all(|app| city_count(app) == 1),
+        );
+        assert_eq!(
+            server_app
+                .world
+                .resource::<RenetServer>()
+                .clients_id()
+                .len(),
+            2,
+            "both clients should connect at the same time"
+        );
+
+        // Join after the world changed.
+        server_app.world.spawn((City, Replication));
+        let mut late_client = client_app(3, port)?;
+        update_until(
+            &mut [
+                &mut server_app,
+                &mut first_client,
+                &mut second_client,
+                &mut late_client,
+            ],
+            |apps| apps[1..].iter().all(|app| city_count(app) == 2),
+        );
+        assert_eq!(
+            server_app
+                .world
+                .resource::<RenetServer>()
+                .clients_id()
+                .len(),
+            3
+        );
+
+        Ok(())
+    }
+
+    fn replication_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, ReplicationPlugins))
+            .replicate::<City>();
+        app
+    }
+
+    fn client_app(id: u64, port: u16) -> Result<App> {
+        let mut app = replication_app();
+        let settings = ClientSettings {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            port,
+            connect_token: None,
+            player: PlayerSettings {
+                name: format!("Player {id}"),
+                id,
+            },
+            conditions: Default::default(),
+        };
+        let network_channels = app.world.resource::<NetworkChannels>();
+        let (client, transport) = create_client(
+            &settings,
+            SocketAddr::new(settings.ip, settings.port),
+            network_channels.server_channels(),
+            network_channels.client_channels(),
+        )?;
+        app.insert_resource(client).insert_resource(transport);
+
+        Ok(app)
+    }
+
+    /// Updates all apps until the condition is met.
+    ///
+    /// Panics if it takes too long.
+    fn update_until(apps: &mut [&mut App], condition: impl Fn(&[&mut App]) -> bool) {
+        for _ in 0..500 {
+            for app in apps.iter_mut() {
+                app.update();
+            }
+            if condition(apps) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("apps should reach the expected state");
+    }
+
+    fn city_count(app: &App) -> usize {
+        app.world
+            .iter_entities()
+            .filter(|entity| entity.contains::<City>())
+            .count()
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use bevy_replicon::renet::transport::NetcodeClientTransport;
+
+use crate::core::{error, settings::NetworkConditions};
+
+/// Relays packets between the client transport and the server with simulated [`NetworkConditions`].
+pub(super) struct ConditionerPlugin;
+
+impl Plugin for ConditionerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            First,
+            (
+                Self::relay_system
+                    .pipe(error::report)
+                    .run_if(resource_exists::<LinkConditioner>()),
+                Self::cleanup_system.run_if(resource_removed::<NetcodeClientTransport>()),
+            ),
+        );
+    }
+}
+
+impl ConditionerPlugin {
+    fn relay_system(mut conditioner: ResMut<LinkConditioner>) -> Result<()> {
+        conditioner.receive()?;
+        conditioner.send()
+    }
+
+    fn cleanup_system(mut commands: Commands) {
+        commands.remove_resource::<LinkConditioner>();
+    }
+}
+
+/// Netcode packets are limited by the MTU, so this is enough for any of them.
+const MAX_PACKET_SIZE: usize = 2048;
+
+/// Local UDP relay between the client transport and the server.
+///
+/// Netcode transports own their sockets, so the client connects to the relay instead of the server.
+#[derive(Resource)]
+pub(crate) struct LinkConditioner {
+    conditions: NetworkConditions,
+    /// Socket to which the client transport sends packets.
+    local_socket: UdpSocket,
+    /// Address of the client transport, known after its first packet.
+    client_addr: Option<SocketAddr>,
+    /// Socket connected to the server.
+    server_socket: UdpSocket,
+    queue: Vec<DelayedPacket>,
+}
+
+impl LinkConditioner {
+    pub(super) fn new(conditions: NetworkConditions, server_addr: SocketAddr) -> Result<Self> {
+        let (local_ip, unspecified_ip): (IpAddr, IpAddr) = match server_addr {
+            SocketAddr::V4(_) => (Ipv4Addr::LOCALHOST.into(), Ipv4Addr::UNSPECIFIED.into()),
+            SocketAddr::V6(_) => (Ipv6Addr::LOCALHOST.into(), Ipv6Addr::UNSPECIFIED.into()),
+        };
+        let local_socket = UdpSocket::bind((local_ip, 0))?;
+        local_socket.set_nonblocking(true)?;
+        let server_socket = UdpSocket::bind((unspecified_ip, 0))?;
+        server_socket.set_nonblocking(true)?;
+        server_socket
+            .connect(server_addr)
+            .with_context(|| format!("unable to connect to {server_addr}"))?;
+
+        Ok(Self {
+            conditions,
+            local_socket,
+            client_addr: None,
+            server_socket,
+            queue: Default::default(),
+        })
+    }
+
+    /// Returns address to which the client should connect.
+    pub(super) fn local_addr(&self) -> Result<SocketAddr> {
+        self.local_socket
+            .local_addr()
+            .context("unable to get link conditioner address")
+    }
+
+    /// Queues packets from both sides.
+    fn receive(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        while let Some((len, addr)) = super::receive(&self.local_socket, &mut buffer)
+            .context("unable to receive packet from client")?
+        {
+            self.client_addr = Some(addr);
+            self.enqueue(now, PacketTarget::Server, &buffer[..len]);
+        }
+        while let Some((len, _)) = super::receive(&self.server_socket, &mut buffer)
+            .context("unable to receive packet from server")?
+        {
+            self.enqueue(now, PacketTarget::Client, &buffer[..len]);
+        }
+
+        Ok(())
+    }
+
+    fn enqueue(&mut self, now: Instant, target: PacketTarget, bytes: &[u8]) {
+        if fastrand::f32() < self.conditions.packet_loss {
+            return;
+        }
+
+        let copies = if fastrand::f32() < self.conditions.duplication {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            self.queue.push(DelayedPacket {
+                send_at: now + self.delay(),
+                target,
+                bytes: bytes.to_vec(),
+            });
+        }
+    }
+
+    /// Returns latency with a random jitter.
+    ///
+    /// Packets could be reordered because of jitter, as in real networks.
+    fn delay(&self) -> Duration {
+        let jitter = self.conditions.jitter as i64;
+        let delay = self.conditions.latency as i64 + fastrand::i64(-jitter..=jitter);
+        Duration::from_millis(delay.max(0) as u64)
+    }
+
+    /// Sends packets whose delay has passed.
+    fn send(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let mut result = Ok(());
+        self.queue.retain(|packet| {
+            if packet.send_at > now {
+                return true;
+            }
+
+            let sent = match packet.target {
+                PacketTarget::Server => self.server_socket.send(&packet.bytes).map(|_| ()),
+                PacketTarget::Client => match self.client_addr {
+                    Some(addr) => self.local_socket.send_to(&packet.bytes, addr).map(|_| ()),
+                    None => Ok(()),
+                },
+            };
+            match sent {
+                // The server could be down, for example during reconnection.
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+                    debug!("unable to relay packet to the server: {e}");
+                }
+                Err(e) => result = Err(e),
+                Ok(()) => (),
+            }
+            false
+        });
+
+        result.context("unable to relay packet")
+    }
+}
+
+struct DelayedPacket {
+    send_at: Instant,
+    target: PacketTarget,
+    bytes: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+enum PacketTarget {
+    Server,
+    Client,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn relay() -> Result<()> {
+        let server = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        server.set_read_timeout(Some(Duration::from_secs(1)))?;
+        let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        client.set_nonblocking(true)?;
+
+        let mut conditioner = LinkConditioner::new(
+            NetworkConditions {
+                duplication: 1.0,
+                ..Default::default()
+            },
+            server.local_addr()?,
+        )?;
+        client.send_to(b"ping", conditioner.local_addr()?)?;
+        relay_until(&mut conditioner, |conditioner| {
+            conditioner.client_addr.is_some()
+        })?;
+
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        for _ in 0..2 {
+            let (len, addr) = server.recv_from(&mut buffer)?;
+            assert_eq!(&buffer[..len], b"ping");
+            server.send_to(b"pong", addr)?;
+        }
+
+        let mut received = 0;
+        relay_until(&mut conditioner, |_| {
+            while client.recv(&mut buffer).is_ok() {
+                received += 1;
+            }
+            received == 4
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn packet_loss() -> Result<()> {
+        let server = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+
+        let mut conditioner = LinkConditioner::new(
+            NetworkConditions {
+                packet_loss: 1.0,
+                ..Default::default()
+            },
+            server.local_addr()?,
+        )?;
+        client.send_to(b"ping", conditioner.local_addr()?)?;
+        relay_until(&mut conditioner, |conditioner| {
+            conditioner.client_addr.is_some()
+        })?;
+        assert!(conditioner.queue.is_empty(), "packet should be dropped");
+
+        Ok(())
+    }
+
+    /// Relays packets until the condition is met, loopback delivery isn't instant.
+    fn relay_until(
+        conditioner: &mut LinkConditioner,
+        mut condition: impl FnMut(&LinkConditioner) -> bool,
+    ) -> Result<()> {
+        for _ in 0..100 {
+            conditioner.receive()?;
+            conditioner.send()?;
+            if condition(conditioner) {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("condition should be met");
+    }
+}
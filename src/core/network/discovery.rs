@@ -0,0 +1,301 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_replicon::renet::RenetServer;
+use serde::{Deserialize, Serialize};
+
+use super::{ServerSettings, PROTOCOL_ID};
+use crate::core::{error, game_state::GameState, game_world::WorldName};
+
+pub(super) struct DiscoveryPlugin;
+
+impl Plugin for DiscoveryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::WorldBrowser),
+            Self::discovery_start_system.pipe(error::report),
+        )
+        .add_systems(OnExit(GameState::WorldBrowser), Self::discovery_stop_system)
+        .add_systems(
+            Update,
+            (
+                Self::response_system
+                    .pipe(error::report)
+                    .run_if(resource_exists::<DiscoveryResponder>())
+                    .run_if(resource_exists::<RenetServer>())
+                    .run_if(resource_exists::<WorldName>()),
+                Self::responder_cleanup_system.run_if(resource_removed::<RenetServer>()),
+                (
+                    Self::query_system
+                        .pipe(error::report)
+                        .run_if(resource_added::<LanDiscovery>().or_else(on_timer(QUERY_INTERVAL))),
+                    Self::receive_system.pipe(error::report),
+                )
+                    .chain()
+                    .run_if(resource_exists::<LanDiscovery>()),
+            ),
+        );
+    }
+}
+
+impl DiscoveryPlugin {
+    fn response_system(
+        responder: Res<DiscoveryResponder>,
+        server: Res<RenetServer>,
+        world_name: Res<WorldName>,
+    ) -> Result<()> {
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        while let Some((len, addr)) = super::receive(&responder.socket, &mut buffer)
+            .context("unable to receive discovery query")?
+        {
+            if &buffer[..len] != QUERY {
+                debug!("ignoring unknown discovery packet from {addr}");
+                continue;
+            }
+            if responder.bind_ip.is_loopback() && !addr.ip().is_loopback() {
+                // Remote peers can't join a server bound to a loopback address.
+                debug!("ignoring discovery query from remote {addr}");
+                continue;
+            }
+
+            let info = ServerInfo {
+                protocol_id: PROTOCOL_ID,
+                world_name: world_name.0.clone(),
+                players: server.clients_id().len(),
+                max_players: responder.max_players,
+                port: responder.port,
+                secure: responder.secure,
+            };
+            let bytes = bincode::serialize(&info).context("unable to serialize server info")?;
+            responder
+                .socket
+                .send_to(&bytes, addr)
+                .with_context(|| format!("unable to answer discovery query from {addr}"))?;
+        }
+
+        Ok(())
+    }
+
+    fn responder_cleanup_system(mut commands: Commands) {
+        commands.remove_resource::<DiscoveryResponder>();
+    }
+
+    fn discovery_start_system(mut commands: Commands) -> Result<()> {
+        commands.insert_resource(LanDiscovery::new()?);
+        Ok(())
+    }
+
+    fn discovery_stop_system(mut commands: Commands) {
+        commands.remove_resource::<LanDiscovery>();
+    }
+
+    fn query_system(discovery: Res<LanDiscovery>) -> Result<()> {
+        // Loopback interface doesn't receive broadcasts,
+        // so query it directly to find servers on the same machine.
+        for ip in [Ipv4Addr::BROADCAST, Ipv4Addr::LOCALHOST] {
+            let addr = SocketAddr::new(ip.into(), DISCOVERY_PORT);
+            if let Err(e) = discovery.socket.send_to(QUERY, addr) {
+                // Broadcasts could be unavailable without network, it's not an error.
+                debug!("unable to send discovery query to {addr}: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn receive_system(time: Res<Time>, mut discovery: ResMut<LanDiscovery>) -> Result<()> {
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        let mut changed = false;
+        while let Some((len, addr)) = super::receive(&discovery.socket, &mut buffer)
+            .context("unable to receive discovery response")?
+        {
+            let info: ServerInfo = match bincode::deserialize(&buffer[..len]) {
+                Ok(info) => info,
+                Err(e) => {
+                    debug!("ignoring invalid discovery response from {addr}: {e}");
+                    continue;
+                }
+            };
+            if info.protocol_id != PROTOCOL_ID {
+                debug!("ignoring server {addr} with incompatible protocol");
+                continue;
+            }
+
+            let server = DiscoveredServer {
+                ip: addr.ip(),
+                info,
+                last_seen: time.elapsed(),
+            };
+            let servers = &mut discovery.bypass_change_detection().servers;
+            match servers
+                .iter_mut()
+                .find(|other| other.ip == server.ip && other.info.port == server.info.port)
+            {
+                Some(other) => {
+                    changed |= other.info != server.info;
+                    *other = server;
+                }
+                None => {
+                    servers.push(server);
+                    changed = true;
+                }
+            }
+        }
+
+        let servers = &mut discovery.bypass_change_detection().servers;
+        let count = servers.len();
+        servers.retain(|server| time.elapsed() - server.last_seen < SERVER_TIMEOUT);
+        if changed || servers.len() != count {
+            discovery.set_changed();
+        }
+
+        Ok(())
+    }
+}
+
+/// Port on which servers answer discovery queries.
+pub(crate) const DISCOVERY_PORT: u16 = 4762;
+const QUERY_INTERVAL: Duration = Duration::from_secs(2);
+/// Time after which a server that stopped answering is removed from the list.
+const SERVER_TIMEOUT: Duration = Duration::from_secs(6);
+const QUERY: &[u8] = b"lifescape-discovery";
+const MAX_PACKET_SIZE: usize = 1200;
+
+/// Answers LAN discovery queries with information about the hosted world.
+#[derive(Resource)]
+pub(crate) struct DiscoveryResponder {
+    socket: UdpSocket,
+    /// Address of the game server, queries from other machines are ignored if it's a loopback.
+    bind_ip: IpAddr,
+    port: u16,
+    max_players: usize,
+    secure: bool,
+}
+
+impl DiscoveryResponder {
+    /// Listens for queries on all interfaces since broadcasts don't reach sockets bound to a specific address.
+    ///
+    /// Only one server per machine could be discovered.
+    pub(crate) fn new(settings: &ServerSettings) -> Result<Self> {
+        let bind_addr = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), DISCOVERY_PORT);
+        let socket =
+            UdpSocket::bind(bind_addr).with_context(|| format!("unable to bind to {bind_addr}"))?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            bind_ip: settings.bind_ip,
+            port: settings.port,
+            max_players: settings.max_clients,
+            secure: settings.private_key.is_some(),
+        })
+    }
+}
+
+/// Inserts [`DiscoveryResponder`] for the created server.
+///
+/// The game could be hosted without discovery, so the error is only logged.
+pub(crate) fn start_responder(commands: &mut Commands, settings: &ServerSettings) {
+    match DiscoveryResponder::new(settings) {
+        Ok(responder) => commands.insert_resource(responder),
+        Err(e) => warn!("unable to start LAN discovery: {e:#}"),
+    }
+}
+
+/// Periodically queries the local network for servers.
+#[derive(Resource)]
+pub(crate) struct LanDiscovery {
+    socket: UdpSocket,
+    servers: Vec<DiscoveredServer>,
+}
+
+impl LanDiscovery {
+    pub(crate) fn new() -> Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .context("unable to bind discovery socket")?;
+        socket.set_broadcast(true)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            servers: Default::default(),
+        })
+    }
+
+    pub(crate) fn servers(&self) -> &[DiscoveredServer] {
+        &self.servers
+    }
+}
+
+pub(crate) struct DiscoveredServer {
+    pub(crate) ip: IpAddr,
+    pub(crate) info: ServerInfo,
+    last_seen: Duration,
+}
+
+/// Response to a discovery query.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct ServerInfo {
+    protocol_id: u64,
+    pub(crate) world_name: String,
+    pub(crate) players: usize,
+    pub(crate) max_players: usize,
+    /// Game port, could differ from [`DISCOVERY_PORT`].
+    pub(crate) port: u16,
+    /// Whether the server requires connect tokens.
+    pub(crate) secure: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_discovery() -> Result<()> {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugins(DiscoveryPlugin);
+
+        // Bind to an ephemeral port to avoid conflicts with running servers.
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        socket.set_nonblocking(true)?;
+        let responder_addr = socket.local_addr()?;
+        app.insert_resource(DiscoveryResponder {
+            socket,
+            bind_ip: Ipv4Addr::LOCALHOST.into(),
+            port: 1234,
+            max_players: 4,
+            secure: false,
+        })
+        .insert_resource(RenetServer::new(Default::default()))
+        .insert_resource(WorldName("World".to_string()));
+
+        let discovery = LanDiscovery::new()?;
+        discovery.socket.send_to(QUERY, responder_addr)?;
+        app.insert_resource(discovery);
+
+        for _ in 0..100 {
+            app.update();
+            if !app.world.resource::<LanDiscovery>().servers().is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let discovery = app.world.resource::<LanDiscovery>();
+        let [server] = discovery.servers() else {
+            panic!("server should be discovered");
+        };
+        assert_eq!(server.ip, IpAddr::from(Ipv4Addr::LOCALHOST));
+        assert_eq!(server.info.world_name, "World");
+        assert_eq!(server.info.players, 0);
+        assert_eq!(server.info.max_players, 4);
+        assert_eq!(server.info.port, 1234);
+        assert!(!server.info.secure);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+use bevy_replicon::prelude::*;
+
+/// Smooths replicated [`Transform`] on clients by interpolating between received snapshots.
+///
+/// Entities are displayed in the past by [`Interpolation::delay`] to always have two snapshots to interpolate between.
+/// Enabled per entity kind with [`AppInterpolationExt::interpolate_transform`].
+pub(super) struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (Self::snapshot_system, Self::interpolation_system)
+                .chain()
+                .after(ClientSet::Receive)
+                .run_if(not(has_authority())),
+        );
+    }
+}
+
+impl InterpolationPlugin {
+    /// Buffers transforms written by replication.
+    fn snapshot_system(
+        time: Res<Time>,
+        mut entities: Query<
+            (&Transform, &Interpolation, &mut TransformSnapshots),
+            Changed<Transform>,
+        >,
+    ) {
+        for (&transform, interpolation, mut snapshots) in &mut entities {
+            // Changed by interpolation, not by replication.
+            if snapshots.displayed == Some(transform) {
+                continue;
+            }
+
+            let now = time.elapsed();
+            if let Some(&last) = snapshots.buffer.back() {
+                if last.transform.translation.distance(transform.translation)
+                    > interpolation.teleport_distance
+                {
+                    snapshots.buffer.clear();
+                } else if now.saturating_sub(last.received_at) > interpolation.delay {
+                    // Start moving from the last position after a pause instead of jumping to the middle.
+                    snapshots.buffer.push_back(Snapshot {
+                        received_at: now - interpolation.delay,
+                        transform: last.transform,
+                    });
+                }
+            }
+
+            snapshots.buffer.push_back(Snapshot {
+                received_at: now,
+                transform,
+            });
+        }
+    }
+
+    fn interpolation_system(
+        time: Res<Time>,
+        mut entities: Query<(&mut Transform, &Interpolation, &mut TransformSnapshots)>,
+    ) {
+        for (mut transform, interpolation, mut snapshots) in &mut entities {
+            let render_time = time.elapsed().saturating_sub(interpolation.delay);
+            snapshots.remove_outdated(render_time);
+            let Some(interpolated) = snapshots.sample(render_time) else {
+                continue;
+            };
+
+            snapshots.displayed = Some(interpolated);
+            if *transform != interpolated {
+                *transform = interpolated;
+            }
+        }
+    }
+
+    fn init_system<C: Component>(
+        interpolation: Interpolation,
+    ) -> impl Fn(Commands, Query<Entity, Added<C>>) {
+        move |mut commands, entities| {
+            for entity in &entities {
+                commands
+                    .entity(entity)
+                    .insert((interpolation, TransformSnapshots::default()));
+            }
+        }
+    }
+}
+
+pub(crate) trait AppInterpolationExt {
+    /// Interpolates replicated transforms of entities with component `C` on clients.
+    fn interpolate_transform<C: Component>(&mut self, interpolation: Interpolation) -> &mut Self;
+}
+
+impl AppInterpolationExt for App {
+    fn interpolate_transform<C: Component>(&mut self, interpolation: Interpolation) -> &mut Self {
+        self.add_systems(
+            PreUpdate,
+            InterpolationPlugin::init_system::<C>(interpolation)
+                .after(ClientSet::Receive)
+                .before(InterpolationPlugin::snapshot_system)
+                .run_if(not(has_authority())),
+        )
+    }
+}
+
+/// Interpolation parameters for an entity kind.
+#[derive(Clone, Component, Copy)]
+pub(crate) struct Interpolation {
+    /// Time by which entities are displayed in the past.
+    ///
+    /// Should be longer than the interval between server updates.
+    pub(crate) delay: Duration,
+    /// Distance between two snapshots after which the entity is moved instantly.
+    pub(crate) teleport_distance: f32,
+}
+
+/// Received transforms of an entity, from the oldest.
+#[derive(Component, Default)]
+struct TransformSnapshots {
+    buffer: VecDeque<Snapshot>,
+    /// Last transform written by interpolation, used to distinguish it from replicated changes.
+    displayed: Option<Transform>,
+}
+
+impl TransformSnapshots {
+    /// Removes snapshots that are no longer needed to interpolate at the specified time.
+    fn remove_outdated(&mut self, render_time: Duration) {
+        while self
+            .buffer
+            .get(1)
+            .map_or(false, |snapshot| snapshot.received_at <= render_time)
+        {
+            self.buffer.pop_front();
+        }
+    }
+
+    /// Returns transform at the specified time.
+    ///
+    /// Uses the closest snapshot if the time is outside of the buffer.
+    fn sample(&self, render_time: Duration) -> Option<Transform> {
+        let last = self.buffer.back()?;
+        let Some(index) = self
+            .buffer
+            .iter()
+            .position(|snapshot| snapshot.received_at > render_time)
+        else {
+            return Some(last.transform);
+        };
+        let next = self.buffer[index];
+        let Some(previous) = index.checked_sub(1).map(|index| self.buffer[index]) else {
+            return Some(next.transform);
+        };
+
+        let interval = (next.received_at - previous.received_at).as_secs_f32();
+        let t = (render_time - previous.received_at).as_secs_f32() / interval;
+        Some(Transform {
+            translation: previous
+                .transform
+                .translation
+                .lerp(next.transform.translation, t),
+            rotation: previous
+                .transform
+                .rotation
+                .slerp(next.transform.rotation, t),
+            scale: previous.transform.scale.lerp(next.transform.scale, t),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Snapshot {
+    /// Local time when the transform was received.
+    received_at: Duration,
+    transform: Transform,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    #[test]
+    fn sampling() {
+        let mut snapshots = TransformSnapshots::default();
+        assert_eq!(snapshots.sample(Duration::ZERO), None);
+
+        snapshots.buffer.push_back(Snapshot {
+            received_at: Duration::from_millis(100),
+            transform: Transform::IDENTITY,
+        });
+        snapshots.buffer.push_back(Snapshot {
+            received_at: Duration::from_millis(200),
+            transform: Transform::from_xyz(2.0, 0.0, 0.0)
+                .with_rotation(Quat::from_rotation_y(FRAC_PI_2)),
+        });
+
+        assert_eq!(
+            snapshots.sample(Duration::ZERO),
+            Some(Transform::IDENTITY),
+            "should use the first snapshot before the buffer"
+        );
+
+        let middle = snapshots
+            .sample(Duration::from_millis(150))
+            .expect("time should be inside the buffer");
+        assert!(middle.translation.abs_diff_eq(Vec3::X, 1e-5));
+        assert!(middle
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_y(FRAC_PI_2 / 2.0), 1e-5));
+
+        snapshots.remove_outdated(Duration::from_millis(300));
+        assert_eq!(snapshots.buffer.len(), 1);
+        assert_eq!(
+            snapshots.sample(Duration::from_millis(300)),
+            Some(snapshots.buffer[0].transform),
+            "should use the last snapshot after the buffer"
+        );
+    }
+}
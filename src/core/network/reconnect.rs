@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use bevy::prelude::*;
+use bevy_replicon::{
+    prelude::*,
+    renet::{transport::NetcodeClientTransport, RenetClient},
+    transport::client_just_connected,
+};
+
+use super::ClientSettings;
+use crate::core::{
+    actor::{ActiveActor, Actor},
+    error,
+    game_state::GameState,
+    game_world::WorldName,
+    player::{ControlledActor, Player},
+};
+
+pub(super) struct ReconnectPlugin;
+
+impl Plugin for ReconnectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                Self::disconnect_system
+                    .run_if(resource_exists::<ClientSettings>())
+                    .run_if(resource_exists::<WorldName>())
+                    .run_if(not(resource_exists::<Reconnect>())),
+                Self::leave_system
+                    .run_if(resource_removed::<WorldName>())
+                    .run_if(not(resource_exists::<Reconnect>())),
+                Self::retry_system
+                    .pipe(error::report)
+                    .run_if(resource_exists::<Reconnect>()),
+                Self::connection_system
+                    .run_if(client_just_connected())
+                    .run_if(resource_exists::<Reconnect>()),
+                Self::resume_system
+                    .run_if(resource_exists::<ResumeSession>())
+                    .run_if(in_state(GameState::World)),
+            ),
+        )
+        .add_systems(OnExit(GameState::World), Self::resume_cleanup_system);
+    }
+}
+
+impl ReconnectPlugin {
+    /// Leaves the world on unexpected disconnection and starts reconnecting.
+    fn disconnect_system(
+        mut commands: Commands,
+        mut game_state: ResMut<NextState<GameState>>,
+        transport: Option<Res<NetcodeClientTransport>>,
+    ) {
+        let Some(transport) = transport else {
+            return;
+        };
+        if !transport.is_disconnected() {
+            return;
+        }
+
+        match transport.disconnect_reason() {
+            Some(reason) => warn!("lost connection to server: {reason:?}"),
+            None => warn!("lost connection to server"),
+        }
+        commands.remove_resource::<RenetClient>();
+        commands.remove_resource::<NetcodeClientTransport>();
+        // Replicated entities will be received again after reconnection.
+        commands.remove_resource::<WorldName>();
+        commands.insert_resource(Reconnect::default());
+        game_state.set(GameState::MainMenu);
+    }
+
+    /// Disconnects from the server when the player leaves the world.
+    ///
+    /// Without it the stale connection would be considered lost in the next world.
+    fn leave_system(mut commands: Commands) {
+        commands.remove_resource::<RenetClient>();
+        commands.remove_resource::<NetcodeClientTransport>();
+        commands.remove_resource::<ClientSettings>();
+    }
+
+    fn retry_system(
+        mut commands: Commands,
+        mut reconnect: ResMut<Reconnect>,
+        time: Res<Time>,
+        settings: Res<ClientSettings>,
+        network_channels: Res<NetworkChannels>,
+        transport: Option<Res<NetcodeClientTransport>>,
+    ) -> Result<()> {
+        if let Some(transport) = transport {
+            if !transport.is_disconnected() {
+                // Still connecting.
+                return Ok(());
+            }
+
+            commands.remove_resource::<RenetClient>();
+            commands.remove_resource::<NetcodeClientTransport>();
+            if reconnect.attempt >= MAX_ATTEMPTS {
+                commands.remove_resource::<Reconnect>();
+                commands.remove_resource::<ClientSettings>();
+                bail!("unable to reconnect after {MAX_ATTEMPTS} attempts");
+            }
+            reconnect.timer = Timer::new(backoff(reconnect.attempt), TimerMode::Once);
+            return Ok(());
+        }
+
+        reconnect.timer.tick(time.delta());
+        if reconnect.timer.finished() {
+            reconnect.attempt += 1;
+            info!("reconnecting to server, attempt {}", reconnect.attempt);
+            super::connect(&mut commands, &network_channels, settings.clone())?;
+        }
+
+        Ok(())
+    }
+
+    fn connection_system(mut commands: Commands) {
+        commands.remove_resource::<Reconnect>();
+        commands.insert_resource(ResumeSession);
+    }
+
+    /// Restores the actor that the server remembers for this player.
+    ///
+    /// Waits until the player is replicated, the player can also pick an actor manually.
+    fn resume_system(
+        mut commands: Commands,
+        mut game_state: ResMut<NextState<GameState>>,
+        transport: Res<NetcodeClientTransport>,
+        players: Query<(&Player, &ControlledActor)>,
+        actors: Query<(), With<Actor>>,
+    ) {
+        let client_id = transport.client_id();
+        let Some((_, controlled_actor)) = players.iter().find(|(player, _)| player.0 == client_id)
+        else {
+            return;
+        };
+
+        if actors.get(controlled_actor.0).is_ok() {
+            info!("resuming session with actor {:?}", controlled_actor.0);
+            commands.entity(controlled_actor.0).insert(ActiveActor);
+            game_state.set(GameState::Family);
+            commands.remove_resource::<ResumeSession>();
+        }
+    }
+
+    fn resume_cleanup_system(mut commands: Commands) {
+        commands.remove_resource::<ResumeSession>();
+    }
+}
+
+const MAX_ATTEMPTS: u32 = 6;
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Returns delay before the next reconnection attempt.
+///
+/// Doubles with each failed attempt.
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1)
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF)
+}
+
+/// Present while the client is trying to restore the lost connection.
+#[derive(Resource)]
+pub(crate) struct Reconnect {
+    /// Number of the current attempt, 0 until the first one starts.
+    attempt: u32,
+    timer: Timer,
+}
+
+impl Reconnect {
+    pub(crate) fn attempt(&self) -> u32 {
+        self.attempt
+    }
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self {
+            attempt: 0,
+            timer: Timer::new(backoff(0), TimerMode::Once),
+        }
+    }
+}
+
+/// Present after reconnection until the previous actor is restored.
+#[derive(Resource)]
+struct ResumeSession;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_growth() {
+        assert_eq!(backoff(0), Duration::from_secs(1));
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+        assert_eq!(backoff(MAX_ATTEMPTS * 10), MAX_BACKOFF);
+    }
+}
@@ -16,9 +16,11 @@ use super::{
     component_commands::ComponentCommandsExt,
     cursor_hover::Hoverable,
     cursor_hover::OutlineHoverExt,
-    game_world::WorldName,
+    game_world::{recording::AppRecordingExt, WorldName},
     lot::LotVertices,
+    player::Ownership,
     ready_scene::ReadyScene,
+    rejection::{self, RejectReason},
 };
 use mirror::MirrorPlugin;
 use placing_object::PlacingObjectPlugin;
@@ -32,12 +34,17 @@ impl Plugin for ObjectPlugin {
             .add_client_event::<ObjectSpawn>(SendPolicy::Unordered)
             .add_mapped_client_event::<ObjectMove>(SendPolicy::Ordered)
             .add_mapped_client_event::<ObjectDespawn>(SendPolicy::Unordered)
+            .record_client_event::<ObjectSpawn>()
+            .record_mapped_client_event::<ObjectMove>()
+            .record_mapped_client_event::<ObjectDespawn>()
             .add_server_event::<ObjectEventConfirmed>(SendPolicy::Unordered)
+            .add_server_event::<ObjectEventRejected>(SendPolicy::Unordered)
             .add_systems(
                 Update,
                 (
                     (Self::init_system, Self::scene_init_system)
                         .run_if(resource_exists::<WorldName>()),
+                    rejection::report_system::<ObjectEventRejected>,
                     (
                         Self::spawn_system,
                         Self::movement_system,
@@ -109,6 +116,8 @@ impl ObjectPlugin {
         mut commands: Commands,
         mut spawn_events: EventReader<FromClient<ObjectSpawn>>,
         mut confirm_events: EventWriter<ToClients<ObjectEventConfirmed>>,
+        mut reject_events: EventWriter<ToClients<ObjectEventRejected>>,
+        ownership: Ownership,
         cities: Query<(Entity, &Transform), With<City>>,
         lots: Query<(Entity, &LotVertices)>,
     ) {
@@ -118,6 +127,10 @@ impl ObjectPlugin {
                     "received object spawn position {} with 'y' outside of city size",
                     event.position
                 );
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: ObjectEventRejected(RejectReason::OutsideCity),
+                });
                 continue;
             }
 
@@ -127,15 +140,26 @@ impl ObjectPlugin {
                 .find(|(_, x)| x.abs() < HALF_CITY_SIZE)
             else {
                 error!("unable to find a city for object spawn position {}", event.position);
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: ObjectEventRejected(RejectReason::OutsideCity),
+                });
                 continue;
             };
 
-            // TODO: Add a check if user can spawn an object on the lot.
             let parent_entity = lots
                 .iter()
                 .find(|(_, vertices)| vertices.contains_point(event.position))
                 .map(|(lot_entity, _)| lot_entity)
                 .unwrap_or(city_entity);
+            if !ownership.can_control(client_id, parent_entity) {
+                error!("client {client_id} is not allowed to spawn objects on {parent_entity:?}");
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: ObjectEventRejected(RejectReason::NotOwner),
+                });
+                continue;
+            }
 
             commands.entity(parent_entity).with_children(|parent| {
                 parent.spawn(ObjectBundle::new(
@@ -154,19 +178,61 @@ impl ObjectPlugin {
     fn movement_system(
         mut move_events: EventReader<FromClient<ObjectMove>>,
         mut confirm_events: EventWriter<ToClients<ObjectEventConfirmed>>,
+        mut reject_events: EventWriter<ToClients<ObjectEventRejected>>,
+        rapier_ctx: Res<RapierContext>,
+        ownership: Ownership,
         mut transforms: Query<&mut Transform>,
+        children: Query<&Children>,
+        colliders: Query<(&Collider, &GlobalTransform)>,
+        global_transforms: Query<&GlobalTransform>,
     ) {
         for FromClient { client_id, event } in move_events.iter().copied() {
+            if !ownership.can_control(client_id, event.entity) {
+                error!(
+                    "client {client_id} is not allowed to move {:?}",
+                    event.entity
+                );
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: ObjectEventRejected(RejectReason::NotOwner),
+                });
+                continue;
+            }
             match transforms.get_mut(event.entity) {
                 Ok(mut transform) => {
-                    transform.translation = event.translation;
-                    transform.rotation = event.rotation;
+                    let new_transform = Transform::from_translation(event.translation)
+                        .with_rotation(event.rotation)
+                        .with_scale(transform.scale);
+                    if collides(
+                        &rapier_ctx,
+                        event.entity,
+                        *transform,
+                        new_transform,
+                        &children,
+                        &colliders,
+                        &global_transforms,
+                    ) {
+                        error!("unable to move {:?} because of collision", event.entity);
+                        reject_events.send(ToClients {
+                            mode: SendMode::Direct(client_id),
+                            event: ObjectEventRejected(RejectReason::Collision),
+                        });
+                        continue;
+                    }
+
+                    *transform = new_transform;
                     confirm_events.send(ToClients {
                         mode: SendMode::Direct(client_id),
                         event: ObjectEventConfirmed,
                     });
                 }
-                Err(e) => error!("unable to apply object movement: {e}",),
+                Err(e) => {
+                    error!("unable to apply object movement: {e}");
+                    reject_events.send(ToClients {
+                        mode: SendMode::Direct(client_id),
+                        event: ObjectEventRejected(RejectReason::InvalidEntity),
+                    });
+                }
             }
         }
     }
@@ -175,8 +241,18 @@ impl ObjectPlugin {
         mut commands: Commands,
         mut despawn_events: EventReader<FromClient<ObjectDespawn>>,
         mut confirm_events: EventWriter<ToClients<ObjectEventConfirmed>>,
+        mut reject_events: EventWriter<ToClients<ObjectEventRejected>>,
+        ownership: Ownership,
     ) {
         for FromClient { client_id, event } in despawn_events.iter().copied() {
+            if !ownership.can_control(client_id, event.0) {
+                error!("client {client_id} is not allowed to despawn {:?}", event.0);
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: ObjectEventRejected(RejectReason::NotOwner),
+                });
+                continue;
+            }
             commands.entity(event.0).despawn_recursive();
             confirm_events.send(ToClients {
                 mode: SendMode::Direct(client_id),
@@ -186,8 +262,49 @@ impl ObjectPlugin {
     }
 }
 
+/// Returns `true` if the object intersects other objects or walls after changing its transform.
+///
+/// Checks colliders of the object children, so objects with unloaded scenes never collide.
+fn collides(
+    rapier_ctx: &RapierContext,
+    object_entity: Entity,
+    transform: Transform,
+    new_transform: Transform,
+    children: &Query<&Children>,
+    colliders: &Query<(&Collider, &GlobalTransform)>,
+    global_transforms: &Query<&GlobalTransform>,
+) -> bool {
+    let Ok(object_transform) = global_transforms.get(object_entity) else {
+        return false;
+    };
+
+    // Object keeps its parent, so only the local transform changes.
+    let object_affine = object_transform.affine();
+    let new_object_affine =
+        object_affine * transform.compute_affine().inverse() * new_transform.compute_affine();
+    let descendants: Vec<_> = children.iter_descendants(object_entity).collect();
+    let not_descendant = |entity| !descendants.contains(&entity);
+    let groups = CollisionGroups::new(Group::ALL, Group::OBJECT | Group::WALL);
+    let filter = QueryFilter::from(groups).predicate(&not_descendant);
+    for (collider, collider_transform) in colliders.iter_many(&descendants) {
+        let relative_affine = object_affine.inverse() * collider_transform.affine();
+        let (_, rotation, translation) = GlobalTransform::from(new_object_affine * relative_affine)
+            .to_scale_rotation_translation();
+        let mut intersects = false;
+        rapier_ctx.intersections_with_shape(translation, rotation, collider, filter, |_| {
+            intersects = true;
+            false
+        });
+        if intersects {
+            return true;
+        }
+    }
+
+    false
+}
+
 #[derive(Bundle)]
-struct ObjectBundle {
+pub(super) struct ObjectBundle {
     object_path: ObjectPath,
     transform: Transform,
     parent_sync: ParentSync,
@@ -195,7 +312,7 @@ struct ObjectBundle {
 }
 
 impl ObjectBundle {
-    fn new(metadata_path: PathBuf, translation: Vec3, rotation: Quat) -> Self {
+    pub(super) fn new(metadata_path: PathBuf, translation: Vec3, rotation: Quat) -> Self {
         Self {
             object_path: ObjectPath(metadata_path),
             transform: Transform::default()
@@ -210,7 +327,7 @@ impl ObjectBundle {
 /// Contains path to the object metadata file.
 #[derive(Clone, Component, Debug, Default, Event, Reflect)]
 #[reflect(Component)]
-pub(crate) struct ObjectPath(PathBuf);
+pub(crate) struct ObjectPath(pub(crate) PathBuf);
 
 #[derive(Clone, Debug, Deserialize, Event, Serialize)]
 struct ObjectSpawn {
@@ -246,3 +363,7 @@ impl MapEventEntities for ObjectDespawn {
 /// An event from server which indicates action confirmation.
 #[derive(Deserialize, Event, Serialize, Debug, Default)]
 struct ObjectEventConfirmed;
+
+/// An event from server which indicates that the action was not applied.
+#[derive(Clone, Copy, Debug, Deref, Deserialize, Event, Serialize)]
+struct ObjectEventRejected(RejectReason);
@@ -16,7 +16,10 @@ use crate::core::{
     cursor_hover::CursorHover,
     family::FamilyMode,
     game_state::GameState,
-    object::{ObjectDespawn, ObjectEventConfirmed, ObjectMove, ObjectPath, ObjectSpawn},
+    object::{
+        ObjectDespawn, ObjectEventConfirmed, ObjectEventRejected, ObjectMove, ObjectPath,
+        ObjectSpawn,
+    },
     player_camera::PlayerCamera,
     ready_scene::ReadyScene,
     wall::{WallEdges, WallObject, HALF_WIDTH},
@@ -49,7 +52,8 @@ impl Plugin for PlacingObjectPlugin {
                     Self::despawn_system.run_if(action_just_pressed(Action::Delete)),
                     Self::cancel_system.pipe(Self::cleanup_system).run_if(
                         action_just_pressed(Action::Cancel)
-                            .or_else(on_event::<ObjectEventConfirmed>()),
+                            .or_else(on_event::<ObjectEventConfirmed>())
+                            .or_else(on_event::<ObjectEventRejected>()),
                     ),
                 ),
                 (
@@ -0,0 +1,368 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::{
+        entity::{EntityMap, EntityMapper, MapEntities},
+        reflect::ReflectMapEntities,
+        system::SystemParam,
+    },
+    prelude::*,
+};
+use bevy_replicon::{
+    prelude::*,
+    renet::{
+        transport::{NetcodeServerTransport, NETCODE_USER_DATA_BYTES},
+        RenetServer, ServerEvent,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    actor::ActiveActor,
+    cli::Cli,
+    family::ActorFamily,
+    game_state::GameState,
+    game_world::{recording::AppRecordingExt, WorldName},
+    settings::Settings,
+};
+
+pub(super) struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.replicate::<Player>()
+            .replicate::<Owner>()
+            .replicate::<ControlledActor>()
+            .add_mapped_client_event::<ActorControl>(SendPolicy::Ordered)
+            .record_mapped_client_event::<ActorControl>()
+            .add_systems(
+                OnEnter(GameState::World),
+                Self::local_player_system
+                    .run_if(has_authority())
+                    .run_if(|cli: Res<Cli>| !cli.is_dedicated_server()),
+            )
+            .add_systems(
+                Update,
+                (
+                    Self::connection_system
+                        .run_if(resource_exists::<NetcodeServerTransport>())
+                        .run_if(resource_exists::<WorldName>()),
+                    Self::cleanup_system.run_if(resource_removed::<WorldName>()),
+                    Self::activation_system.run_if(in_state(GameState::Family)),
+                    (
+                        Self::load_system,
+                        Self::control_system,
+                        Self::expiration_system,
+                    )
+                        .run_if(has_authority()),
+                ),
+            );
+    }
+}
+
+impl PlayerPlugin {
+    /// Registers the local player in the world if it was never hosted by this player before.
+    fn local_player_system(
+        mut commands: Commands,
+        settings: Res<Settings>,
+        mut players: Query<(&Player, &mut Name)>,
+    ) {
+        register_player(
+            &mut commands,
+            &mut players,
+            settings.player.id,
+            settings.player.name.clone(),
+        );
+    }
+
+    fn connection_system(
+        mut commands: Commands,
+        mut server_events: EventReader<ServerEvent>,
+        mut server: ResMut<RenetServer>,
+        time: Res<Time>,
+        transport: Res<NetcodeServerTransport>,
+        mut players: Query<(&Player, &mut Name)>,
+        entities: Query<(Entity, &Player)>,
+    ) {
+        for event in &mut server_events {
+            match *event {
+                ServerEvent::ClientConnected { client_id } if client_id == SERVER_ID => {
+                    // Remote clients with this ID would be treated as the host.
+                    error!("disconnecting client with reserved ID {SERVER_ID}");
+                    server.disconnect(client_id);
+                }
+                ServerEvent::ClientDisconnected { client_id, .. } if client_id == SERVER_ID => (),
+                ServerEvent::ClientConnected { client_id } => {
+                    let name = client_name(&transport, client_id);
+                    register_player(&mut commands, &mut players, client_id, name);
+                    if let Some((entity, _)) =
+                        entities.iter().find(|(_, player)| player.0 == client_id)
+                    {
+                        commands.entity(entity).remove::<DisconnectedAt>();
+                    }
+                }
+                ServerEvent::ClientDisconnected { client_id, .. } => {
+                    if let Some((entity, _)) =
+                        entities.iter().find(|(_, player)| player.0 == client_id)
+                    {
+                        commands
+                            .entity(entity)
+                            .insert(DisconnectedAt(time.elapsed()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reports the locally activated actor to the server to restore it after reconnection.
+    fn activation_system(
+        mut control_events: EventWriter<ActorControl>,
+        actors: Query<Entity, Added<ActiveActor>>,
+    ) {
+        if let Some(entity) = actors.iter().last() {
+            control_events.send(ActorControl(entity));
+        }
+    }
+
+    /// Removes sessions from a previous run because all players are disconnected after loading.
+    fn load_system(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+        for entity in &players {
+            commands.entity(entity).remove::<ControlledActor>();
+        }
+    }
+
+    fn control_system(
+        mut commands: Commands,
+        mut control_events: EventReader<FromClient<ActorControl>>,
+        ownership: Ownership,
+        players: Query<(Entity, &Player)>,
+    ) {
+        for FromClient { client_id, event } in control_events.iter().copied() {
+            if !ownership.can_control(client_id, event.0) {
+                error!(
+                    "client {client_id} is not allowed to control actor {:?}",
+                    event.0
+                );
+                continue;
+            }
+
+            let player_id = ownership.player_id(client_id);
+            if let Some((entity, _)) = players.iter().find(|(_, player)| player.0 == player_id) {
+                commands.entity(entity).insert(ControlledActor(event.0));
+            }
+        }
+    }
+
+    fn expiration_system(
+        mut commands: Commands,
+        time: Res<Time>,
+        players: Query<(Entity, &Player, &DisconnectedAt)>,
+    ) {
+        for (entity, player, disconnected_at) in &players {
+            if time.elapsed() - disconnected_at.0 > SESSION_GRACE_PERIOD {
+                debug!("session of player {} expired", player.0);
+                commands
+                    .entity(entity)
+                    .remove::<(ControlledActor, DisconnectedAt)>();
+            }
+        }
+    }
+
+    fn cleanup_system(mut commands: Commands, players: Query<Entity, With<Player>>) {
+        for entity in &players {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Time during which a disconnected player can reconnect and continue with the same actor.
+const SESSION_GRACE_PERIOD: Duration = Duration::from_secs(120);
+
+/// Spawns a player with the specified ID or updates the name of the existing one.
+fn register_player(
+    commands: &mut Commands,
+    players: &mut Query<(&Player, &mut Name)>,
+    id: u64,
+    name: String,
+) {
+    if let Some((_, mut player_name)) = players.iter_mut().find(|(player, _)| player.0 == id) {
+        if player_name.as_str() != name {
+            player_name.set(name);
+        }
+    } else {
+        info!("player {id} with name {name} joined for the first time");
+        commands.spawn(PlayerBundle::new(id, name));
+    }
+}
+
+/// Returns name that the connected client sent in user data.
+pub(crate) fn client_name(transport: &NetcodeServerTransport, client_id: u64) -> String {
+    transport
+        .user_data(client_id)
+        .map(|user_data| name_from_user_data(&user_data))
+        .unwrap_or_else(|| format!("Player {client_id}"))
+}
+
+/// Encodes player name into netcode user data to send it on connection.
+///
+/// Names longer than the user data are truncated.
+pub(crate) fn name_to_user_data(name: &str) -> [u8; NETCODE_USER_DATA_BYTES] {
+    let mut user_data = [0; NETCODE_USER_DATA_BYTES];
+    let len = name.len().min(u8::MAX as usize);
+    user_data[0] = len as u8;
+    user_data[1..=len].copy_from_slice(&name.as_bytes()[..len]);
+    user_data
+}
+
+fn name_from_user_data(user_data: &[u8; NETCODE_USER_DATA_BYTES]) -> String {
+    let len = user_data[0] as usize;
+    String::from_utf8_lossy(&user_data[1..=len]).into_owned()
+}
+
+/// Checks which entities players are allowed to control.
+#[derive(SystemParam)]
+pub(crate) struct Ownership<'w, 's> {
+    settings: Res<'w, Settings>,
+    owners: Query<'w, 's, &'static Owner>,
+    actors: Query<'w, 's, &'static ActorFamily>,
+    parents: Query<'w, 's, &'static Parent>,
+}
+
+impl Ownership<'_, '_> {
+    /// Returns persistent player ID for the client.
+    ///
+    /// Clients connect with their player ID, events from the host are sent as [`SERVER_ID`].
+    pub(crate) fn player_id(&self, client_id: u64) -> u64 {
+        if client_id == SERVER_ID {
+            self.settings.player.id
+        } else {
+            client_id
+        }
+    }
+
+    /// Returns `true` if the client is allowed to control the entity.
+    ///
+    /// Entity is controlled by the owner of itself, its family or its closest owned ancestor.
+    /// Entities without any owner can be controlled by anyone.
+    pub(crate) fn can_control(&self, client_id: u64, entity: Entity) -> bool {
+        match self.owner(entity) {
+            Some(owner) => owner.0 == self.player_id(client_id),
+            None => true,
+        }
+    }
+
+    /// Returns the owner of the entity with the same rules as [`Self::can_control`].
+    pub(crate) fn owner(&self, entity: Entity) -> Option<Owner> {
+        let mut current_entity = entity;
+        loop {
+            if let Ok(&owner) = self.owners.get(current_entity) {
+                return Some(owner);
+            }
+            if let Ok(family) = self.actors.get(current_entity) {
+                return self.owners.get(family.0).ok().copied();
+            }
+            current_entity = **self.parents.get(current_entity).ok()?;
+        }
+    }
+}
+
+#[derive(Bundle)]
+struct PlayerBundle {
+    name: Name,
+    player: Player,
+    replication: Replication,
+}
+
+impl PlayerBundle {
+    fn new(id: u64, name: String) -> Self {
+        Self {
+            name: Name::new(name),
+            player: Player(id),
+            replication: Replication,
+        }
+    }
+}
+
+/// Persistent player identity that is saved with the world.
+///
+/// Contains the same ID that the player uses as a client ID on connection.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub(crate) struct Player(pub(crate) u64);
+
+/// Contains ID of the [`Player`] that owns a family or a lot.
+#[derive(Clone, Component, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub(crate) struct Owner(pub(crate) u64);
+
+/// Actor that the player controlled last time.
+///
+/// Removed after [`SESSION_GRACE_PERIOD`] since the player disconnected.
+#[derive(Component, Reflect)]
+#[reflect(Component, MapEntities)]
+pub(crate) struct ControlledActor(pub(crate) Entity);
+
+impl MapEntities for ControlledActor {
+    fn map_entities(&mut self, entity_mapper: &mut EntityMapper) {
+        self.0 = entity_mapper.get_or_reserve(self.0);
+    }
+}
+
+// Required to register the type as [`Reflect`], same as for [`Parent`].
+impl FromWorld for ControlledActor {
+    fn from_world(_world: &mut World) -> Self {
+        Self(Entity::PLACEHOLDER)
+    }
+}
+
+/// Time since startup when the player disconnected.
+#[derive(Component)]
+struct DisconnectedAt(Duration);
+
+/// Sent by players to let the server know which actor they currently control.
+#[derive(Clone, Copy, Debug, Deserialize, Event, Serialize)]
+struct ActorControl(Entity);
+
+impl MapEventEntities for ActorControl {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapError> {
+        self.0 = entity_map.get(self.0).ok_or(MapError(self.0))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+
+    #[test]
+    fn user_data_name() {
+        const NAME: &str = "Player name";
+        assert_eq!(name_from_user_data(&name_to_user_data(NAME)), NAME);
+
+        let long_name = "a".repeat(NETCODE_USER_DATA_BYTES * 2);
+        assert_eq!(
+            name_from_user_data(&name_to_user_data(&long_name)).len(),
+            u8::MAX as usize
+        );
+    }
+
+    #[test]
+    fn ownership() {
+        const OWNER_ID: u64 = 1;
+        let mut world = World::new();
+        world.insert_resource(Settings::default());
+        let family_entity = world.spawn(Owner(OWNER_ID)).id();
+        let actor_entity = world.spawn(ActorFamily(family_entity)).id();
+        let task_entity = world.spawn_empty().set_parent(actor_entity).id();
+        let public_entity = world.spawn_empty().id();
+
+        let mut system_state = SystemState::<Ownership>::new(&mut world);
+        let ownership = system_state.get(&world);
+        assert!(ownership.can_control(OWNER_ID, family_entity));
+        assert!(ownership.can_control(OWNER_ID, task_entity));
+        assert!(!ownership.can_control(OWNER_ID + 1, task_entity));
+        assert!(ownership.can_control(OWNER_ID + 1, public_entity));
+    }
+}
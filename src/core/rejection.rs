@@ -0,0 +1,47 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::Deref,
+};
+
+use anyhow::anyhow;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::error::ErrorReport;
+
+/// Reason why the server rejected an action requested by a client.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum RejectReason {
+    OutsideCity,
+    InvalidLot,
+    LotNotEmpty,
+    LotShapeMismatch,
+    NotOwner,
+    Collision,
+    InvalidEntity,
+}
+
+impl Display for RejectReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let message = match self {
+            RejectReason::OutsideCity => "position is outside of city bounds",
+            RejectReason::InvalidLot => "target is not a lot",
+            RejectReason::LotNotEmpty => "lot already contains buildings",
+            RejectReason::LotShapeMismatch => "lot has a different shape",
+            RejectReason::NotOwner => "target belongs to another player",
+            RejectReason::Collision => "collides with another object",
+            RejectReason::InvalidEntity => "target no longer exists",
+        };
+        f.write_str(message)
+    }
+}
+
+/// Shows rejections of the local player actions.
+pub(crate) fn report_system<T: Event + Deref<Target = RejectReason>>(
+    mut reject_events: EventReader<T>,
+    mut error_events: EventWriter<ErrorReport>,
+) {
+    for reason in reject_events.iter().map(|event| **event) {
+        error_events.send(ErrorReport(anyhow!("action was rejected: {reason}")));
+    }
+}
@@ -12,8 +12,17 @@ pub(super) struct SettingsPlugin;
 impl Plugin for SettingsPlugin {
     fn build(&self, app: &mut App) {
         let game_paths = app.world.resource::<GamePaths>();
+        let mut settings = Settings::read(&game_paths.settings).unwrap_or_default();
+        if settings.player.id == 0 {
+            // Generate a persistent identity on the first launch.
+            // Limited by `i64` because TOML supports only signed integers.
+            settings.player.id = fastrand::u64(1..i64::MAX as u64);
+            if let Err(e) = settings.write(&game_paths.settings) {
+                error!("unable to save generated player ID: {e:#}");
+            }
+        }
 
-        app.insert_resource(Settings::read(&game_paths.settings).unwrap_or_default())
+        app.insert_resource(settings)
             .add_event::<SettingsApply>()
             .add_systems(
                 Update,
@@ -37,19 +46,21 @@ pub(crate) struct SettingsApply;
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Reflect, Resource, Serialize)]
 #[serde(default)]
 pub(crate) struct Settings {
+    pub(crate) player: PlayerSettings,
     pub(crate) video: VideoSettings,
     // TODO: TOML implementations have issues with [`HashSet`]:
     // https://github.com/alexcrichton/toml-rs/issues/469 and https://github.com/ordian/toml_edit/issues/319
     #[serde(skip)]
     #[reflect(ignore)]
     pub(crate) controls: ControlsSettings,
+    pub(crate) saves: SaveSettings,
     pub(crate) developer: DeveloperSettings,
 }
 
 impl Settings {
     /// Creates [`Settings`] from the application settings file.
     /// Will be initialed with defaults if the file does not exist.
-    fn read(file_name: &Path) -> Result<Settings> {
+    pub(crate) fn read(file_name: &Path) -> Result<Settings> {
         match fs::read_to_string(file_name) {
             Ok(content) => toml::from_str::<Settings>(&content)
                 .with_context(|| format!("unable to read settings from {file_name:?}")),
@@ -75,6 +86,24 @@ impl Settings {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Reflect, Serialize)]
+#[serde(default)]
+pub(crate) struct PlayerSettings {
+    /// Name displayed to other players.
+    pub(crate) name: String,
+    /// Persistent ID to identify the player on servers, 0 means not generated yet.
+    pub(crate) id: u64,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            name: "Player".to_string(),
+            id: 0,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Reflect, Serialize)]
 #[serde(default)]
 pub(crate) struct VideoSettings {
@@ -120,6 +149,33 @@ impl Default for ControlsSettings {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Reflect, Serialize)]
+#[serde(default)]
+pub(crate) struct SaveSettings {
+    /// Save worlds in binary format instead of RON.
+    pub(crate) binary: bool,
+    /// Compress binary saves.
+    pub(crate) compress: bool,
+    /// Interval between autosaves in seconds, 0 disables autosaving.
+    pub(crate) autosave_interval: u32,
+    /// Number of previous saves to keep as backups on autosave.
+    pub(crate) backups: usize,
+    /// Skip broken or unknown components when loading instead of failing.
+    pub(crate) lenient_loading: bool,
+}
+
+impl Default for SaveSettings {
+    fn default() -> Self {
+        Self {
+            binary: false,
+            compress: false,
+            autosave_interval: 300,
+            backups: 3,
+            lenient_loading: false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Reflect, Serialize)]
 #[serde(default)]
 pub(crate) struct DeveloperSettings {
@@ -127,4 +183,28 @@ pub(crate) struct DeveloperSettings {
     pub(crate) debug_collisions: bool,
     pub(crate) debug_paths: bool,
     pub(crate) wireframe: bool,
+    pub(crate) network: NetworkConditions,
+}
+
+/// Simulated network conditions for connections to servers.
+///
+/// Useful to test multiplayer on a single machine.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Reflect, Serialize)]
+#[serde(default)]
+pub(crate) struct NetworkConditions {
+    /// Delay in milliseconds added to packets in each direction.
+    pub(crate) latency: u32,
+    /// Maximum random deviation from the latency in milliseconds.
+    pub(crate) jitter: u32,
+    /// Probability of dropping a packet from 0 to 1.
+    pub(crate) packet_loss: f32,
+    /// Probability of delivering a packet twice from 0 to 1.
+    pub(crate) duplication: f32,
+}
+
+impl NetworkConditions {
+    /// Returns `true` if packets don't need to be altered.
+    pub(crate) fn is_ideal(&self) -> bool {
+        *self == Self::default()
+    }
 }
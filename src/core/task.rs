@@ -1,5 +1,7 @@
 use std::{
     any,
+    cmp::Reverse,
+    collections::HashMap,
     fmt::{self, Debug, Formatter},
 };
 
@@ -22,8 +24,17 @@ use serde::{
 use strum::{EnumVariantNames, IntoStaticStr, VariantNames};
 
 use super::{
-    action::Action, actor::Actor, component_commands::ComponentCommandsExt, family::FamilyMode,
+    action::Action,
+    actor::{
+        needs::{Need, NeedEffect, NeedKind},
+        object_interaction::ObjectInteractions,
+        Actor,
+    },
+    component_commands::ComponentCommandsExt,
+    family::FamilyMode,
     game_state::GameState,
+    game_world::recording::AppRecordingExt,
+    player::Ownership,
 };
 
 pub(super) struct TaskPlugin;
@@ -31,9 +42,17 @@ pub(super) struct TaskPlugin;
 impl Plugin for TaskPlugin {
     fn build(&self, app: &mut App) {
         app.replicate::<TaskState>()
+            .replicate::<TaskPriority>()
+            .replicate::<TaskOrder>()
+            .replicate::<TaskProgress>()
             .add_mapped_client_reflect_event::<TaskRequest, TaskRequestSerializer, TaskRequestDeserializer>(SendPolicy::Unordered)
             .add_client_event::<TaskCancel>(SendPolicy::Unordered)
+            .add_mapped_client_event::<TaskReorder>(SendPolicy::Ordered)
+            .record_mapped_client_reflect_event::<TaskRequest, TaskRequestSerializer, TaskRequestDeserializer>()
+            .record_mapped_client_event::<TaskCancel>()
+            .record_mapped_client_event::<TaskReorder>()
             .add_event::<TaskList>()
+            .add_event::<TaskFinished>()
             .configure_set(
                 Update,
                 TaskListSet
@@ -44,11 +63,22 @@ impl Plugin for TaskPlugin {
             .add_systems(
                 Update,
                 (
+                    Self::init_system,
                     Self::queue_system,
+                    Self::reorder_system,
                     Self::activation_system,
                     Self::cancelation_system,
                 )
                     .run_if(has_authority()),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    Self::need_effects_system
+                        .before(Self::finish_system)
+                        .run_if(has_authority()),
+                    Self::finish_system,
+                ),
             );
     }
 }
@@ -57,57 +87,160 @@ impl TaskPlugin {
     fn queue_system(
         mut commands: Commands,
         mut task_events: ResMut<Events<FromClient<TaskRequest>>>,
-        actors: Query<(), With<Actor>>,
+        ownership: Ownership,
+        object_interactions: ObjectInteractions,
+        actors: Query<Option<&Children>, With<Actor>>,
+        tasks: Query<&TaskOrder>,
     ) {
-        for event in task_events.drain().map(|event| event.event) {
-            if actors.get(event.entity).is_ok() {
-                commands.entity(event.entity).with_children(|parent| {
-                    parent
-                        .spawn((
-                            Name::new(event.task.name().to_string()),
-                            event.task.groups(),
-                            Replication,
-                            TaskState::Queued,
-                        ))
-                        .insert_reflect([event.task.into_reflect()]);
+        // Multiple tasks could be requested for the same actor in a single frame.
+        let mut next_orders = HashMap::new();
+        for FromClient { client_id, event } in task_events.drain() {
+            let Some(task) = object_interactions.rebuild(event.task) else {
+                error!("client {client_id} requested an interaction that the object doesn't offer");
+                continue;
+            };
+
+            if !ownership.can_control(client_id, event.entity) {
+                error!(
+                    "client {client_id} is not allowed to control {:?}",
+                    event.entity
+                );
+            } else if let Ok(children) = actors.get(event.entity) {
+                let next_order = next_orders.entry(event.entity).or_insert_with(|| {
+                    children
+                        .and_then(|children| tasks.iter_many(children).map(|order| order.0).max())
+                        .map_or(0, |order| order + 1)
                 });
+                let order = TaskOrder(*next_order);
+                *next_order += 1;
+
+                queue_task(&mut commands, event.entity, &*task, task.priority(), order);
             } else {
                 error!("entity {:?} is not an actor", event.entity);
             }
         }
     }
 
+    /// Inserts missing queue components for tasks from saves before priorities were introduced.
+    fn init_system(
+        mut commands: Commands,
+        tasks: Query<(Entity, Option<&TaskPriority>, Option<&TaskOrder>), Added<TaskState>>,
+    ) {
+        for (entity, priority, order) in &tasks {
+            if priority.is_none() {
+                commands.entity(entity).insert(TaskPriority::default());
+            }
+            if order.is_none() {
+                commands.entity(entity).insert(TaskOrder::default());
+            }
+        }
+    }
+
+    fn reorder_system(
+        mut reorder_events: EventReader<FromClient<TaskReorder>>,
+        ownership: Ownership,
+        mut tasks: Query<(&TaskState, &mut TaskPriority, &mut TaskOrder)>,
+        parents: Query<&Parent>,
+        actors: Query<&Children, With<Actor>>,
+    ) {
+        for FromClient { client_id, event } in &mut reorder_events {
+            if !ownership.can_control(*client_id, event.task) {
+                error!(
+                    "client {client_id} is not allowed to reorder {:?}",
+                    event.task
+                );
+                continue;
+            }
+
+            let Some(children) = parents
+                .get(event.task)
+                .ok()
+                .and_then(|parent| actors.get(**parent).ok())
+            else {
+                error!("entity {:?} is not an actor task", event.task);
+                continue;
+            };
+
+            let mut queue: Vec<_> = children
+                .iter()
+                .filter_map(|&entity| {
+                    let (&state, &priority, &order) = tasks.get(entity).ok()?;
+                    (state == TaskState::Queued).then_some((entity, priority, order))
+                })
+                .collect();
+            queue.sort_by_key(|&(_, priority, order)| queue_key(priority, order));
+            let mut queue: Vec<_> = queue
+                .into_iter()
+                .map(|(entity, priority, _)| (entity, priority))
+                .collect();
+
+            let Some(priority) = reorder(&mut queue, event.task, event.index) else {
+                error!(
+                    "task {:?} can't be moved to index {} by client {client_id}",
+                    event.task, event.index
+                );
+                continue;
+            };
+
+            for (index, &(entity, _)) in queue.iter().enumerate() {
+                let (_, mut task_priority, mut task_order) = tasks
+                    .get_mut(entity)
+                    .expect("queue should contain only tasks");
+                if entity == event.task {
+                    task_priority.set_if_neq(priority);
+                }
+                task_order.set_if_neq(TaskOrder(index as u32));
+            }
+        }
+    }
+
     fn activation_system(
-        mut tasks: Query<(&TaskGroups, &mut TaskState)>,
+        mut tasks: Query<(&TaskGroups, &TaskPriority, &TaskOrder, &mut TaskState)>,
         actors: Query<&Children, With<Actor>>,
     ) {
         for children in &actors {
             let current_groups = tasks
                 .iter_many(children)
-                .filter(|(_, &state)| state == TaskState::Active)
-                .map(|(&groups, _)| groups)
+                .filter(|(.., &state)| state == TaskState::Active)
+                .map(|(&groups, ..)| groups)
                 .reduce(|acc, groups| acc & groups)
                 .unwrap_or_default();
 
-            let mut iter = tasks.iter_many_mut(children);
-            while let Some((groups, mut state)) = iter.fetch_next() {
-                if *state == TaskState::Queued && !groups.intersects(current_groups) {
-                    *state = TaskState::Active;
-                    break;
-                }
+            let next_entity = children
+                .iter()
+                .filter_map(|&entity| {
+                    let (groups, &priority, &order, &state) = tasks.get(entity).ok()?;
+                    (state == TaskState::Queued && !groups.intersects(current_groups))
+                        .then_some((entity, queue_key(priority, order)))
+                })
+                .min_by_key(|&(_, key)| key)
+                .map(|(entity, _)| entity);
+
+            if let Some(entity) = next_entity {
+                let (.., mut state) = tasks
+                    .get_mut(entity)
+                    .expect("entity was obtained from the query");
+                *state = TaskState::Active;
             }
         }
     }
 
     fn cancelation_system(
-        mut commands: Commands,
         mut cancel_events: EventReader<FromClient<TaskCancel>>,
-        mut tasks: Query<&mut TaskState>,
+        mut finish_events: EventWriter<TaskFinished>,
+        ownership: Ownership,
+        mut tasks: Query<(&Parent, &mut TaskState)>,
     ) {
-        for event in cancel_events.iter().map(|event| &event.event) {
-            if let Ok(mut state) = tasks.get_mut(event.0) {
+        for FromClient { client_id, event } in &mut cancel_events {
+            if !ownership.can_control(*client_id, event.0) {
+                error!("client {client_id} is not allowed to cancel {:?}", event.0);
+            } else if let Ok((parent, mut state)) = tasks.get_mut(event.0) {
                 match *state {
-                    TaskState::Queued => commands.entity(event.0).despawn(),
+                    TaskState::Queued => finish_events.send(TaskFinished {
+                        task: event.0,
+                        actor: **parent,
+                        outcome: TaskOutcome::Cancelled,
+                    }),
                     TaskState::Active => *state = TaskState::Cancelled,
                     TaskState::Cancelled => (),
                 }
@@ -116,6 +249,39 @@ impl TaskPlugin {
             }
         }
     }
+
+    fn need_effects_system(
+        mut finish_events: EventReader<TaskFinished>,
+        tasks: Query<&NeedEffects>,
+        actors: Query<&Children>,
+        mut needs: Query<(&NeedKind, &mut Need)>,
+    ) {
+        for event in &mut finish_events {
+            if event.outcome != TaskOutcome::Success {
+                continue;
+            }
+            let (Ok(effects), Ok(children)) = (tasks.get(event.task), actors.get(event.actor))
+            else {
+                continue;
+            };
+
+            let mut iter = needs.iter_many_mut(children);
+            while let Some((&kind, mut need)) = iter.fetch_next() {
+                for effect in effects.0.iter().filter(|effect| effect.kind == kind) {
+                    need.0 = (need.0 + effect.delta).clamp(0.0, 100.0);
+                }
+            }
+        }
+    }
+
+    fn finish_system(mut commands: Commands, mut finish_events: EventReader<TaskFinished>) {
+        for event in &mut finish_events {
+            debug!("task {:?} finished with {:?}", event.task, event.outcome);
+            if let Some(mut entity) = commands.get_entity(event.task) {
+                entity.despawn();
+            }
+        }
+    }
 }
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
@@ -142,6 +308,98 @@ pub(crate) enum TaskState {
     Cancelled,
 }
 
+/// Importance of a task, queued tasks with higher priority are activated first.
+#[derive(Clone, Component, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Reflect)]
+#[reflect(Component)]
+pub(crate) enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Position of a task in the actor's queue among tasks with the same priority.
+#[derive(Clone, Component, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Reflect)]
+#[reflect(Component)]
+pub(crate) struct TaskOrder(pub(crate) u32);
+
+/// Spawns a queued task as a child of the actor and returns its entity.
+pub(crate) fn queue_task(
+    commands: &mut Commands,
+    actor_entity: Entity,
+    task: &dyn Task,
+    priority: TaskPriority,
+    order: TaskOrder,
+) -> Entity {
+    let mut task_entity = Entity::PLACEHOLDER;
+    commands.entity(actor_entity).with_children(|parent| {
+        task_entity = parent
+            .spawn((
+                Name::new(task.name().to_string()),
+                task.groups(),
+                priority,
+                order,
+                TaskProgress::default(),
+                NeedEffects(task.need_effects()),
+                Replication,
+                TaskState::Queued,
+            ))
+            .insert_reflect([task.clone_value()])
+            .id();
+    });
+    task_entity
+}
+
+/// Need changes that will be applied to the actor after successful completion of the task.
+///
+/// Available only on server.
+#[derive(Component)]
+struct NeedEffects(Vec<NeedEffect>);
+
+/// Completion percentage of an active task.
+///
+/// Updated by the plugin of the task if it's possible to estimate.
+#[derive(Clone, Component, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub(crate) struct TaskProgress(pub(crate) f32);
+
+/// Returns a key by which queued tasks are sorted in the order of their activation.
+pub(crate) fn queue_key(
+    priority: TaskPriority,
+    order: TaskOrder,
+) -> (Reverse<TaskPriority>, TaskOrder) {
+    (Reverse(priority), order)
+}
+
+/// Moves the task to the index in the queue sorted by [`queue_key`].
+///
+/// Returns the priority that the task should have to keep the queue sorted
+/// or [`None`] if the task is not in the queue or the index is out of bounds.
+fn reorder(
+    queue: &mut Vec<(Entity, TaskPriority)>,
+    task_entity: Entity,
+    index: usize,
+) -> Option<TaskPriority> {
+    if index >= queue.len() {
+        return None;
+    }
+    let current_index = queue
+        .iter()
+        .position(|&(entity, _)| entity == task_entity)?;
+
+    let (_, priority) = queue.remove(current_index);
+    queue.insert(index, (task_entity, priority));
+
+    // Adopt priority of the neighbor to stay at the index.
+    let neighbor = index
+        .checked_sub(1)
+        .or_else(|| (queue.len() > 1).then_some(1));
+    let priority = neighbor.map_or(priority, |neighbor| queue[neighbor].1);
+    queue[index].1 = priority;
+
+    Some(priority)
+}
+
 bitflags! {
     #[derive(Default, Component, Clone, Copy)]
     pub(crate) struct TaskGroups: u8 {
@@ -158,6 +416,30 @@ pub(crate) trait Task: Reflect + Debug {
     fn groups(&self) -> TaskGroups {
         TaskGroups::default()
     }
+    fn priority(&self) -> TaskPriority {
+        TaskPriority::default()
+    }
+    fn need_effects(&self) -> Vec<NeedEffect> {
+        Vec::new()
+    }
+}
+
+/// An event of task completion.
+///
+/// Sent by task plugins instead of despawning tasks, the task will be despawned after it.
+#[derive(Clone, Copy, Debug, Event)]
+pub(crate) struct TaskFinished {
+    pub(crate) task: Entity,
+    /// Actor that performed the task.
+    pub(crate) actor: Entity,
+    pub(crate) outcome: TaskOutcome,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TaskOutcome {
+    Success,
+    Cancelled,
+    Failed,
 }
 
 /// An event of canceling the specified task.
@@ -173,6 +455,22 @@ impl MapEventEntities for TaskCancel {
     }
 }
 
+/// An event of moving a queued task to the specified index in the actor's queue.
+///
+/// Index is in the order of [`queue_key`]. Emitted by players.
+#[derive(Debug, Deserialize, Event, Serialize)]
+pub(crate) struct TaskReorder {
+    pub(crate) task: Entity,
+    pub(crate) index: usize,
+}
+
+impl MapEventEntities for TaskReorder {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapError> {
+        self.task = entity_map.get(self.task).ok_or(MapError(self.task))?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Event)]
 pub(crate) struct TaskRequest {
     pub(crate) entity: Entity,
@@ -317,6 +615,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn task_reordering() {
+        let entities: Vec<_> = (0..3).map(Entity::from_raw).collect();
+        let mut queue = vec![
+            (entities[0], TaskPriority::High),
+            (entities[1], TaskPriority::Normal),
+            (entities[2], TaskPriority::Normal),
+        ];
+
+        assert_eq!(reorder(&mut queue, entities[2], 3), None);
+        assert_eq!(reorder(&mut queue, Entity::PLACEHOLDER, 0), None);
+
+        assert_eq!(
+            reorder(&mut queue, entities[2], 0),
+            Some(TaskPriority::High),
+            "task moved to the front should take priority of the next task"
+        );
+        assert_eq!(
+            queue,
+            [
+                (entities[2], TaskPriority::High),
+                (entities[0], TaskPriority::High),
+                (entities[1], TaskPriority::Normal),
+            ]
+        );
+
+        assert_eq!(
+            reorder(&mut queue, entities[0], 2),
+            Some(TaskPriority::Normal),
+            "task moved to the back should take priority of the previous task"
+        );
+        assert_eq!(
+            queue,
+            [
+                (entities[2], TaskPriority::High),
+                (entities[1], TaskPriority::Normal),
+                (entities[0], TaskPriority::Normal),
+            ]
+        );
+    }
+
     #[derive(Reflect, Debug)]
     struct DummyTask;
 
@@ -13,7 +13,12 @@ use itertools::{Itertools, MinMaxResult};
 use oxidized_navigation::NavMeshAffector;
 use serde::{Deserialize, Serialize};
 
-use super::{collision_groups::LifescapeGroupsExt, game_world::WorldName};
+use super::{
+    collision_groups::LifescapeGroupsExt,
+    game_world::{recording::AppRecordingExt, WorldName},
+    player::Ownership,
+    rejection::{self, RejectReason},
+};
 use creating_wall::{CreatingWall, CreatingWallPlugin};
 
 pub(super) struct WallPlugin;
@@ -26,13 +31,16 @@ impl Plugin for WallPlugin {
             .register_type::<Vec<(Vec2, Vec2)>>()
             .replicate::<WallEdges>()
             .add_mapped_client_event::<WallCreate>(SendPolicy::Unordered)
+            .record_mapped_client_event::<WallCreate>()
             .add_server_event::<WallEventConfirmed>(SendPolicy::Unordered)
+            .add_server_event::<WallEventRejected>(SendPolicy::Unordered)
             .add_systems(
                 Update,
                 (
                     (Self::init_system, Self::mesh_update_system)
                         .run_if(resource_exists::<WorldName>()),
                     Self::wall_creation_system.run_if(has_authority()),
+                    rejection::report_system::<WallEventRejected>,
                 ),
             );
     }
@@ -66,10 +74,23 @@ impl WallPlugin {
         mut commands: Commands,
         mut create_events: EventReader<FromClient<WallCreate>>,
         mut confirm_events: EventWriter<ToClients<WallEventConfirmed>>,
+        mut reject_events: EventWriter<ToClients<WallEventRejected>>,
+        ownership: Ownership,
         children: Query<&Children>,
         mut walls: Query<&mut WallEdges, Without<CreatingWall>>,
     ) {
         for FromClient { client_id, event } in create_events.iter().copied() {
+            if !ownership.can_control(client_id, event.lot_entity) {
+                error!(
+                    "client {client_id} is not allowed to build walls on {:?}",
+                    event.lot_entity
+                );
+                reject_events.send(ToClients {
+                    mode: SendMode::Direct(client_id),
+                    event: WallEventRejected(RejectReason::NotOwner),
+                });
+                continue;
+            }
             confirm_events.send(ToClients {
                 mode: SendMode::Direct(client_id),
                 event: WallEventConfirmed,
@@ -307,14 +328,14 @@ impl FromWorld for WallMaterial {
 }
 
 #[derive(Bundle)]
-struct WallBundle {
+pub(super) struct WallBundle {
     edges: WallEdges,
     parent_sync: ParentSync,
     replication: Replication,
 }
 
 impl WallBundle {
-    fn new(edges: Vec<(Vec2, Vec2)>) -> Self {
+    pub(super) fn new(edges: Vec<(Vec2, Vec2)>) -> Self {
         Self {
             edges: WallEdges(edges),
             parent_sync: Default::default(),
@@ -347,6 +368,10 @@ impl MapEventEntities for WallCreate {
 #[derive(Debug, Event, Serialize, Deserialize)]
 struct WallEventConfirmed;
 
+/// An event from server which indicates that the wall wasn't created.
+#[derive(Clone, Copy, Debug, Deref, Deserialize, Event, Serialize)]
+struct WallEventRejected(RejectReason);
+
 /// A component that marks that entity can be placed only on walls.
 #[derive(Component, Default, Reflect)]
 #[reflect(Component, Default)]
@@ -3,7 +3,7 @@ use leafwing_input_manager::common_conditions::{
     action_just_pressed, action_just_released, action_pressed,
 };
 
-use super::{WallCreate, WallEdges, WallEventConfirmed};
+use super::{WallCreate, WallEdges, WallEventConfirmed, WallEventRejected};
 use crate::core::{
     action::Action,
     cursor_hover::CursorHover,
@@ -30,6 +30,7 @@ impl Plugin for CreatingWallPlugin {
                     .run_if(any_with_component::<CreatingWall>()),
                 Self::despawn_system.run_if(action_just_pressed(Action::Cancel)),
                 Self::despawn_system.run_if(on_event::<WallEventConfirmed>()),
+                Self::despawn_system.run_if(on_event::<WallEventRejected>()),
             )
                 .run_if(in_state(GameState::Family))
                 .run_if(in_state(FamilyMode::Building))
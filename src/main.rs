@@ -7,15 +7,21 @@ mod ui;
 
 use std::time::Duration;
 
+use anyhow::Result;
 use bevy::{
+    animation::AnimationPlugin,
+    app::ScheduleRunnerPlugin,
     asset::ChangeWatcher,
+    gltf::GltfPlugin,
     log::LogPlugin,
     pbr::wireframe::WireframePlugin,
     prelude::*,
     render::{
+        mesh::skinning::SkinnedMeshInverseBindposes,
         settings::{WgpuFeatures, WgpuSettings},
         RenderPlugin,
     },
+    scene::ScenePlugin,
 };
 use bevy_atmosphere::prelude::*;
 use bevy_mod_outline::OutlinePlugin;
@@ -25,62 +31,126 @@ use bevy_replicon::prelude::*;
 use leafwing_input_manager::prelude::*;
 use oxidized_navigation::{NavMeshSettings, OxidizedNavigationPlugin};
 
-use crate::core::{action::Action, cli::Cli, CorePlugins};
+use crate::core::{action::Action, cli::Cli, CorePlugins, ServerPlugins};
 use ui::UiPlugins;
 
-fn main() {
-    App::new()
-        .insert_resource(AmbientLight {
-            color: Color::WHITE,
-            brightness: 0.3,
-        })
-        .init_resource::<Cli>()
-        .add_plugins(
-            DefaultPlugins
-                .set(LogPlugin {
-                    filter: "info,wgpu_core=warn,wgpu_hal=warn,naga=warn,lifescape=debug".into(),
-                    level: bevy::log::Level::DEBUG,
-                })
-                .set(AssetPlugin {
-                    watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+/// Number of updates per second for dedicated server.
+const SERVER_TICK_RATE: f64 = 60.0;
+
+fn main() -> Result<()> {
+    let cli = Cli::default();
+    if let Some(result) = cli.run_management() {
+        return result;
+    }
+
+    let dedicated_server = cli.is_dedicated_server();
+    // Replay uses recorded frame durations, so there is no need to wait between updates.
+    let tick = if cli.is_replay() {
+        Duration::ZERO
+    } else {
+        Duration::from_secs_f64(1.0 / SERVER_TICK_RATE)
+    };
+    let mut app = App::new();
+    app.insert_resource(cli);
+    if dedicated_server {
+        add_server_plugins(&mut app, tick);
+    } else {
+        add_game_plugins(&mut app);
+    }
+    app.run();
+
+    Ok(())
+}
+
+fn add_game_plugins(app: &mut App) {
+    app.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 0.3,
+    })
+    .add_plugins(
+        DefaultPlugins
+            .set(LogPlugin {
+                filter: "info,wgpu_core=warn,wgpu_hal=warn,naga=warn,lifescape=debug".into(),
+                level: bevy::log::Level::DEBUG,
+            })
+            .set(AssetPlugin {
+                watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+                ..Default::default()
+            })
+            .set(RenderPlugin {
+                wgpu_settings: WgpuSettings {
+                    features: WgpuFeatures::POLYGON_MODE_LINE,
                     ..Default::default()
-                })
-                .set(RenderPlugin {
-                    wgpu_settings: WgpuSettings {
-                        features: WgpuFeatures::POLYGON_MODE_LINE,
-                        ..Default::default()
-                    },
-                }),
-        )
-        .add_plugins((
-            ReplicationPlugins,
-            WireframePlugin,
-            AtmospherePlugin,
-            InputManagerPlugin::<Action>::default(),
-            OxidizedNavigationPlugin {
-                settings: NavMeshSettings {
-                    cell_width: 0.25,
-                    cell_height: 0.1,
-                    tile_width: 100,
-                    world_half_extents: 250.0,
-                    world_bottom_bound: -100.0,
-                    max_traversable_slope_radians: (40.0_f32 - 0.1).to_radians(),
-                    walkable_height: 20,
-                    walkable_radius: 1,
-                    step_height: 3,
-                    min_region_area: 100,
-                    merge_region_area: 500,
-                    max_contour_simplification_error: 1.1,
-                    max_edge_length: 80,
-                    max_tile_generation_tasks: None,
                 },
-            },
-            RapierPhysicsPlugin::<NoUserData>::default(),
-            RapierDebugRenderPlugin::default(),
-            OutlinePlugin,
-            PolylinePlugin,
-            CorePlugins,
-            UiPlugins,
-        ))
-        .run();
+            }),
+    )
+    .add_plugins((
+        ReplicationPlugins,
+        WireframePlugin,
+        AtmospherePlugin,
+        InputManagerPlugin::<Action>::default(),
+        OxidizedNavigationPlugin {
+            settings: nav_mesh_settings(),
+        },
+        RapierPhysicsPlugin::<NoUserData>::default(),
+        RapierDebugRenderPlugin::default(),
+        OutlinePlugin,
+        PolylinePlugin,
+        CorePlugins,
+        UiPlugins,
+    ));
+}
+
+/// Adds plugins for a dedicated server without window and renderer.
+///
+/// Updates run with the specified interval.
+fn add_server_plugins(app: &mut App, tick: Duration) {
+    app.add_plugins((
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(tick)),
+        LogPlugin {
+            filter: "info,lifescape=debug".into(),
+            level: bevy::log::Level::DEBUG,
+        },
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+        AnimationPlugin,
+    ))
+    // Assets that are normally registered by rendering plugins.
+    // Needed to load object scenes with colliders and create wall meshes.
+    .add_asset::<Mesh>()
+    .add_asset::<SkinnedMeshInverseBindposes>()
+    .add_asset::<Image>()
+    .add_asset::<StandardMaterial>()
+    .add_asset::<Polyline>()
+    .add_asset::<PolylineMaterial>()
+    .add_plugins((
+        GltfPlugin::default(),
+        ReplicationPlugins,
+        OxidizedNavigationPlugin {
+            settings: nav_mesh_settings(),
+        },
+        RapierPhysicsPlugin::<NoUserData>::default(),
+        ServerPlugins,
+    ));
+}
+
+fn nav_mesh_settings() -> NavMeshSettings {
+    NavMeshSettings {
+        cell_width: 0.25,
+        cell_height: 0.1,
+        tile_width: 100,
+        world_half_extents: 250.0,
+        world_bottom_bound: -100.0,
+        max_traversable_slope_radians: (40.0_f32 - 0.1).to_radians(),
+        walkable_height: 20,
+        walkable_radius: 1,
+        step_height: 3,
+        min_region_area: 100,
+        merge_region_area: 500,
+        max_contour_simplification_error: 1.1,
+        max_edge_length: 80,
+        max_tile_generation_tasks: None,
+    }
 }
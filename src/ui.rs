@@ -6,6 +6,7 @@ mod hud;
 mod ingame_menu;
 mod main_menu;
 mod preview;
+mod save_indicator;
 mod settings_menu;
 mod theme;
 mod widget;
@@ -22,6 +23,7 @@ use hud::HudPlugin;
 use ingame_menu::InGameMenuPlugin;
 use main_menu::MainMenuPlugin;
 use preview::PreviewPlugin;
+use save_indicator::SaveIndicatorPlugin;
 use settings_menu::SettingsMenuPlugin;
 use theme::ThemePlugin;
 use widget::WidgetPlugin;
@@ -42,6 +44,7 @@ impl PluginGroup for UiPlugins {
             .add(WidgetPlugin)
             .add(MainMenuPlugin)
             .add(PreviewPlugin)
+            .add(SaveIndicatorPlugin)
             .add(SettingsMenuPlugin)
             .add(ThemePlugin)
             .add(WorldBrowserPlugin)
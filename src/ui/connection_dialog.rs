@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use bevy_replicon::{prelude::*, renet::transport::NetcodeClientTransport};
 
+use crate::core::network::{reconnect::Reconnect, ClientSettings};
+
 use super::{
     theme::Theme,
     widget::{button::TextButtonBundle, click::Click, ui_root::UiRoot, DialogBundle, LabelBundle},
@@ -22,7 +24,16 @@ impl Plugin for ConnectionDialogPlugin {
 }
 
 impl ConnectionDialogPlugin {
-    fn setup_system(mut commands: Commands, theme: Res<Theme>, roots: Query<Entity, With<UiRoot>>) {
+    fn setup_system(
+        mut commands: Commands,
+        theme: Res<Theme>,
+        reconnect: Option<Res<Reconnect>>,
+        roots: Query<Entity, With<UiRoot>>,
+    ) {
+        let text = match reconnect {
+            Some(reconnect) => format!("Reconnecting to server, attempt {}", reconnect.attempt()),
+            None => "Connecting to server".to_string(),
+        };
         commands.entity(roots.single()).with_children(|parent| {
             parent
                 .spawn((ConnectionDialog, DialogBundle::new(&theme)))
@@ -41,7 +52,7 @@ impl ConnectionDialogPlugin {
                             ..Default::default()
                         })
                         .with_children(|parent| {
-                            parent.spawn(LabelBundle::normal(&theme, "Connecting to server"));
+                            parent.spawn(LabelBundle::normal(&theme, text));
                             parent
                                 .spawn((CancelButton, TextButtonBundle::normal(&theme, "Cancel")));
                         });
@@ -58,13 +69,19 @@ impl ConnectionDialogPlugin {
         for event in &mut click_events {
             if buttons.get(event.0).is_ok() {
                 commands.remove_resource::<RenetClient>();
+                commands.remove_resource::<NetcodeClientTransport>();
+                commands.remove_resource::<Reconnect>();
+                commands.remove_resource::<ClientSettings>();
                 commands.entity(dialogs.single()).despawn_recursive();
             }
         }
     }
 
     fn cleanup_system(mut commands: Commands, dialogs: Query<Entity, With<ConnectionDialog>>) {
-        commands.entity(dialogs.single()).despawn_recursive();
+        // Connection could be lost after the dialog was closed.
+        if let Ok(entity) = dialogs.get_single() {
+            commands.entity(entity).despawn_recursive();
+        }
     }
 }
 
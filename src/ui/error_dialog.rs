@@ -4,13 +4,20 @@ use super::{
     theme::Theme,
     widget::{button::TextButtonBundle, click::Click, ui_root::UiRoot, DialogBundle, LabelBundle},
 };
-use crate::core::error::ErrorReport;
+use crate::core::{error::ErrorReport, game_world::LoadReport};
 
 pub(super) struct ErrorDialogPlugin;
 
 impl Plugin for ErrorDialogPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (Self::setup_system, Self::button_system));
+        app.add_systems(
+            Update,
+            (
+                Self::setup_system,
+                Self::load_report_system.run_if(resource_added::<LoadReport>()),
+                Self::button_system,
+            ),
+        );
     }
 }
 
@@ -22,35 +29,30 @@ impl ErrorDialogPlugin {
         roots: Query<Entity, With<UiRoot>>,
     ) {
         for error in &mut error_events {
-            commands.entity(roots.single()).with_children(|parent| {
-                parent
-                    .spawn((ErrorDialog, DialogBundle::new(&theme)))
-                    .with_children(|parent| {
-                        parent
-                            .spawn(NodeBundle {
-                                style: Style {
-                                    flex_direction: FlexDirection::Column,
-                                    justify_content: JustifyContent::Center,
-                                    align_items: AlignItems::Center,
-                                    padding: theme.padding.normal,
-                                    row_gap: theme.gap.normal,
-                                    ..Default::default()
-                                },
-                                background_color: theme.panel_color.into(),
-                                ..Default::default()
-                            })
-                            .with_children(|parent| {
-                                parent.spawn(LabelBundle::normal(
-                                    &theme,
-                                    format!("Error: {:#}", error.0),
-                                ));
-                                parent.spawn((OkButton, TextButtonBundle::normal(&theme, "Ok")));
-                            });
-                    });
-            });
+            setup_dialog(
+                &mut commands,
+                roots.single(),
+                &theme,
+                format!("Error: {:#}", error.0),
+            );
         }
     }
 
+    fn load_report_system(
+        mut commands: Commands,
+        report: Res<LoadReport>,
+        theme: Res<Theme>,
+        roots: Query<Entity, With<UiRoot>>,
+    ) {
+        setup_dialog(
+            &mut commands,
+            roots.single(),
+            &theme,
+            format!("World loaded with {}", *report),
+        );
+        commands.remove_resource::<LoadReport>();
+    }
+
     fn button_system(
         mut commands: Commands,
         mut click_events: EventReader<Click>,
@@ -65,6 +67,32 @@ impl ErrorDialogPlugin {
     }
 }
 
+fn setup_dialog(commands: &mut Commands, root_entity: Entity, theme: &Theme, text: String) {
+    commands.entity(root_entity).with_children(|parent| {
+        parent
+            .spawn((ErrorDialog, DialogBundle::new(theme)))
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: theme.padding.normal,
+                            row_gap: theme.gap.normal,
+                            ..Default::default()
+                        },
+                        background_color: theme.panel_color.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(LabelBundle::normal(theme, text));
+                        parent.spawn((OkButton, TextButtonBundle::normal(theme, "Ok")));
+                    });
+            });
+    });
+}
+
 #[derive(Component)]
 struct OkButton;
 
@@ -1,3 +1,4 @@
+mod chat_node;
 mod city_hud;
 mod family_hud;
 mod objects_node;
@@ -5,6 +6,7 @@ pub(super) mod task_menu;
 
 use bevy::prelude::*;
 
+use chat_node::ChatNodePlugin;
 use city_hud::CityHudPlugin;
 use family_hud::FamilyHudPlugin;
 use objects_node::ObjectsNodePlugin;
@@ -15,6 +17,7 @@ pub(super) struct HudPlugin;
 impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
+            ChatNodePlugin,
             CityHudPlugin,
             ObjectsNodePlugin,
             FamilyHudPlugin,
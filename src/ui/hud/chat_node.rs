@@ -0,0 +1,108 @@
+use std::mem;
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{
+    core::{
+        chat::{ChatHistory, ChatSend},
+        game_state::GameState,
+        settings::Settings,
+    },
+    ui::{
+        theme::Theme,
+        widget::text_edit::{ActiveEdit, TextEditBundle},
+    },
+};
+
+pub(super) struct ChatNodePlugin;
+
+impl Plugin for ChatNodePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                Self::messages_system,
+                Self::send_system.run_if(input_just_pressed(KeyCode::Return)),
+            )
+                .run_if(in_state(GameState::Family).or_else(in_state(GameState::City))),
+        );
+    }
+}
+
+impl ChatNodePlugin {
+    fn messages_system(
+        theme: Res<Theme>,
+        settings: Res<Settings>,
+        history: Res<ChatHistory>,
+        mut labels: Query<(&mut Text, Ref<ChatLabel>)>,
+    ) {
+        let Ok((mut text, label)) = labels.get_single_mut() else {
+            return;
+        };
+        if !history.is_changed() && !label.is_added() {
+            return;
+        }
+
+        let skip = history.iter().count().saturating_sub(VISIBLE_MESSAGES);
+        text.sections = history
+            .iter()
+            .skip(skip)
+            .enumerate()
+            .map(|(index, message)| {
+                let style = match message.sender_id() {
+                    Some(id) if id == settings.player.id => &theme.chat.own_text,
+                    Some(_) => &theme.chat.player_text,
+                    None => &theme.chat.system_text,
+                };
+                let separator = if index == 0 { "" } else { "\n" };
+                TextSection::new(format!("{separator}{message}"), style.clone())
+            })
+            .collect();
+    }
+
+    fn send_system(
+        mut commands: Commands,
+        mut send_events: EventWriter<ChatSend>,
+        mut chat_edits: Query<(Entity, &mut Text), (With<ChatEdit>, With<ActiveEdit>)>,
+    ) {
+        if let Ok((entity, mut text)) = chat_edits.get_single_mut() {
+            let message = mem::take(&mut text.sections[0].value);
+            if !message.trim().is_empty() {
+                send_events.send(ChatSend(message));
+            }
+            commands.entity(entity).remove::<ActiveEdit>();
+        }
+    }
+}
+
+/// Number of last messages from [`ChatHistory`] displayed in the HUD.
+const VISIBLE_MESSAGES: usize = 8;
+
+pub(super) fn setup_chat_node(parent: &mut ChildBuilder, theme: &Theme) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                top: Val::Px(0.0),
+                width: Val::Px(450.0),
+                flex_direction: FlexDirection::Column,
+                padding: theme.padding.normal,
+                row_gap: theme.gap.normal,
+                ..Default::default()
+            },
+            background_color: theme.panel_color.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn((ChatLabel, TextBundle::default()));
+            parent.spawn((ChatEdit, TextEditBundle::empty(theme)));
+        });
+}
+
+#[derive(Component)]
+struct ChatLabel;
+
+/// Text edit that sends its content on enter.
+#[derive(Component)]
+struct ChatEdit;
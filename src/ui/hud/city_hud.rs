@@ -1,19 +1,30 @@
+use std::mem;
+
 use bevy::prelude::*;
-use strum::IntoEnumIterator;
+use strum::{Display, EnumIter, IntoEnumIterator};
 
 use crate::{
     core::{
         asset_metadata::{ObjectCategory, ObjectMetadata},
         city::CityMode,
+        game_paths::GamePaths,
         game_state::GameState,
-        lot::LotTool,
+        lot::{
+            blueprint::{
+                ActiveBlueprint, BlueprintExport, BlueprintExportRequest, BlueprintExported,
+            },
+            LotTool,
+        },
     },
     ui::{
-        hud::objects_node,
+        hud::{chat_node, objects_node},
         theme::Theme,
         widget::{
             button::{ExclusiveButton, TabContent, TextButtonBundle, Toggled},
+            click::Click,
+            text_edit::{ActiveEdit, TextEditBundle},
             ui_root::UiRoot,
+            Dialog, DialogBundle, LabelBundle,
         },
     },
 };
@@ -25,7 +36,14 @@ impl Plugin for CityHudPlugin {
         app.add_systems(OnEnter(GameState::City), Self::setup_system)
             .add_systems(
                 Update,
-                (Self::mode_button_system, Self::tool_button_system)
+                (
+                    Self::mode_button_system,
+                    Self::tool_button_system,
+                    Self::blueprint_button_system,
+                    Self::export_dialog_system,
+                    Self::export_dialog_button_system,
+                    Self::blueprints_refresh_system.run_if(on_event::<BlueprintExported>()),
+                )
                     .run_if(in_state(GameState::City)),
             );
     }
@@ -36,8 +54,14 @@ impl CityHudPlugin {
         mut commands: Commands,
         mut tab_commands: Commands,
         theme: Res<Theme>,
+        game_paths: Res<GamePaths>,
         object_metadata: Res<Assets<ObjectMetadata>>,
     ) {
+        let blueprint_names = game_paths
+            .get_blueprint_names()
+            .map_err(|e| error!("unable to get blueprint names: {e}"))
+            .unwrap_or_default();
+
         commands
             .spawn((
                 UiRoot,
@@ -51,6 +75,8 @@ impl CityHudPlugin {
                 },
             ))
             .with_children(|parent| {
+                chat_node::setup_chat_node(parent, &theme);
+
                 let tabs_entity = parent
                     .spawn(NodeBundle {
                         style: Style {
@@ -86,7 +112,7 @@ impl CityHudPlugin {
                                     ObjectCategory::CITY_CATEGORIES,
                                 );
                             }
-                            CityMode::Lots => setup_lots_node(parent, &theme),
+                            CityMode::Lots => setup_lots_node(parent, &theme, &blueprint_names),
                         })
                         .id();
 
@@ -124,9 +150,80 @@ impl CityHudPlugin {
             }
         }
     }
+
+    fn blueprint_button_system(
+        mut commands: Commands,
+        buttons: Query<(Ref<Toggled>, &BlueprintButton), Changed<Toggled>>,
+    ) {
+        for (toggled, blueprint_button) in &buttons {
+            if toggled.0 && !toggled.is_added() {
+                commands.insert_resource(ActiveBlueprint(blueprint_button.0.clone()));
+            }
+        }
+    }
+
+    fn export_dialog_system(
+        mut commands: Commands,
+        mut request_events: EventReader<BlueprintExportRequest>,
+        theme: Res<Theme>,
+        roots: Query<Entity, With<UiRoot>>,
+    ) {
+        if let Some(event) = request_events.iter().last() {
+            setup_export_dialog(&mut commands, roots.single(), event.0, &theme);
+        }
+    }
+
+    fn export_dialog_button_system(
+        mut commands: Commands,
+        mut export_events: EventWriter<BlueprintExport>,
+        mut click_events: EventReader<Click>,
+        mut text_edits: Query<&mut Text, With<BlueprintNameEdit>>,
+        buttons: Query<&ExportDialogButton>,
+        dialogs: Query<(Entity, &ExportLot), With<Dialog>>,
+    ) {
+        for event in &mut click_events {
+            let Ok(&button) = buttons.get(event.0) else {
+                continue;
+            };
+
+            let (dialog_entity, export_lot) = dialogs.single();
+            if button == ExportDialogButton::Export {
+                let mut blueprint_name = text_edits.single_mut();
+                export_events.send(BlueprintExport {
+                    lot_entity: export_lot.0,
+                    name: mem::take(&mut blueprint_name.sections[0].value),
+                });
+            }
+
+            commands.entity(dialog_entity).despawn_recursive();
+        }
+    }
+
+    fn blueprints_refresh_system(
+        mut commands: Commands,
+        theme: Res<Theme>,
+        game_paths: Res<GamePaths>,
+        active_blueprint: Option<Res<ActiveBlueprint>>,
+        blueprints_nodes: Query<Entity, With<BlueprintsNode>>,
+    ) {
+        let blueprint_names = game_paths
+            .get_blueprint_names()
+            .map_err(|e| error!("unable to get blueprint names: {e}"))
+            .unwrap_or_default();
+
+        let active_name = active_blueprint
+            .as_ref()
+            .map(|blueprint| blueprint.0.as_str());
+        commands
+            .entity(blueprints_nodes.single())
+            .despawn_descendants()
+            .with_children(|parent| {
+                setup_blueprint_buttons(parent, &theme, &blueprint_names, active_name)
+            });
+    }
 }
 
-fn setup_lots_node(parent: &mut ChildBuilder, theme: &Theme) {
+fn setup_lots_node(parent: &mut ChildBuilder, theme: &Theme, blueprint_names: &[String]) {
     parent
         .spawn(NodeBundle {
             style: Style {
@@ -145,4 +242,106 @@ fn setup_lots_node(parent: &mut ChildBuilder, theme: &Theme) {
                 ));
             }
         });
+
+    parent
+        .spawn((
+            BlueprintsNode,
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: theme.gap.normal,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| setup_blueprint_buttons(parent, theme, blueprint_names, None));
+}
+
+fn setup_blueprint_buttons(
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    blueprint_names: &[String],
+    active_name: Option<&str>,
+) {
+    for name in blueprint_names {
+        parent.spawn((
+            BlueprintButton(name.clone()),
+            ExclusiveButton,
+            Toggled(active_name == Some(name.as_str())),
+            TextButtonBundle::normal(theme, name),
+        ));
+    }
+}
+
+fn setup_export_dialog(
+    commands: &mut Commands,
+    root_entity: Entity,
+    lot_entity: Entity,
+    theme: &Theme,
+) {
+    commands.entity(root_entity).with_children(|parent| {
+        parent
+            .spawn((ExportLot(lot_entity), DialogBundle::new(theme)))
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: theme.padding.normal,
+                            row_gap: theme.gap.normal,
+                            ..Default::default()
+                        },
+                        background_color: theme.panel_color.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(LabelBundle::normal(theme, "Export blueprint"));
+                        parent.spawn((
+                            BlueprintNameEdit,
+                            ActiveEdit,
+                            TextEditBundle::new(theme, "New blueprint"),
+                        ));
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    column_gap: theme.gap.normal,
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                for dialog_button in ExportDialogButton::iter() {
+                                    parent.spawn((
+                                        dialog_button,
+                                        TextButtonBundle::normal(theme, dialog_button.to_string()),
+                                    ));
+                                }
+                            });
+                    });
+            });
+    });
+}
+
+/// Contains [`BlueprintButton`]s, refreshed after each export.
+#[derive(Component)]
+struct BlueprintsNode;
+
+/// Selects blueprint for [`LotTool::Blueprint`].
+#[derive(Component)]
+struct BlueprintButton(String);
+
+/// Lot that will be exported from the dialog.
+#[derive(Component)]
+struct ExportLot(Entity);
+
+#[derive(Component)]
+struct BlueprintNameEdit;
+
+#[derive(Component, EnumIter, Clone, Copy, Display, PartialEq)]
+enum ExportDialogButton {
+    Export,
+    Cancel,
 }
@@ -1,17 +1,18 @@
 use bevy::prelude::*;
 use strum::{EnumIter, IntoEnumIterator};
 
-use super::objects_node;
+use super::{chat_node, objects_node};
 use crate::{
     core::{
         actor::{
+            autonomy::{FreeWill, FreeWillToggle},
             needs::{Need, NeedGlyph},
             ActiveActor,
         },
         asset_metadata::{ObjectCategory, ObjectMetadata},
         family::{ActiveFamily, Budget, BuildingMode, FamilyMembers, FamilyMode, FamilyPlugin},
         game_state::GameState,
-        task::{TaskCancel, TaskState},
+        task::{self, TaskCancel, TaskOrder, TaskPriority, TaskProgress, TaskReorder, TaskState},
     },
     ui::{
         preview::Preview,
@@ -21,6 +22,7 @@ use crate::{
                 ButtonPlugin, ExclusiveButton, ImageButtonBundle, TabContent, TextButtonBundle,
                 Toggled,
             },
+            checkbox::{Checkbox, CheckboxBundle},
             click::Click,
             progress_bar::{ProgressBar, ProgressBarBundle},
             ui_root::UiRoot,
@@ -43,15 +45,24 @@ impl Plugin for FamilyHudPlugin {
             Update,
             (
                 Self::mode_button_system,
-                Self::tasks_node_system,
+                (
+                    Self::tasks_node_system,
+                    apply_deferred,
+                    Self::queue_sorting_system,
+                )
+                    .chain(),
                 // To run despawn commands after image spawns.
                 Self::task_cleanup_system.after(ButtonPlugin::image_init_system),
+                Self::task_progress_system,
                 Self::need_bars_system,
                 Self::budget_system,
+                Self::free_will_system,
                 Self::building_mode_button_system.run_if(in_state(FamilyMode::Building)),
                 (
                     Self::tasks_node_setup_system,
                     Self::task_button_system,
+                    Self::task_drag_system,
+                    Self::task_drop_system,
                     Self::actor_buttons_system,
                     Self::needs_node_setup_system,
                 )
@@ -68,7 +79,7 @@ impl FamilyHudPlugin {
         mut tab_commands: Commands,
         theme: Res<Theme>,
         object_metadata: Res<Assets<ObjectMetadata>>,
-        families: Query<(&Budget, &FamilyMembers), With<ActiveFamily>>,
+        families: Query<(&Budget, &FamilyMembers, Option<&FreeWill>), With<ActiveFamily>>,
         actors: Query<Entity, With<ActiveActor>>,
     ) {
         commands
@@ -84,6 +95,8 @@ impl FamilyHudPlugin {
                 },
             ))
             .with_children(|parent| {
+                chat_node::setup_chat_node(parent, &theme);
+
                 let tabs_entity = parent
                     .spawn(NodeBundle {
                         style: Style {
@@ -110,8 +123,13 @@ impl FamilyHudPlugin {
                             FamilyMode::Life => {
                                 setup_tasks_node(parent, &theme);
 
-                                let (&budget, members) = families.single();
+                                let (&budget, members, free_will) = families.single();
                                 setup_portrait_node(parent, &theme, budget);
+                                setup_free_will_node(
+                                    parent,
+                                    &theme,
+                                    free_will.map_or(false, |free_will| free_will.0),
+                                );
                                 setup_members_node(parent, &theme, members, actors.single());
                                 setup_info_node(parent, &mut tab_commands, &theme);
                             }
@@ -152,7 +170,7 @@ impl FamilyHudPlugin {
         mut commands: Commands,
         theme: Res<Theme>,
         actors: Query<&Children, Added<ActiveActor>>,
-        tasks: Query<(Entity, &TaskState)>,
+        tasks: Query<(Entity, &TaskState, &TaskProgress)>,
         queued_task_nodes: Query<Entity, With<QueuedTasksNode>>,
         active_task_nodes: Query<Entity, With<ActiveTasksNode>>,
     ) {
@@ -165,7 +183,7 @@ impl FamilyHudPlugin {
         commands.entity(queued_entity).despawn_descendants();
         commands.entity(active_entity).despawn_descendants();
 
-        for (task_entity, state) in tasks.iter_many(children) {
+        for (task_entity, state, progress) in tasks.iter_many(children) {
             match *state {
                 TaskState::Queued => {
                     commands.entity(queued_entity).with_children(|parent| {
@@ -181,6 +199,7 @@ impl FamilyHudPlugin {
                             ButtonTask(task_entity),
                             ImageButtonBundle::placeholder(&theme),
                         ));
+                        setup_task_progress_bar(parent, &theme, task_entity, progress.0);
                     });
                 }
                 TaskState::Cancelled => continue,
@@ -192,7 +211,7 @@ impl FamilyHudPlugin {
         mut commands: Commands,
         theme: Res<Theme>,
         actors: Query<(&Children, Ref<ActiveActor>)>,
-        tasks: Query<(Entity, &TaskState), Changed<TaskState>>,
+        tasks: Query<(Entity, &TaskState, &TaskProgress), Changed<TaskState>>,
         queued_task_nodes: Query<Entity, With<QueuedTasksNode>>,
         active_task_nodes: Query<Entity, With<ActiveTasksNode>>,
         buttons: Query<(Entity, &ButtonTask)>,
@@ -202,7 +221,7 @@ impl FamilyHudPlugin {
             return;
         }
 
-        for (task_entity, state) in tasks.iter_many(children) {
+        for (task_entity, state, progress) in tasks.iter_many(children) {
             match *state {
                 TaskState::Queued => {
                     commands
@@ -220,9 +239,11 @@ impl FamilyHudPlugin {
                         .find(|(_, button_task)| button_task.0 == task_entity)
                         .expect("all tasks should be queued first");
 
-                    commands
-                        .entity(button_entity)
-                        .set_parent(active_task_nodes.single());
+                    let active_entity = active_task_nodes.single();
+                    commands.entity(button_entity).set_parent(active_entity);
+                    commands.entity(active_entity).with_children(|parent| {
+                        setup_task_progress_bar(parent, &theme, task_entity, progress.0);
+                    });
                 }
                 TaskState::Cancelled => continue,
             };
@@ -241,10 +262,100 @@ impl FamilyHudPlugin {
         }
     }
 
+    /// Keeps queued task buttons in the order of activation.
+    fn queue_sorting_system(
+        mut commands: Commands,
+        tasks: Query<(&TaskPriority, &TaskOrder)>,
+        queued_task_nodes: Query<(Entity, Option<&Children>), With<QueuedTasksNode>>,
+        buttons: Query<&ButtonTask>,
+    ) {
+        let Ok((node_entity, Some(children))) = queued_task_nodes.get_single() else {
+            return;
+        };
+
+        let mut sorted_buttons = children.to_vec();
+        sorted_buttons.sort_by_key(|&button_entity| {
+            buttons
+                .get(button_entity)
+                .ok()
+                .and_then(|button_task| tasks.get(button_task.0).ok())
+                .map(|(&priority, &order)| task::queue_key(priority, order))
+        });
+        if children[..] != sorted_buttons[..] {
+            commands
+                .entity(node_entity)
+                .replace_children(&sorted_buttons);
+        }
+    }
+
+    fn task_drag_system(
+        mut commands: Commands,
+        buttons: Query<(Entity, &Interaction), (Changed<Interaction>, With<ButtonTask>)>,
+    ) {
+        for (entity, &interaction) in &buttons {
+            if interaction == Interaction::Pressed {
+                commands.entity(entity).insert(DraggedTask);
+            }
+        }
+    }
+
+    /// Moves the dragged task to the position of the queued task under the cursor.
+    fn task_drop_system(
+        mut commands: Commands,
+        mut reorder_events: EventWriter<TaskReorder>,
+        mouse_buttons: Res<Input<MouseButton>>,
+        dragged_buttons: Query<(Entity, &ButtonTask), With<DraggedTask>>,
+        buttons: Query<&Interaction, With<ButtonTask>>,
+        queued_task_nodes: Query<&Children, With<QueuedTasksNode>>,
+    ) {
+        if !mouse_buttons.just_released(MouseButton::Left) {
+            return;
+        }
+        let Ok((dragged_entity, dragged_task)) = dragged_buttons.get_single() else {
+            return;
+        };
+        commands.entity(dragged_entity).remove::<DraggedTask>();
+
+        let Ok(children) = queued_task_nodes.get_single() else {
+            return;
+        };
+        if !children.contains(&dragged_entity) {
+            return;
+        }
+
+        // Releasing over the dragged button is a click.
+        if let Some(index) = children.iter().position(|&entity| {
+            entity != dragged_entity
+                && buttons
+                    .get(entity)
+                    .map_or(false, |&interaction| interaction == Interaction::Hovered)
+        }) {
+            reorder_events.send(TaskReorder {
+                task: dragged_task.0,
+                index,
+            });
+        }
+    }
+
+    fn task_progress_system(
+        tasks: Query<(Entity, &TaskProgress), Changed<TaskProgress>>,
+        mut progress_bars: Query<(&mut ProgressBar, &BarTask)>,
+    ) {
+        for (task_entity, progress) in &tasks {
+            if let Some((mut progress_bar, _)) = progress_bars
+                .iter_mut()
+                .find(|(_, bar_task)| bar_task.0 == task_entity)
+            {
+                progress_bar.0 = progress.0;
+            }
+        }
+    }
+
     fn task_cleanup_system(
         mut commands: Commands,
         mut removed_tasks: RemovedComponents<TaskState>,
         buttons: Query<(Entity, &ButtonTask)>,
+        progress_bars: Query<(Entity, &BarTask)>,
     ) {
         for task_entity in &mut removed_tasks {
             if let Some((button_entity, _)) = buttons
@@ -253,6 +364,12 @@ impl FamilyHudPlugin {
             {
                 commands.entity(button_entity).despawn_recursive();
             }
+            if let Some((bar_entity, _)) = progress_bars
+                .iter()
+                .find(|(_, bar_task)| bar_task.0 == task_entity)
+            {
+                commands.entity(bar_entity).despawn_recursive();
+            }
         }
     }
 
@@ -265,6 +382,21 @@ impl FamilyHudPlugin {
         }
     }
 
+    fn free_will_system(
+        mut toggle_events: EventWriter<FreeWillToggle>,
+        checkboxes: Query<Ref<Checkbox>, With<FreeWillCheckbox>>,
+        families: Query<Entity, With<ActiveFamily>>,
+    ) {
+        for checkbox in &checkboxes {
+            if checkbox.is_changed() && !checkbox.is_added() {
+                toggle_events.send(FreeWillToggle {
+                    family: families.single(),
+                    enabled: checkbox.0,
+                });
+            }
+        }
+    }
+
     fn actor_buttons_system(
         mut commands: Commands,
         actor_buttons: Query<(Ref<Toggled>, &PlayActor), Changed<Toggled>>,
@@ -412,6 +544,23 @@ fn setup_tasks_node(parent: &mut ChildBuilder, theme: &Theme) {
         });
 }
 
+fn setup_task_progress_bar(
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    task_entity: Entity,
+    progress: f32,
+) {
+    parent
+        .spawn((
+            BarTask(task_entity),
+            ProgressBarBundle::new(theme, progress),
+        ))
+        .insert(Style {
+            height: Val::Px(6.0),
+            ..Default::default()
+        });
+}
+
 fn setup_portrait_node(parent: &mut ChildBuilder, theme: &Theme, budget: Budget) {
     parent
         .spawn(NodeBundle {
@@ -430,6 +579,25 @@ fn setup_portrait_node(parent: &mut ChildBuilder, theme: &Theme, budget: Budget)
         });
 }
 
+fn setup_free_will_node(parent: &mut ChildBuilder, theme: &Theme, free_will: bool) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_self: AlignSelf::FlexEnd,
+                padding: theme.padding.normal,
+                ..Default::default()
+            },
+            background_color: theme.panel_color.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                FreeWillCheckbox,
+                CheckboxBundle::new(theme, free_will, "Free will"),
+            ));
+        });
+}
+
 fn setup_members_node(
     parent: &mut ChildBuilder,
     theme: &Theme,
@@ -596,15 +764,25 @@ struct QueuedTasksNode;
 #[derive(Component)]
 struct ButtonTask(Entity);
 
+/// Marks a task button that is pressed and could be dropped on another queued task.
+#[derive(Component)]
+struct DraggedTask;
+
 #[derive(Component)]
 struct BudgetLabel;
 
+#[derive(Component)]
+struct FreeWillCheckbox;
+
 #[derive(Component)]
 struct PlayActor(Entity);
 
 #[derive(Component)]
 struct BarNeed(Entity);
 
+#[derive(Component)]
+struct BarTask(Entity);
+
 #[derive(Component, EnumIter, Clone, Copy, PartialEq)]
 enum InfoTab {
     Needs,
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+
+use super::{theme::Theme, widget::LabelBundle};
+use crate::core::game_world::{GameSave, GameSaveFailed, GameSaved};
+
+/// Shows status of the background world saving in the corner of the screen.
+pub(super) struct SaveIndicatorPlugin;
+
+impl Plugin for SaveIndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                Self::setup_system.run_if(on_event::<GameSave>()),
+                Self::saved_system.run_if(on_event::<GameSaved>()),
+                Self::hide_system,
+                Self::cleanup_system.run_if(on_event::<GameSaveFailed>()),
+            ),
+        );
+    }
+}
+
+impl SaveIndicatorPlugin {
+    fn setup_system(
+        mut commands: Commands,
+        theme: Res<Theme>,
+        indicators: Query<Entity, With<SaveIndicator>>,
+    ) {
+        for entity in &indicators {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        commands
+            .spawn((
+                SaveIndicator,
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(0.0),
+                        right: Val::Px(0.0),
+                        padding: theme.padding.normal,
+                        ..Default::default()
+                    },
+                    background_color: theme.panel_color.into(),
+                    ..Default::default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn(LabelBundle::normal(&theme, "Saving…"));
+            });
+    }
+
+    fn saved_system(
+        mut commands: Commands,
+        indicators: Query<(Entity, &Children), With<SaveIndicator>>,
+        mut texts: Query<&mut Text>,
+    ) {
+        for (entity, children) in &indicators {
+            let mut iter = texts.iter_many_mut(children);
+            let mut text = iter
+                .fetch_next()
+                .expect("save indicator should have child label");
+            text.sections[0].value = "Saved".to_string();
+
+            commands
+                .entity(entity)
+                .insert(HideTimer(Timer::from_seconds(2.0, TimerMode::Once)));
+        }
+    }
+
+    fn hide_system(
+        mut commands: Commands,
+        time: Res<Time>,
+        mut indicators: Query<(Entity, &mut HideTimer)>,
+    ) {
+        for (entity, mut hide_timer) in &mut indicators {
+            if hide_timer.tick(time.delta()).finished() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+
+    fn cleanup_system(mut commands: Commands, indicators: Query<Entity, With<SaveIndicator>>) {
+        for entity in &indicators {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[derive(Component)]
+struct SaveIndicator;
+
+/// Despawns the indicator after the timer finishes.
+#[derive(Component, Deref, DerefMut)]
+struct HideTimer(Timer);
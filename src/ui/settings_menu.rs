@@ -96,6 +96,7 @@ impl SettingsMenuPlugin {
                                 SettingsTab::Controls => {
                                     setup_controls_tab(parent, &theme, &settings)
                                 }
+                                SettingsTab::Saves => setup_saves_tab(parent, &theme, &settings),
                                 SettingsTab::Developer => {
                                     setup_developer_tab(parent, &theme, &settings)
                                 }
@@ -415,6 +416,36 @@ fn setup_controls_tab(parent: &mut ChildBuilder, theme: &Theme, settings: &Setti
         });
 }
 
+fn setup_saves_tab(parent: &mut ChildBuilder, theme: &Theme, settings: &Settings) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                row_gap: theme.gap.normal,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                CheckboxBundle::new(theme, settings.saves.binary, "Binary world saves"),
+                setting_field!(settings.saves.binary),
+            ));
+            parent.spawn((
+                CheckboxBundle::new(theme, settings.saves.compress, "Compress binary saves"),
+                setting_field!(settings.saves.compress),
+            ));
+            parent.spawn((
+                CheckboxBundle::new(
+                    theme,
+                    settings.saves.lenient_loading,
+                    "Skip broken data when loading",
+                ),
+                setting_field!(settings.saves.lenient_loading),
+            ));
+        });
+}
+
 fn setup_developer_tab(parent: &mut ChildBuilder, theme: &Theme, settings: &Settings) {
     parent
         .spawn(NodeBundle {
@@ -457,6 +488,7 @@ enum SettingsTab {
     #[default]
     Video,
     Controls,
+    Saves,
     Developer,
 }
 
@@ -19,6 +19,7 @@ impl ThemePlugin {
 pub(crate) struct Theme {
     pub(crate) button: ButtonTheme,
     pub(crate) label: LabelTheme,
+    pub(crate) chat: ChatTheme,
     pub(crate) text_edit: TextEditTheme,
     pub(crate) checkbox: CheckboxTheme,
     pub(crate) gap: GapTheme,
@@ -106,6 +107,23 @@ impl FromWorld for Theme {
                     color: Color::rgb(0.1, 0.1, 0.1),
                 },
             },
+            chat: ChatTheme {
+                player_text: TextStyle {
+                    font: text_handle.clone(),
+                    font_size: 25.0,
+                    color: Color::rgb(0.1, 0.1, 0.1),
+                },
+                own_text: TextStyle {
+                    font: text_handle.clone(),
+                    font_size: 25.0,
+                    color: Color::rgb(0.15, 0.45, 0.15),
+                },
+                system_text: TextStyle {
+                    font: text_handle.clone(),
+                    font_size: 25.0,
+                    color: Color::rgb(0.4, 0.4, 0.4),
+                },
+            },
             text_edit: TextEditTheme {
                 style: Style {
                     min_width: Val::Px(200.0),
@@ -183,6 +201,13 @@ pub(crate) struct LabelTheme {
     pub(crate) symbol: TextStyle,
 }
 
+pub(crate) struct ChatTheme {
+    pub(crate) player_text: TextStyle,
+    /// Messages from the local player.
+    pub(crate) own_text: TextStyle,
+    pub(crate) system_text: TextStyle,
+}
+
 pub(crate) struct TextEditTheme {
     pub(crate) style: Style,
     pub(crate) text: TextStyle,
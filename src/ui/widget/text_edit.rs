@@ -26,7 +26,8 @@ impl TextEditPlugin {
         mut text_edits: Query<&mut Text, With<ActiveEdit>>,
     ) {
         if let Ok(mut text) = text_edits.get_single_mut() {
-            for event in &mut char_events {
+            // Control characters like backspace or enter are handled separately.
+            for event in char_events.iter().filter(|event| !event.char.is_control()) {
                 text.sections[0].value.push(event.char);
             }
             if keys.pressed(KeyCode::Back) {
@@ -1,4 +1,9 @@
-use std::{fs, mem, net::Ipv4Addr};
+use std::{
+    fs, mem,
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{Context, Result};
 use bevy::prelude::*;
@@ -10,14 +15,22 @@ use crate::core::{
     error,
     game_paths::GamePaths,
     game_state::GameState,
-    game_world::{GameLoad, GameWorldPlugin, WorldName},
-    network::{self, DEFAULT_PORT},
+    game_world::{
+        self, backup, GameLoad, GameLoadFailed, GameWorldPlugin, WorldMetadata, WorldName,
+    },
+    network::{
+        self,
+        discovery::{self, DiscoveredServer, LanDiscovery},
+        ClientSettings, ServerSettings, DEFAULT_PORT,
+    },
+    settings::Settings,
 };
 
 use super::{
     theme::Theme,
     widget::{
         button::TextButtonBundle,
+        checkbox::{Checkbox, CheckboxBundle},
         click::Click,
         text_edit::{ActiveEdit, TextEditBundle},
         ui_root::UiRoot,
@@ -41,6 +54,15 @@ impl Plugin for WorldBrowserPlugin {
                     Self::world_browser_button_system,
                     Self::create_dialog_button_system,
                     Self::join_dialog_button_system.pipe(error::report),
+                    Self::lan_servers_system.run_if(resource_changed::<LanDiscovery>()),
+                    Self::lan_join_button_system.pipe(error::report),
+                    Self::restore_dialog_system
+                        .pipe(error::report)
+                        .run_if(on_event::<GameLoadFailed>())
+                        .after(GameWorldPlugin::loading_system),
+                    Self::restore_dialog_button_system
+                        .pipe(error::report)
+                        .run_if(any_with_component::<RestoreDialog>()),
                 )
                     .run_if(in_state(GameState::WorldBrowser)),
             );
@@ -82,15 +104,30 @@ impl WorldBrowserPlugin {
                         ..Default::default()
                     })
                     .with_children(|parent| {
-                        let world_names = game_paths
-                            .get_world_names()
+                        let worlds = game_world::read_worlds(&game_paths)
                             .map_err(|e| error!("unable to get world names: {e}"))
                             .unwrap_or_default();
-                        for world_name in world_names {
-                            setup_world_node(parent, &theme, world_name);
+                        for (world_name, metadata) in worlds {
+                            setup_world_node(parent, &theme, world_name, metadata.as_ref());
                         }
                     });
 
+                parent.spawn(LabelBundle::normal(&theme, "LAN servers"));
+                parent.spawn((
+                    LanServersNode,
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            padding: theme.padding.normal,
+                            row_gap: theme.gap.normal,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                ));
+
                 parent
                     .spawn(NodeBundle {
                         style: Style {
@@ -112,6 +149,67 @@ impl WorldBrowserPlugin {
             });
     }
 
+    fn lan_servers_system(
+        mut commands: Commands,
+        theme: Res<Theme>,
+        discovery: Res<LanDiscovery>,
+        nodes: Query<Entity, With<LanServersNode>>,
+    ) {
+        let Ok(node_entity) = nodes.get_single() else {
+            return;
+        };
+
+        commands
+            .entity(node_entity)
+            .despawn_descendants()
+            .with_children(|parent| {
+                if discovery.servers().is_empty() {
+                    parent.spawn(LabelBundle::normal(&theme, "No servers found"));
+                }
+                for server in discovery.servers() {
+                    setup_lan_server_node(parent, &theme, server);
+                }
+            });
+    }
+
+    fn lan_join_button_system(
+        mut commands: Commands,
+        mut click_events: EventReader<Click>,
+        theme: Res<Theme>,
+        network_channels: Res<NetworkChannels>,
+        settings: Res<Settings>,
+        buttons: Query<&LanJoinButton>,
+        roots: Query<Entity, With<UiRoot>>,
+    ) -> Result<()> {
+        for event in &mut click_events {
+            let Ok(&button) = buttons.get(event.0) else {
+                continue;
+            };
+
+            if button.secure {
+                // Let the player pick a token.
+                setup_join_world_dialog(
+                    &mut commands,
+                    roots.single(),
+                    &theme,
+                    button.ip,
+                    button.port,
+                );
+            } else {
+                let client_settings = ClientSettings {
+                    ip: button.ip,
+                    port: button.port,
+                    connect_token: None,
+                    player: settings.player.clone(),
+                    conditions: settings.developer.network.clone(),
+                };
+                network::connect(&mut commands, &network_channels, client_settings)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn world_button_system(
         mut commands: Commands,
         mut load_events: EventWriter<GameLoad>,
@@ -158,32 +256,67 @@ impl WorldBrowserPlugin {
         mut commands: Commands,
         mut load_events: EventWriter<GameLoad>,
         mut click_events: EventReader<Click>,
+        game_paths: Res<GamePaths>,
         network_channels: Res<NetworkChannels>,
         dialogs: Query<(Entity, &WorldNode), With<Dialog>>,
         buttons: Query<&HostDialogButton>,
-        text_edits: Query<&Text, With<PortEdit>>,
-        mut labels: Query<&mut Text, Without<PortEdit>>,
+        text_edits: Query<(&Text, &HostEdit)>,
+        secure_checkboxes: Query<&Checkbox, With<SecureCheckbox>>,
+        mut labels: Query<&mut Text, Without<HostEdit>>,
     ) -> Result<()> {
         for event in &mut click_events {
             if let Ok(&button) = buttons.get(event.0) {
                 let (dialog_entity, world_node) = dialogs.single();
                 if button == HostDialogButton::Host {
-                    let mut world_name = labels
-                        .get_mut(world_node.label_entity)
-                        .expect("world label should contain text");
-                    commands
-                        .insert_resource(WorldName(mem::take(&mut world_name.sections[0].value)));
-                    load_events.send_default();
+                    let mut settings = ServerSettings::default();
+                    for (text, host_edit) in &text_edits {
+                        let value = text.sections[0].value.trim();
+                        match host_edit {
+                            HostEdit::BindIp => {
+                                settings.bind_ip = value
+                                    .parse()
+                                    .with_context(|| format!("invalid bind address {value}"))?;
+                            }
+                            HostEdit::PublicIp if value.is_empty() => settings.public_ip = None,
+                            HostEdit::PublicIp => {
+                                let public_ip = value
+                                    .parse()
+                                    .with_context(|| format!("invalid public address {value}"))?;
+                                settings.public_ip = Some(public_ip);
+                            }
+                            HostEdit::Port => {
+                                settings.port = value
+                                    .parse()
+                                    .with_context(|| format!("invalid port {value}"))?;
+                            }
+                            HostEdit::MaxClients => {
+                                settings.max_clients = value
+                                    .parse()
+                                    .with_context(|| format!("invalid players count {value}"))?;
+                            }
+                        }
+                    }
+                    if secure_checkboxes.single().0 {
+                        settings.private_key =
+                            Some(network::read_private_key(&game_paths.server_key)?);
+                    }
 
-                    let port = text_edits.single();
                     let (server, transport) = network::create_server(
-                        port.sections[0].value.parse()?,
+                        &settings,
                         network_channels.server_channels(),
                         network_channels.client_channels(),
                     )
                     .context("unable to create server")?;
                     commands.insert_resource(server);
                     commands.insert_resource(transport);
+                    discovery::start_responder(&mut commands, &settings);
+
+                    let mut world_name = labels
+                        .get_mut(world_node.label_entity)
+                        .expect("world label should contain text");
+                    commands
+                        .insert_resource(WorldName(mem::take(&mut world_name.sections[0].value)));
+                    load_events.send_default();
                 }
                 commands.entity(dialog_entity).despawn_recursive();
             }
@@ -232,9 +365,13 @@ impl WorldBrowserPlugin {
                     WorldBrowserButton::Create => {
                         setup_create_world_dialog(&mut commands, roots.single(), &theme)
                     }
-                    WorldBrowserButton::Join => {
-                        setup_join_world_dialog(&mut commands, roots.single(), &theme)
-                    }
+                    WorldBrowserButton::Join => setup_join_world_dialog(
+                        &mut commands,
+                        roots.single(),
+                        &theme,
+                        Ipv4Addr::LOCALHOST.into(),
+                        DEFAULT_PORT,
+                    ),
                 }
             }
         }
@@ -265,9 +402,11 @@ impl WorldBrowserPlugin {
         mut commands: Commands,
         mut click_events: EventReader<Click>,
         network_channels: Res<NetworkChannels>,
+        settings: Res<Settings>,
         buttons: Query<&JoinDialogButton>,
         port_edits: Query<&Text, With<PortEdit>>,
         ip_edits: Query<&Text, With<IpEdit>>,
+        token_edits: Query<&Text, With<TokenEdit>>,
         dialogs: Query<Entity, With<Dialog>>,
     ) -> Result<()> {
         for event in &mut click_events {
@@ -276,15 +415,20 @@ impl WorldBrowserPlugin {
                     JoinDialogButton::Join => {
                         let ip = ip_edits.single();
                         let port = port_edits.single();
-                        let (client, transport) = network::create_client(
-                            ip.sections[0].value.parse()?,
-                            port.sections[0].value.parse()?,
-                            network_channels.server_channels(),
-                            network_channels.client_channels(),
-                        )
-                        .context("unable to create connection")?;
-                        commands.insert_resource(client);
-                        commands.insert_resource(transport);
+                        let token_path = token_edits.single().sections[0].value.trim();
+                        let connect_token = if token_path.is_empty() {
+                            None
+                        } else {
+                            Some(network::read_token(Path::new(token_path))?)
+                        };
+                        let client_settings = ClientSettings {
+                            ip: ip.sections[0].value.parse()?,
+                            port: port.sections[0].value.parse()?,
+                            connect_token,
+                            player: settings.player.clone(),
+                            conditions: settings.developer.network.clone(),
+                        };
+                        network::connect(&mut commands, &network_channels, client_settings)?;
                     }
                     JoinDialogButton::Cancel => {
                         commands.entity(dialogs.single()).despawn_recursive()
@@ -295,9 +439,59 @@ impl WorldBrowserPlugin {
 
         Ok(())
     }
+
+    /// Offers to restore the world from a backup if it failed to load.
+    fn restore_dialog_system(
+        mut commands: Commands,
+        theme: Res<Theme>,
+        game_paths: Res<GamePaths>,
+        world_name: Res<WorldName>,
+        roots: Query<Entity, With<UiRoot>>,
+    ) -> Result<()> {
+        let backups = game_paths.get_backups(&world_name.0)?;
+        if !backups.is_empty() {
+            setup_restore_world_dialog(
+                &mut commands,
+                roots.single(),
+                &theme,
+                &world_name.0,
+                backups,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn restore_dialog_button_system(
+        mut commands: Commands,
+        mut load_events: EventWriter<GameLoad>,
+        mut click_events: EventReader<Click>,
+        game_paths: Res<GamePaths>,
+        world_name: Res<WorldName>,
+        backup_buttons: Query<&BackupButton>,
+        cancel_buttons: Query<(), With<RestoreCancelButton>>,
+        dialogs: Query<Entity, With<RestoreDialog>>,
+    ) -> Result<()> {
+        for event in &mut click_events {
+            if let Ok(backup_button) = backup_buttons.get(event.0) {
+                commands.entity(dialogs.single()).despawn_recursive();
+                backup::restore(&game_paths, &world_name.0, &backup_button.0)?;
+                load_events.send_default();
+            } else if cancel_buttons.get(event.0).is_ok() {
+                commands.entity(dialogs.single()).despawn_recursive();
+            }
+        }
+
+        Ok(())
+    }
 }
 
-fn setup_world_node(parent: &mut ChildBuilder, theme: &Theme, label: impl Into<String>) {
+fn setup_world_node(
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    label: impl Into<String>,
+    metadata: Option<&WorldMetadata>,
+) {
     parent
         .spawn(NodeBundle {
             style: Style {
@@ -316,11 +510,18 @@ fn setup_world_node(parent: &mut ChildBuilder, theme: &Theme, label: impl Into<S
                     style: Style {
                         width: Val::Percent(100.0),
                         height: Val::Percent(100.0),
+                        flex_direction: FlexDirection::Column,
+                        row_gap: theme.gap.normal,
                         ..Default::default()
                     },
                     ..Default::default()
                 })
-                .add_child(label_entity);
+                .add_child(label_entity)
+                .with_children(|parent| {
+                    if let Some(metadata) = metadata {
+                        parent.spawn(LabelBundle::normal(theme, describe_world(metadata)));
+                    }
+                });
             parent
                 .spawn(NodeBundle {
                     style: Style {
@@ -345,6 +546,71 @@ fn setup_world_node(parent: &mut ChildBuilder, theme: &Theme, label: impl Into<S
         });
 }
 
+fn describe_world(metadata: &WorldMetadata) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "Saved {} ago, played {}\n{} cities, {} families, {} actors\nVersion {}",
+        format_duration(now.saturating_sub(metadata.saved_at)),
+        format_duration(metadata.play_time),
+        metadata.cities,
+        metadata.families,
+        metadata.actors,
+        metadata.game_version,
+    )
+}
+
+/// Formats duration with two most significant units.
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+    if days > 0 {
+        format!("{days}d {}h", hours % 24)
+    } else if hours > 0 {
+        format!("{hours}h {}m", minutes % 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+fn setup_lan_server_node(parent: &mut ChildBuilder, theme: &Theme, server: &DiscoveredServer) {
+    let info = &server.info;
+    let mut description = format!(
+        "{} at {}:{}\n{}/{} players",
+        info.world_name, server.ip, info.port, info.players, info.max_players
+    );
+    if info.secure {
+        description.push_str(", token required");
+    }
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(50.0),
+                padding: theme.padding.normal,
+                column_gap: theme.gap.normal,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceBetween,
+                ..Default::default()
+            },
+            background_color: theme.panel_color.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn(LabelBundle::normal(theme, description));
+            parent.spawn((
+                LanJoinButton {
+                    ip: server.ip,
+                    port: info.port,
+                    secure: info.secure,
+                },
+                TextButtonBundle::normal(theme, "Join"),
+            ));
+        });
+}
+
 fn setup_host_world_dialog(
     commands: &mut Commands,
     root_entity: Entity,
@@ -375,20 +641,33 @@ fn setup_host_world_dialog(
                         parent
                             .spawn(NodeBundle {
                                 style: Style {
+                                    display: Display::Grid,
                                     column_gap: theme.gap.normal,
-                                    justify_content: JustifyContent::Center,
+                                    row_gap: theme.gap.normal,
+                                    grid_template_columns: vec![GridTrack::auto(); 2],
                                     ..Default::default()
                                 },
                                 ..Default::default()
                             })
                             .with_children(|parent| {
-                                parent.spawn(LabelBundle::normal(theme, "Port:"));
-                                parent.spawn((
-                                    PortEdit,
-                                    TextEditBundle::new(theme, DEFAULT_PORT.to_string()),
-                                ));
+                                let settings = ServerSettings::default();
+                                for host_edit in HostEdit::iter() {
+                                    let value = match host_edit {
+                                        HostEdit::BindIp => settings.bind_ip.to_string(),
+                                        HostEdit::PublicIp => String::new(),
+                                        HostEdit::Port => settings.port.to_string(),
+                                        HostEdit::MaxClients => settings.max_clients.to_string(),
+                                    };
+                                    parent.spawn(LabelBundle::normal(theme, host_edit.label()));
+                                    parent.spawn((host_edit, TextEditBundle::new(theme, value)));
+                                }
                             });
 
+                        parent.spawn((
+                            SecureCheckbox,
+                            CheckboxBundle::new(theme, false, "Only players with tokens"),
+                        ));
+
                         parent
                             .spawn(NodeBundle {
                                 style: Style {
@@ -507,7 +786,13 @@ fn setup_create_world_dialog(commands: &mut Commands, root_entity: Entity, theme
     });
 }
 
-fn setup_join_world_dialog(commands: &mut Commands, root_entity: Entity, theme: &Theme) {
+fn setup_join_world_dialog(
+    commands: &mut Commands,
+    root_entity: Entity,
+    theme: &Theme,
+    ip: IpAddr,
+    port: u16,
+) {
     commands.entity(root_entity).with_children(|parent| {
         parent
             .spawn(DialogBundle::new(theme))
@@ -541,16 +826,16 @@ fn setup_join_world_dialog(commands: &mut Commands, root_entity: Entity, theme:
                             })
                             .with_children(|parent| {
                                 parent.spawn(LabelBundle::normal(theme, "IP:"));
-                                parent.spawn((
-                                    IpEdit,
-                                    TextEditBundle::new(theme, Ipv4Addr::LOCALHOST.to_string()),
-                                ));
+                                parent.spawn((IpEdit, TextEditBundle::new(theme, ip.to_string())));
 
                                 parent.spawn(LabelBundle::normal(theme, "Port:"));
                                 parent.spawn((
                                     PortEdit,
-                                    TextEditBundle::new(theme, DEFAULT_PORT.to_string()),
+                                    TextEditBundle::new(theme, port.to_string()),
                                 ));
+
+                                parent.spawn(LabelBundle::normal(theme, "Token file:"));
+                                parent.spawn((TokenEdit, TextEditBundle::empty(theme)));
                             });
 
                         parent
@@ -574,6 +859,64 @@ fn setup_join_world_dialog(commands: &mut Commands, root_entity: Entity, theme:
     });
 }
 
+fn setup_restore_world_dialog(
+    commands: &mut Commands,
+    root_entity: Entity,
+    theme: &Theme,
+    world_name: &str,
+    backups: Vec<PathBuf>,
+) {
+    commands.entity(root_entity).with_children(|parent| {
+        parent
+            .spawn((RestoreDialog, DialogBundle::new(theme)))
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: theme.padding.normal,
+                            row_gap: theme.gap.normal,
+                            ..Default::default()
+                        },
+                        background_color: theme.panel_color.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(LabelBundle::normal(
+                            theme,
+                            format!("Unable to load world {world_name}. Restore it from a backup?"),
+                        ));
+
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    column_gap: theme.gap.normal,
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                for (index, backup_path) in backups.into_iter().enumerate() {
+                                    parent.spawn((
+                                        BackupButton(backup_path),
+                                        TextButtonBundle::normal(
+                                            theme,
+                                            format!("Backup {}", index + 1),
+                                        ),
+                                    ));
+                                }
+                                parent.spawn((
+                                    RestoreCancelButton,
+                                    TextButtonBundle::normal(theme, "Cancel"),
+                                ));
+                            });
+                    });
+            });
+    });
+}
+
 #[derive(Component, EnumIter, Clone, Copy, Display)]
 enum WorldButton {
     Play,
@@ -594,6 +937,18 @@ struct WorldNode {
     node_entity: Entity,
 }
 
+/// Container for servers from [`LanDiscovery`].
+#[derive(Component)]
+struct LanServersNode;
+
+/// Connects to a discovered server.
+#[derive(Clone, Component, Copy)]
+struct LanJoinButton {
+    ip: IpAddr,
+    port: u16,
+    secure: bool,
+}
+
 #[derive(Component, EnumIter, Clone, Copy, Display)]
 enum WorldBrowserButton {
     Create,
@@ -615,6 +970,33 @@ struct PortEdit;
 #[derive(Component)]
 struct IpEdit;
 
+/// Path to a connect token for servers that accept only players with tokens.
+#[derive(Component)]
+struct TokenEdit;
+
+#[derive(Component)]
+struct SecureCheckbox;
+
+/// Text edit with a server parameter in the host dialog.
+#[derive(Component, EnumIter, Clone, Copy)]
+enum HostEdit {
+    BindIp,
+    PublicIp,
+    Port,
+    MaxClients,
+}
+
+impl HostEdit {
+    fn label(self) -> &'static str {
+        match self {
+            HostEdit::BindIp => "Bind IP:",
+            HostEdit::PublicIp => "Public IP:",
+            HostEdit::Port => "Port:",
+            HostEdit::MaxClients => "Max players:",
+        }
+    }
+}
+
 #[derive(Component, EnumIter, Clone, Copy, Display, PartialEq)]
 enum HostDialogButton {
     Host,
@@ -626,3 +1008,13 @@ enum JoinDialogButton {
     Join,
     Cancel,
 }
+
+#[derive(Component)]
+struct RestoreDialog;
+
+/// Restores world from the stored backup path.
+#[derive(Component)]
+struct BackupButton(PathBuf);
+
+#[derive(Component)]
+struct RestoreCancelButton;
@@ -17,7 +17,11 @@ use super::{
 use crate::core::{
     actor::ActiveActor,
     city::{ActiveCity, City, CityBundle},
-    family::{Family, FamilyDespawn, FamilyMembers},
+    family::{
+        family_spawn::{FamilyScene, FamilySpawn},
+        Family, FamilyDespawn, FamilyExport, FamilyMembers,
+    },
+    game_paths::GamePaths,
     game_state::GameState,
     game_world::WorldName,
 };
@@ -36,6 +40,8 @@ impl Plugin for WorldMenuPlugin {
                     Self::city_button_system,
                     Self::create_button_system,
                     Self::city_dialog_button_system,
+                    Self::import_button_system,
+                    Self::import_dialog_button_system,
                     Self::entity_node_despawn_system,
                 )
                     .run_if(in_state(GameState::World)),
@@ -135,6 +141,10 @@ impl WorldMenuPlugin {
                             CreateEntityButton,
                             TextButtonBundle::normal(&theme, "Create new"),
                         ));
+                        parent.spawn((
+                            ImportFamilyButton,
+                            TextButtonBundle::normal(&theme, "Import family"),
+                        ));
                     });
             });
     }
@@ -182,6 +192,7 @@ impl WorldMenuPlugin {
     fn family_button_system(
         mut commands: Commands,
         mut despawn_events: EventWriter<FamilyDespawn>,
+        mut export_events: EventWriter<FamilyExport>,
         mut click_events: EventReader<Click>,
         mut game_state: ResMut<NextState<GameState>>,
         buttons: Query<(&WorldEntityNode, &FamilyButton)>,
@@ -205,6 +216,7 @@ impl WorldMenuPlugin {
                         commands.entity(actor_entity).insert(ActiveActor);
                         game_state.set(GameState::Family);
                     }
+                    FamilyButton::Export => export_events.send(FamilyExport(world_entity.0)),
                     FamilyButton::Delete => despawn_events.send(FamilyDespawn(world_entity.0)),
                 }
             }
@@ -281,6 +293,60 @@ impl WorldMenuPlugin {
         }
     }
 
+    fn import_button_system(
+        mut commands: Commands,
+        mut click_events: EventReader<Click>,
+        theme: Res<Theme>,
+        game_paths: Res<GamePaths>,
+        buttons: Query<(), With<ImportFamilyButton>>,
+        cities: Query<(Entity, &Name), With<City>>,
+        roots: Query<Entity, With<UiRoot>>,
+    ) {
+        for event in &mut click_events {
+            if buttons.get(event.0).is_ok() {
+                let family_names = game_paths
+                    .get_family_names()
+                    .map_err(|e| error!("unable to read families: {e}"))
+                    .unwrap_or_default();
+                setup_import_family_dialog(
+                    &mut commands,
+                    roots.single(),
+                    &theme,
+                    &family_names,
+                    &cities,
+                );
+            }
+        }
+    }
+
+    fn import_dialog_button_system(
+        mut commands: Commands,
+        mut spawn_events: EventWriter<FamilySpawn>,
+        mut click_events: EventReader<Click>,
+        registry: Res<AppTypeRegistry>,
+        game_paths: Res<GamePaths>,
+        import_buttons: Query<&ImportPlaceButton>,
+        dialog_buttons: Query<(), With<ImportCancelButton>>,
+        dialogs: Query<Entity, With<Dialog>>,
+    ) {
+        for event in &mut click_events {
+            if let Ok(import_button) = import_buttons.get(event.0) {
+                let family_path = game_paths.family_path(&import_button.family_name);
+                match FamilyScene::read(&family_path, &registry.read()) {
+                    Ok(scene) => spawn_events.send(FamilySpawn {
+                        city_entity: import_button.city_entity,
+                        scene,
+                        select: false,
+                    }),
+                    Err(e) => error!("unable to import family: {e:#}"),
+                }
+                commands.entity(dialogs.single()).despawn_recursive();
+            } else if dialog_buttons.get(event.0).is_ok() {
+                commands.entity(dialogs.single()).despawn_recursive();
+            }
+        }
+    }
+
     fn entity_node_despawn_system(
         mut commands: Commands,
         mut removed_cities: RemovedComponents<City>,
@@ -400,6 +466,70 @@ fn setup_create_city_dialog(commands: &mut Commands, root_entity: Entity, theme:
     });
 }
 
+fn setup_import_family_dialog(
+    commands: &mut Commands,
+    root_entity: Entity,
+    theme: &Theme,
+    family_names: &[String],
+    cities: &Query<(Entity, &Name), With<City>>,
+) {
+    commands.entity(root_entity).with_children(|parent| {
+        parent
+            .spawn(DialogBundle::new(theme))
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: theme.padding.normal,
+                            row_gap: theme.gap.normal,
+                            ..Default::default()
+                        },
+                        background_color: theme.panel_color.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(LabelBundle::normal(theme, "Import family"));
+                        if family_names.is_empty() {
+                            parent.spawn(LabelBundle::normal(theme, "No exported families"));
+                        }
+                        for family_name in family_names {
+                            parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        align_items: AlignItems::Center,
+                                        column_gap: theme.gap.normal,
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn(LabelBundle::normal(theme, family_name.clone()));
+                                    for (city_entity, city_name) in cities {
+                                        parent.spawn((
+                                            ImportPlaceButton {
+                                                family_name: family_name.clone(),
+                                                city_entity,
+                                            },
+                                            TextButtonBundle::normal(
+                                                theme,
+                                                format!("Place in {city_name}"),
+                                            ),
+                                        ));
+                                    }
+                                });
+                        }
+                        parent.spawn((
+                            ImportCancelButton,
+                            TextButtonBundle::normal(theme, "Cancel"),
+                        ));
+                    });
+            });
+    });
+}
+
 #[derive(Clone, Component, Copy, Default, Display, EnumIter, PartialEq)]
 enum WorldTab {
     #[default]
@@ -410,6 +540,7 @@ enum WorldTab {
 #[derive(Component, EnumIter, Clone, Copy, Display)]
 enum FamilyButton {
     Play,
+    Export,
     Delete,
 }
 
@@ -431,6 +562,20 @@ struct WorldEntityNode(Entity);
 #[derive(Component)]
 struct CreateEntityButton;
 
+/// Opens a dialog to import a family from [`GamePaths::families`].
+#[derive(Component)]
+struct ImportFamilyButton;
+
+/// Places a family from [`GamePaths::families`] into the city.
+#[derive(Component)]
+struct ImportPlaceButton {
+    family_name: String,
+    city_entity: Entity,
+}
+
+#[derive(Component)]
+struct ImportCancelButton;
+
 #[derive(Component, EnumIter, Clone, Copy, Display)]
 enum CityDialogButton {
     Create,